@@ -1,11 +1,26 @@
 use crate::*;
 
+/// Kind of an account.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum AccountKind {
+    /// Account controlled by a user, which can move to other shards.
+    ExternallyOwned,
+    /// Contract account, which is pinned to the shard where it is deployed.
+    Contract,
+}
+
 /// Account.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Account {
     pub addr: Address,
     pub shard_id: usize,
+    pub kind: AccountKind,
     pub balance: Wei,
+    /// Contract storage (key -> value). Empty for externally owned accounts.
+    pub storage: HashMap<u64, Wei>,
+    /// Token balances of this account (token contract address -> balance).
+    /// They are kept in the account so that they move with the account.
+    pub token_balances: HashMap<Address, Wei>,
 }
 
 impl Account {
@@ -13,9 +28,35 @@ impl Account {
         Self {
             addr,
             shard_id,
+            kind: AccountKind::ExternallyOwned,
             balance: Wei::MAX / 10,
+            storage: HashMap::new(),
+            token_balances: HashMap::new(),
         }
     }
+
+    /// Contract account with `storage_size` storage slots.
+    pub fn new_contract(addr: Address, shard_id: usize, storage_size: usize) -> Self {
+        let mut account = Self::new(addr, shard_id);
+        account.kind = AccountKind::Contract;
+        account.storage = (0..storage_size as u64).map(|key| (key, 0)).collect();
+        account
+    }
+
+    pub fn is_contract(&self) -> bool {
+        self.kind == AccountKind::Contract
+    }
+
+    /// Size in bytes of the account in the shard state.
+    pub fn state_size(&self) -> u64 {
+        ACCOUNT_STATE_SIZE
+            + self.storage.len() as u64 * STORAGE_SLOT_STATE_SIZE
+            + self.token_balances.len() as u64 * TOKEN_BALANCE_STATE_SIZE
+    }
+
+    pub fn get_token_balance(&self, token: Address) -> Wei {
+        *self.token_balances.get(&token).unwrap_or(&0)
+    }
 }
 
 impl Hash for Account {
@@ -0,0 +1,39 @@
+use crate::*;
+
+/// Rule to couple the base fees of shards, which are otherwise updated independently.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BaseFeeCoupling {
+    /// Each shard updates its base fee from its own gas used.
+    #[default]
+    Independent,
+    /// The base fee is the sum of a global component, updated from the total gas used of all shards,
+    /// and a local component, updated from the gas used of the shard.
+    Global,
+    /// The base fee is smoothed toward the mean of the base fees of the neighbor shards
+    /// (shard_id - 1 and shard_id + 1 in a ring).
+    NeighborSmoothing,
+}
+
+impl BaseFeeCoupling {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BaseFeeCoupling::Independent => "independent",
+            BaseFeeCoupling::Global => "global",
+            BaseFeeCoupling::NeighborSmoothing => "neighbor_smoothing",
+        }
+    }
+}
+
+impl std::str::FromStr for BaseFeeCoupling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(BaseFeeCoupling::Independent),
+            "global" => Ok(BaseFeeCoupling::Global),
+            "neighbor_smoothing" => Ok(BaseFeeCoupling::NeighborSmoothing),
+            _ => Err(format!("unknown base fee coupling: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::*;
+
+/// What happens to the other functions of a transaction when one of them fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchSemantics {
+    /// Execution stops at the failed function, and the state transitions of the transaction are reverted.
+    #[default]
+    AllOrNothing,
+    /// Every function is executed, and the successful ones are kept.
+    BestEffort,
+}
+
+impl BatchSemantics {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchSemantics::AllOrNothing => "all_or_nothing",
+            BatchSemantics::BestEffort => "best_effort",
+        }
+    }
+}
+
+impl std::str::FromStr for BatchSemantics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all_or_nothing" => Ok(BatchSemantics::AllOrNothing),
+            "best_effort" => Ok(BatchSemantics::BestEffort),
+            _ => Err(format!("unknown batch semantics: {}", s)),
+        }
+    }
+}
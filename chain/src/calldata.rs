@@ -0,0 +1,174 @@
+use crate::*;
+
+/// Typed arguments of a function, encoded in `Function::calldata` as comma-separated values.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Calldata {
+    /// No arguments (ETH transfers).
+    Empty,
+    /// The target shard of a moving or yanked account, or the source shard of an applied move.
+    ShardId(usize),
+    /// The token contract and the amount of tokens.
+    Token { token: Address, amount: Wei },
+    /// The called contract and the shard to which the result returns.
+    Call {
+        contract: Address,
+        origin_shard_id: usize,
+    },
+    /// The result of a call and the shard of the callback.
+    CallResult { origin_shard_id: usize, result: Wei },
+    /// The locked amount and the last slot in which the transfer can be committed.
+    Lock { amount: Wei, deadline: Slot },
+    /// Whether or not the destination shard accepts the locked transfer.
+    Vote {
+        lock_hash: TransactionHash,
+        commit: bool,
+    },
+    /// Whether the locked transfer was committed or refunded.
+    Decision { commit: bool, amount: Wei },
+    /// The rollup and the size of the batch in bytes, excluding the header.
+    Batch { rollup_id: usize, size: Gas },
+}
+
+impl Calldata {
+    pub fn encode(&self) -> String {
+        match self {
+            Calldata::Empty => String::new(),
+            Calldata::ShardId(shard_id) => shard_id.to_string(),
+            Calldata::Token { token, amount } => format!("{},{}", token, amount),
+            Calldata::Call {
+                contract,
+                origin_shard_id,
+            } => format!("{},{}", contract, origin_shard_id),
+            Calldata::CallResult {
+                origin_shard_id,
+                result,
+            } => format!("{},{}", origin_shard_id, result),
+            Calldata::Lock { amount, deadline } => format!("{},{}", amount, deadline),
+            Calldata::Vote { lock_hash, commit } => format!("{},{}", lock_hash, commit),
+            Calldata::Decision { commit, amount } => format!("{},{}", commit, amount),
+            Calldata::Batch { rollup_id, size } => format!("{},{}", rollup_id, size),
+        }
+    }
+
+    /// Parse the calldata of a function of the type.
+    pub fn parse(ftype: &FunctionType, calldata: &str) -> Result<Self, String> {
+        let values = if calldata.is_empty() {
+            Vec::new()
+        } else {
+            calldata.split(',').collect::<Vec<_>>()
+        };
+        match ftype {
+            FunctionType::Transfer
+            | FunctionType::CreateCrossTransfer
+            | FunctionType::ApplyCrossTransfer => Ok(Calldata::Empty),
+            FunctionType::PullAccount => Ok(Calldata::Empty),
+            FunctionType::PostBatch => match values.as_slice() {
+                [rollup_id, size] => Ok(Calldata::Batch {
+                    rollup_id: rollup_id
+                        .parse()
+                        .map_err(|e| format!("invalid rollup id {}: {}", rollup_id, e))?,
+                    size: size
+                        .parse()
+                        .map_err(|e| format!("invalid size {}: {}", size, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::CreateCrossTransferAll
+            | FunctionType::ApplyCrossTransferAll
+            | FunctionType::YankAccount => match values.as_slice() {
+                [] => Ok(Calldata::Empty),
+                [shard_id] => shard_id
+                    .parse()
+                    .map(Calldata::ShardId)
+                    .map_err(|e| format!("invalid shard id {}: {}", shard_id, e)),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::Mint
+            | FunctionType::TokenTransfer
+            | FunctionType::CreateCrossTokenTransfer
+            | FunctionType::ApplyCrossTokenTransfer => match values.as_slice() {
+                [token, amount] => Ok(Calldata::Token {
+                    token: token
+                        .parse()
+                        .map_err(|e| format!("invalid token {}: {}", token, e))?,
+                    amount: amount
+                        .parse()
+                        .map_err(|e| format!("invalid amount {}: {}", amount, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::CreateCrossCall | FunctionType::ApplyCrossCall => {
+                match values.as_slice() {
+                    [contract, origin_shard_id] => Ok(Calldata::Call {
+                        contract: contract
+                            .parse()
+                            .map_err(|e| format!("invalid contract {}: {}", contract, e))?,
+                        origin_shard_id: origin_shard_id
+                            .parse()
+                            .map_err(|e| format!("invalid shard id {}: {}", origin_shard_id, e))?,
+                    }),
+                    _ => Err(format!("invalid calldata: {}", calldata)),
+                }
+            }
+            FunctionType::ApplyCallback => match values.as_slice() {
+                [origin_shard_id, result] => Ok(Calldata::CallResult {
+                    origin_shard_id: origin_shard_id
+                        .parse()
+                        .map_err(|e| format!("invalid shard id {}: {}", origin_shard_id, e))?,
+                    result: result
+                        .parse()
+                        .map_err(|e| format!("invalid result {}: {}", result, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::LockCrossTransfer | FunctionType::ConfirmCrossTransfer => {
+                match values.as_slice() {
+                    [amount, deadline] => Ok(Calldata::Lock {
+                        amount: amount
+                            .parse()
+                            .map_err(|e| format!("invalid amount {}: {}", amount, e))?,
+                        deadline: deadline
+                            .parse()
+                            .map_err(|e| format!("invalid deadline {}: {}", deadline, e))?,
+                    }),
+                    _ => Err(format!("invalid calldata: {}", calldata)),
+                }
+            }
+            FunctionType::CommitCrossTransfer => match values.as_slice() {
+                [lock_hash, commit] => Ok(Calldata::Vote {
+                    lock_hash: lock_hash
+                        .parse()
+                        .map_err(|e| format!("invalid lock hash {}: {}", lock_hash, e))?,
+                    commit: commit
+                        .parse()
+                        .map_err(|e| format!("invalid vote {}: {}", commit, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::AbortCrossTransfer => Ok(Calldata::Empty),
+            FunctionType::ReclaimCrossTransfer => match values.as_slice() {
+                [] => Ok(Calldata::Empty),
+                [token, amount] => Ok(Calldata::Token {
+                    token: token
+                        .parse()
+                        .map_err(|e| format!("invalid token {}: {}", token, e))?,
+                    amount: amount
+                        .parse()
+                        .map_err(|e| format!("invalid amount {}: {}", amount, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+            FunctionType::FinalizeCrossTransfer => match values.as_slice() {
+                [commit, amount] => Ok(Calldata::Decision {
+                    commit: commit
+                        .parse()
+                        .map_err(|e| format!("invalid decision {}: {}", commit, e))?,
+                    amount: amount
+                        .parse()
+                        .map_err(|e| format!("invalid amount {}: {}", amount, e))?,
+                }),
+                _ => Err(format!("invalid calldata: {}", calldata)),
+            },
+        }
+    }
+}
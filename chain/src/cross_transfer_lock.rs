@@ -0,0 +1,20 @@
+use crate::*;
+
+/// Funds locked on the source shard by a two-phase cross-shard transfer.
+#[derive(Clone, Debug)]
+pub struct CrossTransferLock {
+    pub owner: Address,
+    pub amount: Wei,
+    /// The last slot in which the transfer can be committed.
+    pub deadline: Slot,
+}
+
+impl CrossTransferLock {
+    pub fn new(owner: Address, amount: Wei, deadline: Slot) -> Self {
+        Self {
+            owner,
+            amount,
+            deadline,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::*;
+
+/// Transaction fee mechanism of shards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeMarket {
+    /// EIP-1559 with a single base fee for all gas.
+    #[default]
+    OneDimensional,
+    /// EIP-1559 with separate limits and base fees for execution gas,
+    /// outgoing cross-shard receipts and incoming cross-shard receipts.
+    MultiDimensional,
+}
+
+impl FeeMarket {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeeMarket::OneDimensional => "one_dimensional",
+            FeeMarket::MultiDimensional => "multi_dimensional",
+        }
+    }
+}
+
+impl std::str::FromStr for FeeMarket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one_dimensional" => Ok(FeeMarket::OneDimensional),
+            "multi_dimensional" => Ok(FeeMarket::MultiDimensional),
+            _ => Err(format!("unknown fee market: {}", s)),
+        }
+    }
+}
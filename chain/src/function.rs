@@ -13,6 +13,38 @@ pub enum FunctionType {
     ApplyCrossTransfer,
     CreateCrossTransferAll,
     ApplyCrossTransferAll,
+    /// Mint tokens on the home shard of the token contract.
+    Mint,
+    /// Local token transfer.
+    TokenTransfer,
+    /// Burn tokens and initiate transfer of them to a different shard.
+    CreateCrossTokenTransfer,
+    /// Process an incoming token transfer.
+    ApplyCrossTokenTransfer,
+    /// Send a message to call a contract on a different shard.
+    CreateCrossCall,
+    /// Process an incoming message, call the contract and return the result to the origin shard.
+    ApplyCrossCall,
+    /// Process an incoming result of a call, which triggers the callback.
+    ApplyCallback,
+    /// Lock funds for a two-phase cross-shard transfer.
+    LockCrossTransfer,
+    /// Process an incoming lock and vote to commit or abort the transfer.
+    ConfirmCrossTransfer,
+    /// Process an incoming vote and commit the locked funds, or refund them.
+    CommitCrossTransfer,
+    /// Refund the locked funds of a transfer whose deadline has passed.
+    AbortCrossTransfer,
+    /// Process an incoming commit and receive the funds.
+    FinalizeCrossTransfer,
+    /// Reclaim an expired receipt of a cross-shard transfer that was never applied.
+    ReclaimCrossTransfer,
+    /// Lock an account on its shard so that it can be pulled into another shard.
+    YankAccount,
+    /// Pull a yanked account into the shard, which anyone can do.
+    PullAccount,
+    /// Post a batch of a rollup to a data shard.
+    PostBatch,
 }
 
 /// Unit of operation in a transaction.
@@ -22,18 +54,168 @@ pub struct Function {
     pub source: Address,
     pub target: Address,
     pub ftype: FunctionType,
-    pub calldata: String, // TODO
+    /// Encoded `Calldata`.
+    pub calldata: String,
 }
 
-impl Function {
-    /// Calculate the gas of a function.
+impl FunctionType {
+    /// The number of function types.
+    pub const NUM: usize = 21;
+
+    /// All the function types, in the order of their discriminants.
+    pub const ALL: [FunctionType; FunctionType::NUM] = [
+        FunctionType::Transfer,
+        FunctionType::CreateCrossTransfer,
+        FunctionType::ApplyCrossTransfer,
+        FunctionType::CreateCrossTransferAll,
+        FunctionType::ApplyCrossTransferAll,
+        FunctionType::Mint,
+        FunctionType::TokenTransfer,
+        FunctionType::CreateCrossTokenTransfer,
+        FunctionType::ApplyCrossTokenTransfer,
+        FunctionType::CreateCrossCall,
+        FunctionType::ApplyCrossCall,
+        FunctionType::ApplyCallback,
+        FunctionType::LockCrossTransfer,
+        FunctionType::ConfirmCrossTransfer,
+        FunctionType::CommitCrossTransfer,
+        FunctionType::AbortCrossTransfer,
+        FunctionType::FinalizeCrossTransfer,
+        FunctionType::ReclaimCrossTransfer,
+        FunctionType::YankAccount,
+        FunctionType::PullAccount,
+        FunctionType::PostBatch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FunctionType::Transfer => "transfer",
+            FunctionType::CreateCrossTransfer => "create_cross_transfer",
+            FunctionType::ApplyCrossTransfer => "apply_cross_transfer",
+            FunctionType::CreateCrossTransferAll => "create_cross_transfer_all",
+            FunctionType::ApplyCrossTransferAll => "apply_cross_transfer_all",
+            FunctionType::Mint => "mint",
+            FunctionType::TokenTransfer => "token_transfer",
+            FunctionType::CreateCrossTokenTransfer => "create_cross_token_transfer",
+            FunctionType::ApplyCrossTokenTransfer => "apply_cross_token_transfer",
+            FunctionType::CreateCrossCall => "create_cross_call",
+            FunctionType::ApplyCrossCall => "apply_cross_call",
+            FunctionType::ApplyCallback => "apply_callback",
+            FunctionType::LockCrossTransfer => "lock_cross_transfer",
+            FunctionType::ConfirmCrossTransfer => "confirm_cross_transfer",
+            FunctionType::CommitCrossTransfer => "commit_cross_transfer",
+            FunctionType::AbortCrossTransfer => "abort_cross_transfer",
+            FunctionType::FinalizeCrossTransfer => "finalize_cross_transfer",
+            FunctionType::ReclaimCrossTransfer => "reclaim_cross_transfer",
+            FunctionType::YankAccount => "yank_account",
+            FunctionType::PullAccount => "pull_account",
+            FunctionType::PostBatch => "post_batch",
+        }
+    }
+
+    /// Calculate the gas of a function of this type.
     pub fn gas(&self) -> Gas {
-        match self.ftype {
+        match self {
             FunctionType::Transfer => GAS_TRANSFER,
             FunctionType::CreateCrossTransfer => GAS_CREATE_CROSS_TRANSFER,
             FunctionType::ApplyCrossTransfer => GAS_APPLY_CROSS_TRANSFER,
             FunctionType::CreateCrossTransferAll => GAS_CREATE_CROSS_TRANSFER_ALL,
             FunctionType::ApplyCrossTransferAll => GAS_APPLY_CROSS_TRANSFER_ALL,
+            FunctionType::Mint => GAS_MINT,
+            FunctionType::TokenTransfer => GAS_TOKEN_TRANSFER,
+            FunctionType::CreateCrossTokenTransfer => GAS_CREATE_CROSS_TOKEN_TRANSFER,
+            FunctionType::ApplyCrossTokenTransfer => GAS_APPLY_CROSS_TOKEN_TRANSFER,
+            FunctionType::CreateCrossCall => GAS_CREATE_CROSS_CALL,
+            FunctionType::ApplyCrossCall => GAS_APPLY_CROSS_CALL,
+            FunctionType::ApplyCallback => GAS_APPLY_CALLBACK,
+            FunctionType::LockCrossTransfer => GAS_LOCK_CROSS_TRANSFER,
+            FunctionType::ConfirmCrossTransfer => GAS_CONFIRM_CROSS_TRANSFER,
+            FunctionType::CommitCrossTransfer => GAS_COMMIT_CROSS_TRANSFER,
+            FunctionType::AbortCrossTransfer => GAS_ABORT_CROSS_TRANSFER,
+            FunctionType::FinalizeCrossTransfer => GAS_FINALIZE_CROSS_TRANSFER,
+            FunctionType::ReclaimCrossTransfer => GAS_RECLAIM_CROSS_TRANSFER,
+            FunctionType::YankAccount => GAS_YANK_ACCOUNT,
+            FunctionType::PullAccount => GAS_PULL_ACCOUNT,
+            // Plus the size of the batch
+            FunctionType::PostBatch => BATCH_HEADER_SIZE,
+        }
+    }
+
+    /// Calculate the gas per resource of the multidimensional fee market.
+    pub fn gas_by_resource(&self) -> Resources {
+        match self {
+            FunctionType::Transfer => Resources::new(GAS_TRANSFER, 0, 0),
+            FunctionType::CreateCrossTransfer => {
+                Resources::new(GAS_TRANSFER, GAS_RECEIPT_OUT_CROSS_TRANSFER, 0)
+            }
+            FunctionType::ApplyCrossTransfer => {
+                Resources::new(GAS_TRANSFER, 0, GAS_RECEIPT_IN_CROSS_TRANSFER)
+            }
+            FunctionType::CreateCrossTransferAll | FunctionType::YankAccount => {
+                Resources::new(GAS_TRANSFER, GAS_RECEIPT_OUT_CROSS_TRANSFER_ALL, 0)
+            }
+            FunctionType::ApplyCrossTransferAll | FunctionType::PullAccount => {
+                Resources::new(GAS_TRANSFER, 0, GAS_RECEIPT_IN_CROSS_TRANSFER_ALL)
+            }
+            FunctionType::Mint => Resources::new(GAS_MINT, 0, 0),
+            FunctionType::TokenTransfer => Resources::new(GAS_TOKEN_TRANSFER, 0, 0),
+            FunctionType::CreateCrossTokenTransfer => {
+                Resources::new(GAS_TOKEN_TRANSFER, GAS_RECEIPT_OUT_CROSS_TRANSFER, 0)
+            }
+            FunctionType::ApplyCrossTokenTransfer => {
+                Resources::new(GAS_TOKEN_TRANSFER, 0, GAS_RECEIPT_IN_CROSS_TRANSFER)
+            }
+            FunctionType::CreateCrossCall => {
+                Resources::new(GAS_TRANSFER, GAS_RECEIPT_OUT_CROSS_TRANSFER, 0)
+            }
+            FunctionType::ApplyCrossCall => Resources::new(
+                GAS_TRANSFER + GAS_CONTRACT_CALL,
+                GAS_RECEIPT_OUT_CROSS_TRANSFER,
+                GAS_RECEIPT_IN_CROSS_TRANSFER,
+            ),
+            FunctionType::ApplyCallback => {
+                Resources::new(GAS_TRANSFER, 0, GAS_RECEIPT_IN_CROSS_TRANSFER)
+            }
+            FunctionType::LockCrossTransfer => {
+                Resources::new(GAS_TRANSFER, GAS_RECEIPT_OUT_CROSS_TRANSFER, 0)
+            }
+            FunctionType::ConfirmCrossTransfer | FunctionType::CommitCrossTransfer => {
+                Resources::new(
+                    GAS_TRANSFER,
+                    GAS_RECEIPT_OUT_CROSS_TRANSFER,
+                    GAS_RECEIPT_IN_CROSS_TRANSFER,
+                )
+            }
+            FunctionType::AbortCrossTransfer => Resources::new(GAS_TRANSFER, 0, 0),
+            FunctionType::PostBatch => Resources::new(BATCH_HEADER_SIZE, 0, 0),
+            FunctionType::FinalizeCrossTransfer | FunctionType::ReclaimCrossTransfer => {
+                Resources::new(GAS_TRANSFER, 0, GAS_RECEIPT_IN_CROSS_TRANSFER)
+            }
         }
     }
 }
+
+impl Function {
+    /// Calculate the gas of a function.
+    pub fn gas(&self) -> Gas {
+        match self.ftype {
+            FunctionType::PostBatch => match self.parse_calldata() {
+                Ok(Calldata::Batch { size, .. }) => self.ftype.gas() + size,
+                _ => self.ftype.gas(),
+            },
+            _ => self.ftype.gas(),
+        }
+    }
+
+    /// Calculate the gas of a function per resource of the multidimensional fee market.
+    pub fn gas_by_resource(&self) -> Resources {
+        match self.ftype {
+            FunctionType::PostBatch => Resources::new(self.gas(), 0, 0),
+            _ => self.ftype.gas_by_resource(),
+        }
+    }
+
+    pub fn parse_calldata(&self) -> Result<Calldata, String> {
+        Calldata::parse(&self.ftype, &self.calldata)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::*;
+
+/// Rule to adjust the gas target of each shard over epochs within the global capacity budget.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasTargetRule {
+    /// Every shard keeps BLOCK_GAS_TARGET.
+    #[default]
+    Fixed,
+    /// The gas target moves toward the average gas used in the last epoch
+    /// by at most 1 / GAS_TARGET_MAX_CHANGE_DENOMINATOR per epoch.
+    Deterministic,
+    /// Each proposer votes to move the gas target toward its preferred one
+    /// by at most 1 / GAS_TARGET_VOTE_DENOMINATOR, like the gas limit of eth1.
+    ProposerVoting,
+}
+
+impl GasTargetRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GasTargetRule::Fixed => "fixed",
+            GasTargetRule::Deterministic => "deterministic",
+            GasTargetRule::ProposerVoting => "proposer_voting",
+        }
+    }
+
+    /// Compute the gas target of the next epoch from the blocks of the last epoch.
+    /// The result is not yet normalized within the global capacity budget.
+    pub fn compute_next_gas_target(&self, gas_target: Gas, blocks: &[ShardBlock]) -> Gas {
+        let proposed_blocks = blocks.iter().filter(|block| block.proposed);
+        match self {
+            GasTargetRule::Fixed => gas_target,
+            GasTargetRule::Deterministic => {
+                let proposed_block_num = proposed_blocks.clone().count() as Gas;
+                if proposed_block_num == 0 {
+                    return gas_target;
+                }
+                let average_gas_used =
+                    proposed_blocks.map(|block| block.gas_used).sum::<Gas>() / proposed_block_num;
+                let max_delta = gas_target / GAS_TARGET_MAX_CHANGE_DENOMINATOR as Gas;
+                if average_gas_used > gas_target {
+                    gas_target + std::cmp::min(average_gas_used - gas_target, max_delta)
+                } else {
+                    gas_target - std::cmp::min(gas_target - average_gas_used, max_delta)
+                }
+            }
+            GasTargetRule::ProposerVoting => {
+                proposed_blocks.fold(gas_target, |gas_target, block| {
+                    let max_delta = gas_target / GAS_TARGET_VOTE_DENOMINATOR as Gas;
+                    if block.gas_target_vote > gas_target {
+                        gas_target + std::cmp::min(block.gas_target_vote - gas_target, max_delta)
+                    } else {
+                        gas_target - std::cmp::min(gas_target - block.gas_target_vote, max_delta)
+                    }
+                })
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for GasTargetRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(GasTargetRule::Fixed),
+            "deterministic" => Ok(GasTargetRule::Deterministic),
+            "proposer_voting" => Ok(GasTargetRule::ProposerVoting),
+            _ => Err(format!("unknown gas target rule: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(gas_used: Gas, gas_target_vote: Gas) -> ShardBlock {
+        let mut block = ShardBlock::new(0);
+        block.gas_used = gas_used;
+        block.gas_target_vote = gas_target_vote;
+        block
+    }
+
+    #[test]
+    fn fixed_keeps_the_gas_target() {
+        let blocks = vec![block(2 * BLOCK_GAS_TARGET, 2 * BLOCK_GAS_TARGET)];
+        assert_eq!(
+            GasTargetRule::Fixed.compute_next_gas_target(BLOCK_GAS_TARGET, &blocks),
+            BLOCK_GAS_TARGET
+        );
+    }
+
+    #[test]
+    fn deterministic_moves_toward_the_average_gas_used_within_the_max_change() {
+        let max_delta = BLOCK_GAS_TARGET / GAS_TARGET_MAX_CHANGE_DENOMINATOR as Gas;
+        let rule = GasTargetRule::Deterministic;
+        let blocks = vec![block(BLOCK_GAS_TARGET + 2, 0), block(BLOCK_GAS_TARGET, 0)];
+        assert_eq!(
+            rule.compute_next_gas_target(BLOCK_GAS_TARGET, &blocks),
+            BLOCK_GAS_TARGET + 1
+        );
+        let blocks = vec![block(2 * BLOCK_GAS_TARGET, 0)];
+        assert_eq!(
+            rule.compute_next_gas_target(BLOCK_GAS_TARGET, &blocks),
+            BLOCK_GAS_TARGET + max_delta
+        );
+        let blocks = vec![block(0, 0)];
+        assert_eq!(
+            rule.compute_next_gas_target(BLOCK_GAS_TARGET, &blocks),
+            BLOCK_GAS_TARGET - max_delta
+        );
+    }
+
+    #[test]
+    fn deterministic_ignores_missed_proposals() {
+        let mut missed_block = block(0, 0);
+        missed_block.proposed = false;
+        assert_eq!(
+            GasTargetRule::Deterministic.compute_next_gas_target(BLOCK_GAS_TARGET, &[missed_block]),
+            BLOCK_GAS_TARGET
+        );
+    }
+
+    #[test]
+    fn proposer_voting_moves_toward_the_votes() {
+        let gas_target: Gas = 1024 * GAS_TARGET_VOTE_DENOMINATOR as Gas;
+        let rule = GasTargetRule::ProposerVoting;
+        // The gas used does not matter, only the votes
+        let blocks = vec![block(0, 2 * gas_target), block(0, 2 * gas_target)];
+        assert_eq!(
+            rule.compute_next_gas_target(gas_target, &blocks),
+            gas_target + 1024 + 1025
+        );
+        let blocks = vec![block(2 * gas_target, gas_target - 10)];
+        assert_eq!(
+            rule.compute_next_gas_target(gas_target, &blocks),
+            gas_target - 10
+        );
+        let blocks = vec![block(0, gas_target)];
+        assert_eq!(
+            rule.compute_next_gas_target(gas_target, &blocks),
+            gas_target
+        );
+    }
+}
@@ -4,24 +4,52 @@ pub use std::collections::{HashMap, HashSet, VecDeque};
 pub use std::hash::{Hash, Hasher};
 
 mod account;
+mod base_fee_coupling;
+mod batch_semantics;
+mod calldata;
+mod cross_transfer_lock;
 mod custom_types;
+mod fee_market;
 mod function;
+mod gas_target_rule;
+mod outage;
 mod parameters;
+mod proposer;
 mod receipt;
+mod resources;
 mod shard;
 mod shard_block;
+mod shard_kind;
 mod shard_state;
+mod shard_undo_log;
 mod sharded_blockchain;
+mod state_rent;
+mod token_contract;
 mod transaction;
 mod transaction_execution_result;
+mod transaction_type;
 pub use account::*;
+pub use base_fee_coupling::*;
+pub use batch_semantics::*;
+pub use calldata::*;
+pub use cross_transfer_lock::*;
 pub use custom_types::*;
+pub use fee_market::*;
 pub use function::*;
+pub use gas_target_rule::*;
+pub use outage::*;
 pub use parameters::poc_fee::*;
+pub use proposer::*;
 pub use receipt::*;
+pub use resources::*;
 pub use shard::*;
 pub use shard_block::*;
+pub use shard_kind::*;
 pub use shard_state::*;
+use shard_undo_log::*;
 pub use sharded_blockchain::*;
+pub use state_rent::*;
+pub use token_contract::*;
 pub use transaction::*;
 pub use transaction_execution_result::*;
+pub use transaction_type::*;
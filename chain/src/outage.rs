@@ -0,0 +1,68 @@
+use crate::*;
+
+/// Scheduled outage, during which a shard produces no blocks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Outage {
+    pub shard_id: usize,
+    /// The first slot of the outage.
+    pub from_slot: Slot,
+    /// The last slot of the outage (inclusive).
+    pub to_slot: Slot,
+}
+
+impl Outage {
+    pub fn new(shard_id: usize, from_slot: Slot, to_slot: Slot) -> Self {
+        Self {
+            shard_id,
+            from_slot,
+            to_slot,
+        }
+    }
+
+    pub fn covers(&self, shard_id: usize, slot: Slot) -> bool {
+        self.shard_id == shard_id && self.from_slot <= slot && slot <= self.to_slot
+    }
+}
+
+/// Parses "shard_id:from_slot:to_slot".
+impl std::str::FromStr for Outage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(':')
+            .map(|value| value.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid outage {}: {}", s, e))?;
+        if values.len() != 3 || values[1] > values[2] {
+            return Err(format!(
+                "invalid outage {}: expected shard_id:from_slot:to_slot",
+                s
+            ));
+        }
+        Ok(Outage::new(values[0] as usize, values[1], values[2]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outage_is_parsed_and_covers_its_slots() {
+        let outage: Outage = "1:2:3".parse().unwrap();
+        assert_eq!(outage, Outage::new(1, 2, 3));
+        assert!(!outage.covers(1, 1));
+        assert!(outage.covers(1, 2));
+        assert!(outage.covers(1, 3));
+        assert!(!outage.covers(1, 4));
+        assert!(!outage.covers(0, 2));
+    }
+
+    #[test]
+    fn invalid_outages_are_rejected() {
+        for s in ["1:3:2", "1:2", "1:2:3:4", "a:2:3", ""] {
+            assert!(s.parse::<Outage>().is_err(), "{}", s);
+        }
+    }
+}
@@ -7,18 +7,93 @@ pub const SHARD_NUM: usize = 64;
 // EIP-1559 Parameters
 // Ref: https://github.com/ethereum/EIPs/blob/e320c9c341f30d77e41fbb389742d9a0b5b5a1e6/EIPS/eip-1559.md
 pub const BLOCK_GAS_TARGET: Gas = 10_000_000;
-pub const BLOCK_GAS_LIMIT: Gas = BLOCK_GAS_TARGET * 2;
+pub const ELASTICITY_MULTIPLIER: Gas = 2;
+pub const BLOCK_GAS_LIMIT: Gas = BLOCK_GAS_TARGET * ELASTICITY_MULTIPLIER;
 pub const INITIAL_BASE_FEE: GasPrice = 1_000_000_000;
 pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: usize = 8;
 pub const MAX_GASPRICE: GasPrice = 16_384_000_000_000;
 pub const MIN_GASPRICE: GasPrice = 0;
 
+// Multidimensional EIP-1559 Parameters
+/// The receipt gas targets are 1 / RECEIPT_{OUT,IN}_GAS_TARGET_DENOMINATOR of the gas target of the shard.
+pub const RECEIPT_OUT_GAS_TARGET_DENOMINATOR: Gas = 4;
+pub const RECEIPT_IN_GAS_TARGET_DENOMINATOR: Gas = 4;
+pub const INITIAL_RECEIPT_BASE_FEE: GasPrice = INITIAL_BASE_FEE;
+
+// Base fee coupling
+pub const INITIAL_GLOBAL_BASE_FEE: GasPrice = INITIAL_BASE_FEE / 2;
+/// The weight of the mean of the neighbors' base fees is
+/// BASE_FEE_SMOOTHING_NUMERATOR / BASE_FEE_SMOOTHING_DENOMINATOR.
+pub const BASE_FEE_SMOOTHING_NUMERATOR: GasPrice = 1;
+pub const BASE_FEE_SMOOTHING_DENOMINATOR: GasPrice = 4;
+
+// Elastic gas target
+/// The default gas target budget, before the shard number and the gas target are configured.
+pub const GLOBAL_GAS_TARGET_BUDGET: Gas = BLOCK_GAS_TARGET * SHARD_NUM as Gas;
+/// The gas target of a shard is kept above 1 / MIN_GAS_TARGET_DENOMINATOR of the configured one.
+pub const MIN_GAS_TARGET_DENOMINATOR: Gas = 8;
+pub const GAS_TARGET_MAX_CHANGE_DENOMINATOR: usize = 8;
+// Ref: GAS_LIMIT_BOUND_DIVISOR in https://ethereum.github.io/yellowpaper/paper.pdf
+pub const GAS_TARGET_VOTE_DENOMINATOR: usize = 1024;
+
 pub const MEMPOOL_TRANSACTION_NUM: usize = 10_000;
 
+// Data shards, whose gas is counted in bytes
+pub const BLOCK_DATA_TARGET: Gas = 262_144;
+// Ref: the calldata cost of 16 gas per non-zero byte in EIP-2028
+pub const INITIAL_DATA_BASE_FEE: GasPrice = 16 * INITIAL_BASE_FEE;
+/// Bytes of the header of a rollup batch.
+pub const BATCH_HEADER_SIZE: Gas = 100;
+
+// State rent
+/// State size in bytes of an account without storage and token balances.
+pub const ACCOUNT_STATE_SIZE: u64 = 100;
+pub const STORAGE_SLOT_STATE_SIZE: u64 = 64;
+pub const TOKEN_BALANCE_STATE_SIZE: u64 = 64;
+/// The rent per byte per epoch when the shard state has TARGET_SHARD_STATE_SIZE bytes.
+/// It is proportional to the shard state size.
+pub const RENT_PER_BYTE_PER_EPOCH: Wei = 1_000_000_000_000;
+pub const TARGET_SHARD_STATE_SIZE: u64 = 16_384;
+
+// Receipts of cross-shard transfers that are not applied within this period can be reclaimed
+pub const RECEIPT_EXPIRY_EPOCHS: Epoch = 2;
+
 // Gas
 // TODO: to exactly guessed values
 pub const GAS_TRANSFER: Gas = 21_000;
+/// The part of the gas of every function that a multi-function transaction pays only once,
+/// e.g. the signature verification and the nonce update.
+/// Ref: a value-transferring CALL costs 9,000 of the 21,000 gas of a transfer
+pub const GAS_TRANSACTION_BASE: Gas = 12_000;
 pub const GAS_CREATE_CROSS_TRANSFER: Gas = 31_785;
 pub const GAS_APPLY_CROSS_TRANSFER: Gas = 52_820;
 pub const GAS_CREATE_CROSS_TRANSFER_ALL: Gas = GAS_CREATE_CROSS_TRANSFER;
 pub const GAS_APPLY_CROSS_TRANSFER_ALL: Gas = GAS_APPLY_CROSS_TRANSFER;
+pub const GAS_MINT: Gas = 51_000;
+pub const GAS_TOKEN_TRANSFER: Gas = 51_000;
+pub const GAS_CREATE_CROSS_TOKEN_TRANSFER: Gas =
+    GAS_TOKEN_TRANSFER + GAS_CREATE_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_APPLY_CROSS_TOKEN_TRANSFER: Gas =
+    GAS_TOKEN_TRANSFER + GAS_APPLY_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_CONTRACT_CALL: Gas = 30_000;
+pub const GAS_CREATE_CROSS_CALL: Gas = GAS_CREATE_CROSS_TRANSFER;
+pub const GAS_APPLY_CROSS_CALL: Gas =
+    GAS_APPLY_CROSS_TRANSFER + GAS_CONTRACT_CALL + GAS_CREATE_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_APPLY_CALLBACK: Gas = GAS_APPLY_CROSS_TRANSFER;
+pub const GAS_LOCK_CROSS_TRANSFER: Gas = GAS_CREATE_CROSS_TRANSFER;
+pub const GAS_CONFIRM_CROSS_TRANSFER: Gas =
+    GAS_APPLY_CROSS_TRANSFER + GAS_CREATE_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_COMMIT_CROSS_TRANSFER: Gas =
+    GAS_APPLY_CROSS_TRANSFER + GAS_CREATE_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_ABORT_CROSS_TRANSFER: Gas = GAS_TRANSFER;
+pub const GAS_FINALIZE_CROSS_TRANSFER: Gas = GAS_APPLY_CROSS_TRANSFER;
+pub const GAS_RECLAIM_CROSS_TRANSFER: Gas = GAS_APPLY_CROSS_TRANSFER;
+pub const GAS_YANK_ACCOUNT: Gas = GAS_CREATE_CROSS_TRANSFER_ALL;
+pub const GAS_PULL_ACCOUNT: Gas = GAS_APPLY_CROSS_TRANSFER_ALL;
+
+// Receipt part of the gas of cross-shard functions in the multidimensional fee market
+// The rest is execution gas.
+pub const GAS_RECEIPT_OUT_CROSS_TRANSFER: Gas = GAS_CREATE_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_RECEIPT_IN_CROSS_TRANSFER: Gas = GAS_APPLY_CROSS_TRANSFER - GAS_TRANSFER;
+pub const GAS_RECEIPT_OUT_CROSS_TRANSFER_ALL: Gas = GAS_CREATE_CROSS_TRANSFER_ALL - GAS_TRANSFER;
+pub const GAS_RECEIPT_IN_CROSS_TRANSFER_ALL: Gas = GAS_APPLY_CROSS_TRANSFER_ALL - GAS_TRANSFER;
@@ -0,0 +1,162 @@
+use crate::*;
+
+/// Block production behavior of a shard block proposer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposerBehavior {
+    /// Includes transactions in descending order of the effective gas price.
+    #[default]
+    Honest,
+    /// Excludes transactions that process incoming cross-shard transfers, calls and moves,
+    /// or any later leg of them, including commits, aborts and reclaims.
+    CensorCrossShardApply,
+    /// Excludes transactions that move accounts to or from the shard.
+    CensorMovement,
+    /// Fills the rest of each block with the proposer's own transactions to pump the base fee.
+    /// This is an accounting-only approximation: the block is marked full and the stuffed gas is
+    /// recorded, but no transactions are sent, so the proposer pays nothing and is not limited
+    /// by its balance, and the stuffing never displaces the transactions of other users.
+    BaseFeeStuffing,
+    /// Proposes empty blocks to drive the base fee down.
+    EmptyBlock,
+    /// Includes transactions of the proposer's own senders first.
+    SelfPreferencing,
+}
+
+impl ProposerBehavior {
+    /// Whether or not the proposer refuses to include the transaction.
+    pub fn censors(&self, transaction: &Transaction) -> bool {
+        match self {
+            ProposerBehavior::CensorCrossShardApply => {
+                transaction.functions.iter().any(|function| {
+                    matches!(
+                        function.ftype,
+                        FunctionType::ApplyCrossTransfer
+                            | FunctionType::ApplyCrossTransferAll
+                            | FunctionType::ApplyCrossTokenTransfer
+                            | FunctionType::ApplyCrossCall
+                            | FunctionType::ApplyCallback
+                            | FunctionType::ConfirmCrossTransfer
+                            | FunctionType::CommitCrossTransfer
+                            | FunctionType::AbortCrossTransfer
+                            | FunctionType::FinalizeCrossTransfer
+                            | FunctionType::ReclaimCrossTransfer
+                            | FunctionType::PullAccount
+                    )
+                })
+            }
+            ProposerBehavior::CensorMovement => transaction.functions.iter().any(|function| {
+                function.ftype == FunctionType::CreateCrossTransferAll
+                    || function.ftype == FunctionType::ApplyCrossTransferAll
+                    || function.ftype == FunctionType::YankAccount
+                    || function.ftype == FunctionType::PullAccount
+            }),
+            _ => false,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProposerBehavior::Honest => "honest",
+            ProposerBehavior::CensorCrossShardApply => "censor_cross_shard_apply",
+            ProposerBehavior::CensorMovement => "censor_movement",
+            ProposerBehavior::BaseFeeStuffing => "base_fee_stuffing",
+            ProposerBehavior::EmptyBlock => "empty_block",
+            ProposerBehavior::SelfPreferencing => "self_preferencing",
+        }
+    }
+}
+
+impl std::str::FromStr for ProposerBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "honest" => Ok(ProposerBehavior::Honest),
+            "censor_cross_shard_apply" => Ok(ProposerBehavior::CensorCrossShardApply),
+            "censor_movement" => Ok(ProposerBehavior::CensorMovement),
+            "base_fee_stuffing" => Ok(ProposerBehavior::BaseFeeStuffing),
+            "empty_block" => Ok(ProposerBehavior::EmptyBlock),
+            "self_preferencing" => Ok(ProposerBehavior::SelfPreferencing),
+            _ => Err(format!("unknown proposer behavior: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(ftypes: &[FunctionType]) -> Transaction {
+        let functions = ftypes
+            .iter()
+            .map(|ftype| Function {
+                source: 0,
+                target: 1,
+                ftype: ftype.clone(),
+                calldata: "".to_string(),
+            })
+            .collect();
+        Transaction::new(0, 1, 0, functions, 0, INITIAL_BASE_FEE, 0)
+    }
+
+    fn censored_ftypes(behavior: ProposerBehavior) -> Vec<FunctionType> {
+        FunctionType::ALL
+            .iter()
+            .filter(|ftype| behavior.censors(&transaction(&[(*ftype).clone()])))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn censor_cross_shard_apply_excludes_every_incoming_leg() {
+        assert_eq!(
+            censored_ftypes(ProposerBehavior::CensorCrossShardApply),
+            vec![
+                FunctionType::ApplyCrossTransfer,
+                FunctionType::ApplyCrossTransferAll,
+                FunctionType::ApplyCrossTokenTransfer,
+                FunctionType::ApplyCrossCall,
+                FunctionType::ApplyCallback,
+                FunctionType::ConfirmCrossTransfer,
+                FunctionType::CommitCrossTransfer,
+                FunctionType::AbortCrossTransfer,
+                FunctionType::FinalizeCrossTransfer,
+                FunctionType::ReclaimCrossTransfer,
+                FunctionType::PullAccount,
+            ]
+        );
+        // A batch is excluded if any of its functions is
+        assert!(
+            ProposerBehavior::CensorCrossShardApply.censors(&transaction(&[
+                FunctionType::Transfer,
+                FunctionType::ApplyCrossCall
+            ]))
+        );
+    }
+
+    #[test]
+    fn censor_movement_excludes_only_moves() {
+        assert_eq!(
+            censored_ftypes(ProposerBehavior::CensorMovement),
+            vec![
+                FunctionType::CreateCrossTransferAll,
+                FunctionType::ApplyCrossTransferAll,
+                FunctionType::YankAccount,
+                FunctionType::PullAccount,
+            ]
+        );
+    }
+
+    #[test]
+    fn other_behaviors_censor_nothing() {
+        for behavior in [
+            ProposerBehavior::Honest,
+            ProposerBehavior::BaseFeeStuffing,
+            ProposerBehavior::EmptyBlock,
+            ProposerBehavior::SelfPreferencing,
+        ] {
+            assert!(censored_ftypes(behavior).is_empty());
+        }
+    }
+}
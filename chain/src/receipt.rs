@@ -2,10 +2,10 @@ use crate::*;
 
 #[derive(Clone, Debug)]
 pub struct Receipt {
-    slot_number: Slot,
-    from: Address,
-    to: Address,
-    gas_used: Gas,
+    pub slot_number: Slot,
+    pub from: Address,
+    pub to: Address,
+    pub gas_used: Gas,
     pub status: bool,
     pub transaction_hash: TransactionHash,
     pub data: Data,
@@ -29,4 +29,30 @@ impl Receipt {
             data,
         }
     }
+
+    /// The receipt of a function in a multi-function transaction.
+    pub fn new_for_function(
+        slot_number: Slot,
+        transaction: &Transaction,
+        index: usize,
+        gas_used: Gas,
+        status: bool,
+        data: Data,
+    ) -> Self {
+        Self {
+            slot_number,
+            from: transaction.from,
+            to: transaction.functions[index].target,
+            gas_used,
+            status,
+            transaction_hash: transaction.function_hash(index),
+            data,
+        }
+    }
+
+    /// Whether or not the receipt can no longer be applied on the destination shard in the slot,
+    /// so that the sender can reclaim it.
+    pub fn is_expired(&self, slot: Slot) -> bool {
+        slot > self.slot_number + RECEIPT_EXPIRY_EPOCHS * SLOTS_PER_EPOCH
+    }
 }
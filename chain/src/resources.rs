@@ -0,0 +1,65 @@
+use crate::*;
+
+/// Resources of a shard block, priced separately in the multidimensional fee market.
+/// Used for both amounts (gas) and prices (wei/gas) per resource.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Resources {
+    /// Execution gas.
+    pub execution: Gas,
+    /// Bandwidth of outgoing cross-shard receipts.
+    pub receipt_out: Gas,
+    /// Bandwidth of incoming cross-shard receipts.
+    pub receipt_in: Gas,
+}
+
+impl Resources {
+    pub fn new(execution: Gas, receipt_out: Gas, receipt_in: Gas) -> Self {
+        Self {
+            execution,
+            receipt_out,
+            receipt_in,
+        }
+    }
+
+    pub fn total(&self) -> Gas {
+        self.execution + self.receipt_out + self.receipt_in
+    }
+
+    /// Sum of the products of the amounts and the prices.
+    pub fn cost(&self, prices: &Resources) -> Wei {
+        self.execution * prices.execution
+            + self.receipt_out * prices.receipt_out
+            + self.receipt_in * prices.receipt_in
+    }
+
+    /// Whether or not every resource is less than or equal to that of `other`.
+    pub fn fits_in(&self, other: &Resources) -> bool {
+        self.execution <= other.execution
+            && self.receipt_out <= other.receipt_out
+            && self.receipt_in <= other.receipt_in
+    }
+}
+
+impl std::ops::Add for Resources {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            execution: self.execution + other.execution,
+            receipt_out: self.receipt_out + other.receipt_out,
+            receipt_in: self.receipt_in + other.receipt_in,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Resources {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl std::iter::Sum for Resources {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Resources::default(), |a, b| a + b)
+    }
+}
@@ -3,6 +3,7 @@ use crate::*;
 /// Shard chain with the definition of the on-chain state transition rule.
 pub struct Shard {
     pub id: usize,
+    pub kind: ShardKind,
 
     pub blocks: Vec<ShardBlock>,
     pub states: Vec<ShardState>,
@@ -10,26 +11,60 @@ pub struct Shard {
     // Included in a state but only needs to be kept in a snapshot
     pub accounts: HashMap<Address, Account>,
     pub receipts: HashMap<TransactionHash, Receipt>,
+    /// Token contracts whose home shard is this shard.
+    pub token_contracts: HashMap<Address, TokenContract>,
+    /// Funds locked by two-phase cross-shard transfers, by the hash of the locking transaction.
+    pub locks: HashMap<TransactionHash, CrossTransferLock>,
+    /// Outgoing cross-shard transfers that can be reclaimed once their receipts expire.
+    outgoing_transfers: HashSet<TransactionHash>,
 
     // Shard block proposer variables
     pub moving_accounts: HashMap<Address, Account>,
+    /// Accounts that could not pay the rent under StateRent::Hibernate.
+    pub hibernated_accounts: HashMap<Address, Account>,
+    /// The number of accounts evicted or hibernated so far.
+    pub evicted_account_num: usize,
+    /// Moving accounts that were yanked, whose transactions are rejected until they are pulled.
+    yanked_accounts: HashSet<Address>,
     pub mempool: Vec<(Transaction, Option<Receipt>)>,
     used_receipts: HashSet<TransactionHash>,
     account_nonce: HashMap<Address, Nonce>,
+    /// Kept while an all-or-nothing batch is executed.
+    undo_log: Option<ShardUndoLog>,
+    pub fee_market: FeeMarket,
+    pub state_rent: StateRent,
+    pub proposer_behavior: ProposerBehavior,
+    /// Senders affiliated with the proposer, i.e. the proposer's "own" senders.
+    pub proposer_senders: HashSet<Address>,
+    /// The gas target the proposer votes toward under GasTargetRule::ProposerVoting.
+    pub preferred_gas_target: Gas,
 }
 
 impl Shard {
     pub fn new(id: usize) -> Self {
         Self {
             id,
+            kind: ShardKind::Execution,
             blocks: Vec::new(),
-            states: vec![ShardState::new(INITIAL_BASE_FEE)],
+            states: vec![ShardState::new(INITIAL_BASE_FEE, BLOCK_GAS_TARGET)],
             accounts: HashMap::new(),
             receipts: HashMap::new(),
+            token_contracts: HashMap::new(),
+            locks: HashMap::new(),
+            outgoing_transfers: HashSet::new(),
             moving_accounts: HashMap::new(),
+            yanked_accounts: HashSet::new(),
+            hibernated_accounts: HashMap::new(),
+            evicted_account_num: 0,
             used_receipts: HashSet::new(),
             mempool: Vec::new(),
             account_nonce: HashMap::new(),
+            undo_log: None,
+            fee_market: FeeMarket::OneDimensional,
+            state_rent: StateRent::Free,
+            proposer_behavior: ProposerBehavior::Honest,
+            proposer_senders: HashSet::new(),
+            preferred_gas_target: BLOCK_GAS_TARGET,
         }
     }
 
@@ -44,24 +79,88 @@ impl Shard {
     pub fn remove_account(&mut self, addr: Address) {
         self.accounts.remove(&addr);
         self.moving_accounts.remove(&addr);
+        self.yanked_accounts.remove(&addr);
     }
 
-    fn move_account(&mut self, addr: Address) -> Data {
+    /// Move the account out of the shard and return it as the data of the receipt,
+    /// designating the only shard that can take it in.
+    fn move_account(&mut self, addr: Address, target_shard_id: usize) -> Data {
+        self.log_account(addr);
         let account = self
             .accounts
-            .get(&addr)
+            .remove(&addr)
             .expect("the account does not exist");
-        let data = serde_json::to_string(account).unwrap();
-        self.moving_accounts.insert(addr, account.clone());
-        self.accounts.remove(&addr);
-        data
+        let mut designated_account = account.clone();
+        designated_account.shard_id = target_shard_id;
+        self.moving_accounts.insert(addr, account);
+        serde_json::to_string(&designated_account).unwrap()
     }
 
     fn insert_account(&mut self, mut account: Account) {
+        self.log_account(account.addr);
         account.shard_id = self.id;
         self.accounts.insert(account.addr, account);
     }
 
+    /// Size in bytes of the accounts in the shard state.
+    pub fn state_size(&self) -> u64 {
+        self.accounts
+            .values()
+            .map(|account| account.state_size())
+            .sum()
+    }
+
+    /// Rent per epoch of the account if it were on this shard.
+    pub fn estimate_rent(&self, account: &Account) -> Wei {
+        if self.state_rent == StateRent::Free {
+            return 0;
+        }
+        account.state_size() as Wei * RENT_PER_BYTE_PER_EPOCH * self.state_size() as Wei
+            / TARGET_SHARD_STATE_SIZE as Wei
+    }
+
+    /// Charge every account the rent of an epoch.
+    /// Accounts that cannot pay are evicted or hibernated.
+    pub fn collect_rent(&mut self) {
+        if self.state_rent == StateRent::Free {
+            return;
+        }
+        let rent_per_byte =
+            RENT_PER_BYTE_PER_EPOCH * self.state_size() as Wei / TARGET_SHARD_STATE_SIZE as Wei;
+        let mut evicted_addrs = Vec::new();
+        for account in self.accounts.values_mut() {
+            let rent = account.state_size() as Wei * rent_per_byte;
+            if account.balance >= rent {
+                account.balance -= rent;
+            } else {
+                evicted_addrs.push(account.addr);
+            }
+        }
+        for addr in evicted_addrs {
+            let account = self.accounts.remove(&addr).unwrap();
+            if self.state_rent == StateRent::Hibernate {
+                self.hibernated_accounts.insert(addr, account);
+            }
+            self.evicted_account_num += 1;
+        }
+    }
+
+    /// Turn the shard into a data shard before the genesis.
+    pub fn set_kind(&mut self, kind: ShardKind) {
+        assert!(self.blocks.is_empty());
+        self.kind = kind;
+        self.states = match kind {
+            ShardKind::Execution => vec![ShardState::new(INITIAL_BASE_FEE, BLOCK_GAS_TARGET)],
+            ShardKind::Data => vec![ShardState::new(INITIAL_DATA_BASE_FEE, BLOCK_DATA_TARGET)],
+        };
+    }
+
+    pub fn deploy_token_contract(&mut self, token_contract: TokenContract) {
+        assert_eq!(token_contract.home_shard_id, self.id);
+        self.token_contracts
+            .insert(token_contract.addr, token_contract);
+    }
+
     pub fn push_transaction(&mut self, transaction: Transaction, receipt: Option<Receipt>) {
         self.mempool.push((transaction, receipt));
     }
@@ -69,38 +168,114 @@ impl Shard {
     pub fn process_slot(&mut self) {
         // Sort in descending order by fee cap
         let base_fee = self.get_base_fee();
+        let base_fees = self.get_base_fees();
+        let gas_limit = self.get_gas_limit();
+        let gas_targets = self.get_gas_targets();
+        let gas_limits = Resources::new(
+            gas_limit,
+            gas_targets.receipt_out * ELASTICITY_MULTIPLIER,
+            gas_targets.receipt_in * ELASTICITY_MULTIPLIER,
+        );
         self.mempool.sort_by(|a, b| {
-            let b = std::cmp::min(b.0.fee_cap, b.0.gas_premium + base_fee);
-            let a = std::cmp::min(a.0.fee_cap, a.0.gas_premium + base_fee);
-            b.cmp(&a)
+            b.0.effective_gas_price(base_fee)
+                .cmp(&a.0.effective_gas_price(base_fee))
         });
+        if self.proposer_behavior == ProposerBehavior::SelfPreferencing {
+            // Stable sort, so the order by fee cap is kept within each group
+            let proposer_senders = &self.proposer_senders;
+            self.mempool
+                .sort_by_key(|(transaction, _)| !proposer_senders.contains(&transaction.from));
+        }
 
         let mut block = ShardBlock::new(self.blocks.len() as Slot);
+        block.gas_target_vote = self.preferred_gas_target;
 
-        let mut executed_num = 0;
-        let mut skip_transactions = Vec::new();
+        let mut included_transaction_hashes = HashSet::new();
 
         let mut receipts = HashMap::new();
 
         for (transaction, receipt) in self.mempool.clone().iter() {
-            let estimated_gas = self.estimate_transaction_gas(transaction);
-
-            // TODO: Run it and revert it if it doesn't work
-            if block.gas_used + estimated_gas > BLOCK_GAS_LIMIT {
+            if self.proposer_behavior == ProposerBehavior::EmptyBlock {
                 break;
             }
+            if self.proposer_behavior.censors(transaction) {
+                block.censored_transaction_num += 1;
+                continue;
+            }
 
-            if transaction.fee_cap <= base_fee {
-                break;
+            let estimated_gas = self.estimate_transaction_gas(transaction);
+            let estimated_gas_by_resource = self.estimate_transaction_gas_by_resource(transaction);
+
+            match self.fee_market {
+                FeeMarket::OneDimensional => {
+                    // TODO: Run it and revert it if it doesn't work
+                    if block.gas_used + estimated_gas > gas_limit {
+                        break;
+                    }
+
+                    if transaction.fee_cap <= base_fee {
+                        break;
+                    }
+                }
+                FeeMarket::MultiDimensional => {
+                    if transaction.fee_cap <= base_fee {
+                        break;
+                    }
+
+                    // Another transaction may still fit in the resources that are not exhausted.
+                    let fee_caps = transaction.fee_caps();
+                    if (estimated_gas_by_resource.receipt_out > 0
+                        && fee_caps.receipt_out <= base_fees.receipt_out)
+                        || (estimated_gas_by_resource.receipt_in > 0
+                            && fee_caps.receipt_in <= base_fees.receipt_in)
+                    {
+                        continue;
+                    }
+                    if !(block.gas_used_by_resource() + estimated_gas_by_resource)
+                        .fits_in(&gas_limits)
+                    {
+                        continue;
+                    }
+                }
             }
 
-            let (result, data, gas) = self.execute_transaction(transaction, receipt.clone());
+            // The fee payer must be someone else on this shard
+            let has_invalid_fee_payer = match transaction.transaction_type {
+                TransactionType::Sponsored { fee_payer } => {
+                    fee_payer == transaction.from || !self.accounts.contains_key(&fee_payer)
+                }
+                _ => false,
+            };
+
+            let (result, data, gas, function_results) =
+                if self.yanked_accounts.contains(&transaction.from) {
+                    // In-flight transactions of a yanked account fail
+                    block.locked_transaction_num += 1;
+                    (TransactionExecutionResult::Fail, Data::new(), 0, Vec::new())
+                } else if has_invalid_fee_payer {
+                    // Nobody pays for it, so it fails without running
+                    (TransactionExecutionResult::Fail, Data::new(), 0, Vec::new())
+                } else {
+                    self.execute_transaction(transaction, receipt.clone())
+                };
 
             if result == TransactionExecutionResult::Skip {
-                skip_transactions.push(transaction.clone());
+                block.skipped_transactions.push(transaction.hash);
                 continue;
             } else {
-                block.gas_used += gas;
+                // Only the functions that ran use their resources
+                let gas_used_by_resource = function_results
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &(_, _, gas_used))| gas_used > 0)
+                    .map(|(index, _)| transaction.function_gas_by_resource(index))
+                    .sum::<Resources>();
+                block.receipt_out_gas_used += gas_used_by_resource.receipt_out;
+                block.receipt_in_gas_used += gas_used_by_resource.receipt_in;
+                block.gas_used += match self.fee_market {
+                    FeeMarket::OneDimensional => gas,
+                    FeeMarket::MultiDimensional => gas_used_by_resource.execution,
+                };
 
                 // No revert, so apply the state transitions
                 block.executed_transactions.push(transaction.clone());
@@ -108,29 +283,51 @@ impl Shard {
                     block.number,
                     transaction,
                     gas,
-                    match result {
-                        TransactionExecutionResult::Success => true,
-                        _ => false,
-                    },
+                    result == TransactionExecutionResult::Success,
                     data,
                 );
                 receipts.insert(transaction.hash, receipt.clone());
                 self.receipts.insert(transaction.hash, receipt);
+                if transaction.functions.len() > 1 {
+                    for (index, (status, data, gas_used)) in
+                        function_results.into_iter().enumerate()
+                    {
+                        let receipt = Receipt::new_for_function(
+                            block.number,
+                            transaction,
+                            index,
+                            gas_used,
+                            status,
+                            data,
+                        );
+                        receipts.insert(receipt.transaction_hash, receipt.clone());
+                        self.receipts.insert(receipt.transaction_hash, receipt);
+                    }
+                }
             }
-            executed_num += 1;
+            included_transaction_hashes.insert(transaction.hash);
+        }
+        self.mempool
+            .retain(|(transaction, _)| !included_transaction_hashes.contains(&transaction.hash));
+
+        if self.proposer_behavior == ProposerBehavior::BaseFeeStuffing {
+            // The proposer fills the rest of the block with its own transactions.
+            block.stuffed_gas = gas_limit - block.gas_used;
+            block.gas_used = gas_limit;
         }
-        // Removes transactions in excess of MEMPOOL_TRANSACTION_NUM
-        self.mempool = self.mempool[executed_num..].to_vec();
-        //self.mempool.extend(skip_transactions);
 
+        // Removes transactions in excess of MEMPOOL_TRANSACTION_NUM
         self.mempool.sort_by(|a, b| {
-            let b = std::cmp::min(b.0.fee_cap, b.0.gas_premium + base_fee);
-            let a = std::cmp::min(a.0.fee_cap, a.0.gas_premium + base_fee);
-            b.cmp(&a)
+            b.0.effective_gas_price(base_fee)
+                .cmp(&a.0.effective_gas_price(base_fee))
         });
 
         let length = std::cmp::min(self.mempool.len(), MEMPOOL_TRANSACTION_NUM);
-        self.mempool = self.mempool[..length].to_vec();
+        block.dropped_transactions = self.mempool[length..]
+            .iter()
+            .map(|(transaction, _)| transaction.hash)
+            .collect();
+        self.mempool.truncate(length);
         self.blocks.push(block);
 
         let mut state = self.generate_next_state();
@@ -138,29 +335,108 @@ impl Shard {
         self.states.push(state);
     }
 
+    /// Process of a slot whose proposal is missed.
+    /// The base fee, the mempool and the receipts are carried over to the next slot as they are.
+    pub fn skip_slot(&mut self) {
+        let mut block = ShardBlock::new(self.blocks.len() as Slot);
+        block.proposed = false;
+        self.blocks.push(block);
+
+        let prev_state = self
+            .states
+            .last()
+            .expect("the genesis state does not exist");
+        let mut state = ShardState::new(prev_state.base_fee, prev_state.gas_target);
+        state.global_base_fee = prev_state.global_base_fee;
+        state.receipt_out_base_fee = prev_state.receipt_out_base_fee;
+        state.receipt_in_base_fee = prev_state.receipt_in_base_fee;
+        self.states.push(state);
+    }
+
     fn generate_next_state(&mut self) -> ShardState {
         assert!(!self.blocks.is_empty());
-        let prev_base_fee = self
+        let prev_state = self
             .states
             .last()
-            .expect("the genesis state does not exist")
-            .base_fee;
+            .expect("the genesis state does not exist");
         let block_gas_used = self
             .blocks
             .last()
             .expect("the genesis block does not exist")
             .gas_used;
-        let base_fee = ShardState::compute_updated_gasprice(prev_base_fee, block_gas_used);
-        ShardState::new(base_fee)
+        let base_fee = ShardState::compute_updated_gasprice(
+            prev_state.base_fee,
+            block_gas_used,
+            prev_state.gas_target,
+        );
+        let mut state = ShardState::new(base_fee, prev_state.gas_target);
+        state.global_base_fee = prev_state.global_base_fee;
+        state.receipt_out_base_fee = prev_state.receipt_out_base_fee;
+        state.receipt_in_base_fee = prev_state.receipt_in_base_fee;
+        if self.fee_market == FeeMarket::MultiDimensional {
+            let block = self
+                .blocks
+                .last()
+                .expect("the genesis block does not exist");
+            state.receipt_out_base_fee = ShardState::compute_updated_gasprice(
+                prev_state.receipt_out_base_fee,
+                block.receipt_out_gas_used,
+                prev_state.gas_targets().receipt_out,
+            );
+            state.receipt_in_base_fee = ShardState::compute_updated_gasprice(
+                prev_state.receipt_in_base_fee,
+                block.receipt_in_gas_used,
+                prev_state.gas_targets().receipt_in,
+            );
+        }
+        state
     }
 
     /// Estimate the gas usage before including the transaction in the chain.
     fn estimate_transaction_gas(&self, transaction: &Transaction) -> Gas {
-        transaction
-            .functions
-            .iter()
-            .map(|function| function.gas())
-            .sum()
+        transaction.gas()
+    }
+
+    /// Estimate the gas usage per resource of the multidimensional fee market.
+    fn estimate_transaction_gas_by_resource(&self, transaction: &Transaction) -> Resources {
+        transaction.gas_by_resource()
+    }
+
+    /// Estimate the base fee cost of a function of the type in the current slot.
+    pub fn estimate_base_fee_cost(&self, ftype: &FunctionType) -> Wei {
+        match self.fee_market {
+            FeeMarket::OneDimensional => ftype.gas() * self.get_base_fee(),
+            FeeMarket::MultiDimensional => ftype.gas_by_resource().cost(&self.get_base_fees()),
+        }
+    }
+
+    /// The base fee burned and the tip paid for the function at the index of a transaction
+    /// executed in the slot, by the gas used in its receipt. The tip is paid only for execution
+    /// gas, and nothing is paid for a function that used no gas, e.g. one that never ran.
+    pub fn function_fee(&self, slot: Slot, transaction: &Transaction, index: usize) -> (Wei, Wei) {
+        let gas_used = self
+            .receipts
+            .get(&transaction.function_hash(index))
+            .map_or(0, |receipt| receipt.gas_used);
+        if gas_used == 0 {
+            return (0, 0);
+        }
+        let state = &self.states[slot as usize];
+        let tip = transaction.tip(state.base_fee);
+        match self.fee_market {
+            FeeMarket::OneDimensional => (state.base_fee * gas_used, tip * gas_used),
+            FeeMarket::MultiDimensional => {
+                let gas = transaction.function_gas_by_resource(index);
+                (gas.cost(&state.base_fees()), tip * gas.execution)
+            }
+        }
+    }
+
+    pub fn get_base_fees(&self) -> Resources {
+        self.states
+            .last()
+            .expect("the genesis state does not exist")
+            .base_fees()
     }
 
     pub fn get_base_fee(&self) -> GasPrice {
@@ -170,12 +446,44 @@ impl Shard {
             .base_fee
     }
 
+    pub fn get_gas_target(&self) -> Gas {
+        self.states
+            .last()
+            .expect("the genesis state does not exist")
+            .gas_target
+    }
+
+    pub fn get_gas_targets(&self) -> Resources {
+        self.states
+            .last()
+            .expect("the genesis state does not exist")
+            .gas_targets()
+    }
+
+    pub fn get_gas_limit(&self) -> Gas {
+        self.get_gas_target() * ELASTICITY_MULTIPLIER
+    }
+
+    /// Set the gas target used from the next block.
+    pub fn set_gas_target(&mut self, gas_target: Gas) {
+        self.states
+            .last_mut()
+            .expect("the genesis state does not exist")
+            .gas_target = gas_target;
+    }
+
     /// Execute a transaction with validation.
+    /// Also return the status and the data of each function.
     fn execute_transaction(
         &mut self,
         transaction: &Transaction,
         receipt: Option<Receipt>,
-    ) -> (TransactionExecutionResult, Data, Gas) {
+    ) -> (
+        TransactionExecutionResult,
+        Data,
+        Gas,
+        Vec<(bool, Data, Gas)>,
+    ) {
         // TODO: Vec<Data>
         assert_eq!(transaction.shard_id, self.id);
         let mut transaction_gas_used = 0;
@@ -188,20 +496,53 @@ impl Shard {
             .nonce
             .cmp(self.account_nonce.get(&transaction.from).unwrap())
         {
-            Ordering::Greater => (TransactionExecutionResult::Skip, data, 0),
-            Ordering::Less => (TransactionExecutionResult::Fail, data, 0),
+            Ordering::Greater => (TransactionExecutionResult::Skip, data, 0, Vec::new()),
+            Ordering::Less => (TransactionExecutionResult::Fail, data, 0, Vec::new()),
             Ordering::Equal => {
                 *self.account_nonce.entry(transaction.from).or_insert(0) += 1; // TODO: nonce bug?
 
+                let is_batch = transaction.functions.len() > 1;
+                let all_or_nothing =
+                    is_batch && transaction.batch_semantics == BatchSemantics::AllOrNothing;
+                if all_or_nothing {
+                    self.undo_log = Some(ShardUndoLog::default());
+                }
+
                 let mut success = true;
-                for function in &transaction.functions {
-                    // If there is an illegal function, it will be terminated.
-                    transaction_gas_used += function.gas();
-                    let (success_func, data_func) = self.execute_function(function, &receipt);
-                    if let Some(data_func) = data_func {
-                        data = data_func;
+                let mut function_results = Vec::new();
+                for (index, function) in transaction.functions.iter().enumerate() {
+                    let function_gas_used = transaction.function_gas(index);
+                    transaction_gas_used += function_gas_used;
+                    let (success_func, data_func) =
+                        self.execute_function(function, transaction.function_hash(index), &receipt);
+                    if let Some(data_func) = &data_func {
+                        data = data_func.clone();
+                    }
+                    function_results.push((
+                        success_func,
+                        data_func.unwrap_or_default(),
+                        function_gas_used,
+                    ));
+                    success &= success_func;
+                    // If there is an illegal function, the whole batch is terminated.
+                    if !success_func && all_or_nothing {
+                        break;
+                    }
+                }
+
+                if let Some(undo_log) = self.undo_log.take() {
+                    if !success {
+                        self.revert(undo_log);
+                        // No function takes effect, but the gas of the executed ones is consumed
+                        function_results = (0..transaction.functions.len())
+                            .map(|index| {
+                                let gas_used = function_results
+                                    .get(index)
+                                    .map_or(0, |&(_, _, gas_used)| gas_used);
+                                (false, Data::new(), gas_used)
+                            })
+                            .collect();
                     }
-                    success = success_func;
                 }
 
                 if success {
@@ -209,9 +550,15 @@ impl Shard {
                         TransactionExecutionResult::Success,
                         data,
                         transaction_gas_used,
+                        function_results,
                     )
                 } else {
-                    (TransactionExecutionResult::Fail, data, transaction_gas_used)
+                    (
+                        TransactionExecutionResult::Fail,
+                        data,
+                        transaction_gas_used,
+                        function_results,
+                    )
                 }
             }
         }
@@ -220,8 +567,14 @@ impl Shard {
     fn execute_function(
         &mut self,
         function: &Function,
+        transaction_hash: TransactionHash,
         receipt: &Option<Receipt>,
     ) -> (bool, Option<Data>) {
+        let slot = self.blocks.len() as Slot;
+        // Data shards only carry batches, and only data shards do
+        if (self.kind == ShardKind::Data) != (function.ftype == FunctionType::PostBatch) {
+            return (false, None);
+        }
         let mut data = None;
         if function.ftype == FunctionType::Transfer {
             if !self.accounts.contains_key(&function.source)
@@ -229,6 +582,7 @@ impl Shard {
             {
                 return (false, None);
             }
+            self.call_contract(function.source, function.target);
         } else if function.ftype == FunctionType::CreateCrossTransfer {
             if !self.accounts.contains_key(&function.target) {
                 return (false, None);
@@ -237,14 +591,61 @@ impl Shard {
             if function.source != function.target {
                 return (false, None);
             }
+            self.log_outgoing_transfer(transaction_hash);
+            self.outgoing_transfers.insert(transaction_hash);
         } else if function.ftype == FunctionType::ApplyCrossTransfer {
+            if let Some(receipt) = receipt {
+                if receipt.is_expired(slot)
+                    || self.used_receipts.contains(&receipt.transaction_hash)
+                {
+                    return (false, None);
+                }
+                self.use_receipt(receipt.transaction_hash);
+            }
+            self.call_contract(function.source, function.target);
         } else if function.ftype == FunctionType::CreateCrossTransferAll {
-            if !self.accounts.contains_key(&function.target) {
+            let target_shard_id = match function.parse_calldata() {
+                Ok(Calldata::ShardId(shard_id)) if shard_id != self.id => shard_id,
+                _ => return (false, None),
+            };
+            match self.accounts.get(&function.target) {
+                // Contract accounts are immovable
+                Some(account) if !account.is_contract() => {}
+                _ => return (false, None),
+            }
+            // Locked funds must be committed or refunded first
+            if self
+                .locks
+                .values()
+                .any(|lock| lock.owner == function.target)
+            {
                 return (false, None);
             }
 
-            data = Some(self.move_account(function.target));
-        } else if function.ftype == FunctionType::ApplyCrossTransferAll {
+            data = Some(self.move_account(function.target, target_shard_id));
+        } else if function.ftype == FunctionType::YankAccount {
+            let target_shard_id = match function.parse_calldata() {
+                Ok(Calldata::ShardId(shard_id)) if shard_id != self.id => shard_id,
+                _ => return (false, None),
+            };
+            match self.accounts.get(&function.target) {
+                Some(account) if !account.is_contract() && function.source == function.target => {}
+                _ => return (false, None),
+            }
+            if self
+                .locks
+                .values()
+                .any(|lock| lock.owner == function.target)
+            {
+                return (false, None);
+            }
+
+            data = Some(self.move_account(function.target, target_shard_id));
+            self.yanked_accounts.insert(function.target);
+        } else if function.ftype == FunctionType::PullAccount
+            || function.ftype == FunctionType::ApplyCrossTransferAll
+        {
+            // The receipt of a move or a yank, whose account designates the shard that takes it in
             if receipt.is_none() {
                 return (false, None);
             }
@@ -254,17 +655,1140 @@ impl Shard {
                 return (false, None);
             }
 
-            let data = receipt.data;
-            let account: Account = match serde_json::from_str(&data) {
-                Ok(res) => res,
-                Err(_) => {
-                    unreachable!();
-                }
+            let account: Account = match serde_json::from_str(&receipt.data) {
+                Ok(account) => account,
+                Err(_) => return (false, None),
             };
+            if account.addr != function.target || account.shard_id != self.id {
+                return (false, None);
+            }
+            self.use_receipt(receipt.transaction_hash);
             self.insert_account(account);
+        } else if function.ftype == FunctionType::PostBatch {
+            if !self.accounts.contains_key(&function.source) || function.parse_calldata().is_err() {
+                return (false, None);
+            }
+        } else if function.ftype == FunctionType::Mint {
+            let (token, amount) = match function.parse_calldata() {
+                Ok(Calldata::Token { token, amount }) => (token, amount),
+                _ => return (false, None),
+            };
+            self.log_token_contract(token);
+            match self.token_contracts.get_mut(&token) {
+                Some(token_contract) if token_contract.owner == function.source => {
+                    if !self.accounts.contains_key(&function.target) {
+                        return (false, None);
+                    }
+                    token_contract.total_supply += amount;
+                }
+                _ => return (false, None),
+            }
+            self.credit_token(function.target, token, amount);
+        } else if function.ftype == FunctionType::TokenTransfer {
+            let (token, amount) = match function.parse_calldata() {
+                Ok(Calldata::Token { token, amount }) => (token, amount),
+                _ => return (false, None),
+            };
+            if !self.accounts.contains_key(&function.target)
+                || !self.debit_token(function.source, token, amount)
+            {
+                return (false, None);
+            }
+            self.credit_token(function.target, token, amount);
+        } else if function.ftype == FunctionType::CreateCrossTokenTransfer {
+            let (token, amount) = match function.parse_calldata() {
+                Ok(Calldata::Token { token, amount }) => (token, amount),
+                _ => return (false, None),
+            };
+            if !self.debit_token(function.source, token, amount) {
+                return (false, None);
+            }
+            self.log_outgoing_transfer(transaction_hash);
+            self.outgoing_transfers.insert(transaction_hash);
+            data = Some(function.calldata.clone());
+        } else if function.ftype == FunctionType::ApplyCrossTokenTransfer {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if receipt.is_expired(slot) || self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            let (token, amount) =
+                match Calldata::parse(&FunctionType::ApplyCrossTokenTransfer, &receipt.data) {
+                    Ok(Calldata::Token { token, amount }) => (token, amount),
+                    _ => return (false, None),
+                };
+            // Only the recipient of the transfer can be credited
+            if receipt.to != function.target || !self.accounts.contains_key(&function.target) {
+                return (false, None);
+            }
+            self.use_receipt(receipt.transaction_hash);
+            self.credit_token(function.target, token, amount);
+        } else if function.ftype == FunctionType::CreateCrossCall {
+            match function.parse_calldata() {
+                Ok(Calldata::Call {
+                    origin_shard_id, ..
+                }) if origin_shard_id == self.id => {}
+                _ => return (false, None),
+            }
+            if !self.accounts.contains_key(&function.source) {
+                return (false, None);
+            }
+            data = Some(function.calldata.clone());
+        } else if function.ftype == FunctionType::ApplyCrossCall {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            let (contract, origin_shard_id) =
+                match Calldata::parse(&FunctionType::ApplyCrossCall, &receipt.data) {
+                    Ok(Calldata::Call {
+                        contract,
+                        origin_shard_id,
+                    }) => (contract, origin_shard_id),
+                    _ => return (false, None),
+                };
+            let result = match self.accounts.get(&contract) {
+                Some(account) if account.is_contract() => {
+                    self.call_contract(function.source, contract);
+                    let account = self.accounts.get(&contract).unwrap();
+                    if account.storage.is_empty() {
+                        0
+                    } else {
+                        let key = function.source as u64 % account.storage.len() as u64;
+                        *account.storage.get(&key).unwrap_or(&0)
+                    }
+                }
+                _ => return (false, None),
+            };
+            self.use_receipt(receipt.transaction_hash);
+            data = Some(
+                Calldata::CallResult {
+                    origin_shard_id,
+                    result,
+                }
+                .encode(),
+            );
+        } else if function.ftype == FunctionType::ApplyCallback {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            match Calldata::parse(&FunctionType::ApplyCallback, &receipt.data) {
+                Ok(Calldata::CallResult {
+                    origin_shard_id, ..
+                }) if origin_shard_id == self.id => {}
+                _ => return (false, None),
+            }
+            if !self.accounts.contains_key(&function.source) {
+                return (false, None);
+            }
+            self.use_receipt(receipt.transaction_hash);
+        } else if function.ftype == FunctionType::LockCrossTransfer {
+            let (amount, deadline) = match function.parse_calldata() {
+                Ok(Calldata::Lock { amount, deadline }) => (amount, deadline),
+                _ => return (false, None),
+            };
+            self.log_account(function.source);
+            match self.accounts.get_mut(&function.source) {
+                Some(account) if account.balance >= amount => account.balance -= amount,
+                _ => return (false, None),
+            }
+            self.log_lock(transaction_hash);
+            self.locks.insert(
+                transaction_hash,
+                CrossTransferLock::new(function.source, amount, deadline),
+            );
+            data = Some(function.calldata.clone());
+        } else if function.ftype == FunctionType::ConfirmCrossTransfer {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            let deadline = match Calldata::parse(&FunctionType::ConfirmCrossTransfer, &receipt.data)
+            {
+                Ok(Calldata::Lock { deadline, .. }) => deadline,
+                _ => return (false, None),
+            };
+            self.use_receipt(receipt.transaction_hash);
+            // Vote to abort if the deadline has passed or the recipient is not on this shard
+            let commit = slot <= deadline && self.accounts.contains_key(&function.target);
+            data = Some(
+                Calldata::Vote {
+                    lock_hash: receipt.transaction_hash,
+                    commit,
+                }
+                .encode(),
+            );
+        } else if function.ftype == FunctionType::CommitCrossTransfer {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            let (lock_hash, vote) =
+                match Calldata::parse(&FunctionType::CommitCrossTransfer, &receipt.data) {
+                    Ok(Calldata::Vote { lock_hash, commit }) => (lock_hash, commit),
+                    _ => return (false, None),
+                };
+            let lock = match self.locks.get(&lock_hash) {
+                Some(lock) if lock.owner == function.source => lock.clone(),
+                _ => return (false, None),
+            };
+            self.use_receipt(receipt.transaction_hash);
+            self.log_lock(lock_hash);
+            self.locks.remove(&lock_hash);
+            // Too late to commit, so refund
+            let commit = vote && slot <= lock.deadline;
+            if !commit {
+                self.refund(&lock);
+            }
+            data = Some(
+                Calldata::Decision {
+                    commit,
+                    amount: lock.amount,
+                }
+                .encode(),
+            );
+        } else if function.ftype == FunctionType::AbortCrossTransfer {
+            // The receipt of the locking transaction
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let lock_hash = receipt.clone().unwrap().transaction_hash;
+            let lock = match self.locks.get(&lock_hash) {
+                Some(lock) if lock.owner == function.source && slot > lock.deadline => lock.clone(),
+                _ => return (false, None),
+            };
+            self.log_lock(lock_hash);
+            self.locks.remove(&lock_hash);
+            self.refund(&lock);
+            data = Some(
+                Calldata::Decision {
+                    commit: false,
+                    amount: lock.amount,
+                }
+                .encode(),
+            );
+        } else if function.ftype == FunctionType::FinalizeCrossTransfer {
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if self.used_receipts.contains(&receipt.transaction_hash) {
+                return (false, None);
+            }
+
+            let amount = match Calldata::parse(&FunctionType::FinalizeCrossTransfer, &receipt.data)
+            {
+                Ok(Calldata::Decision {
+                    commit: true,
+                    amount,
+                }) => amount,
+                _ => return (false, None),
+            };
+            self.log_account(function.target);
+            match self.accounts.get_mut(&function.target) {
+                Some(account) => account.balance += amount,
+                None => return (false, None),
+            }
+            self.use_receipt(receipt.transaction_hash);
+        } else if function.ftype == FunctionType::ReclaimCrossTransfer {
+            // The receipt of the transfer, which the destination shard no longer accepts.
+            // It is assumed that the sender proves that the destination shard did not consume it,
+            // which is not simulated.
+            if receipt.is_none() {
+                return (false, None);
+            }
+
+            let receipt = receipt.clone().unwrap();
+            if !self.outgoing_transfers.contains(&receipt.transaction_hash)
+                || !receipt.status
+                || receipt.from != function.source
+                || !receipt.is_expired(slot)
+                || !self.accounts.contains_key(&function.source)
+            {
+                return (false, None);
+            }
+
+            match Calldata::parse(&FunctionType::ReclaimCrossTransfer, &receipt.data) {
+                Ok(Calldata::Token { token, amount }) => {
+                    self.credit_token(function.source, token, amount)
+                }
+                Ok(_) => {}
+                Err(_) => return (false, None),
+            }
+            self.log_outgoing_transfer(receipt.transaction_hash);
+            self.outgoing_transfers.remove(&receipt.transaction_hash);
         } else {
             return (false, None);
         }
         (true, data)
     }
+
+    /// A transfer to a contract account calls it, which writes to one of its storage slots.
+    fn call_contract(&mut self, caller: Address, addr: Address) {
+        self.log_account(addr);
+        if let Some(account) = self.accounts.get_mut(&addr) {
+            if account.is_contract() && !account.storage.is_empty() {
+                let key = caller as u64 % account.storage.len() as u64;
+                *account.storage.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Mark the receipt as used, so that it cannot be applied again.
+    fn use_receipt(&mut self, receipt_hash: TransactionHash) {
+        if let Some(undo_log) = &mut self.undo_log {
+            let used_receipts = &self.used_receipts;
+            undo_log
+                .used_receipts
+                .entry(receipt_hash)
+                .or_insert_with(|| used_receipts.contains(&receipt_hash));
+        }
+        self.used_receipts.insert(receipt_hash);
+    }
+
+    /// Log the account, including its moving and yanked states, before it is modified.
+    fn log_account(&mut self, addr: Address) {
+        if let Some(undo_log) = &mut self.undo_log {
+            let (accounts, moving_accounts, yanked_accounts) =
+                (&self.accounts, &self.moving_accounts, &self.yanked_accounts);
+            undo_log
+                .accounts
+                .entry(addr)
+                .or_insert_with(|| accounts.get(&addr).cloned());
+            undo_log
+                .moving_accounts
+                .entry(addr)
+                .or_insert_with(|| moving_accounts.get(&addr).cloned());
+            undo_log
+                .yanked_accounts
+                .entry(addr)
+                .or_insert_with(|| yanked_accounts.contains(&addr));
+        }
+    }
+
+    fn log_token_contract(&mut self, token: Address) {
+        if let Some(undo_log) = &mut self.undo_log {
+            let token_contracts = &self.token_contracts;
+            undo_log
+                .token_contracts
+                .entry(token)
+                .or_insert_with(|| token_contracts.get(&token).cloned());
+        }
+    }
+
+    fn log_lock(&mut self, lock_hash: TransactionHash) {
+        if let Some(undo_log) = &mut self.undo_log {
+            let locks = &self.locks;
+            undo_log
+                .locks
+                .entry(lock_hash)
+                .or_insert_with(|| locks.get(&lock_hash).cloned());
+        }
+    }
+
+    fn log_outgoing_transfer(&mut self, transaction_hash: TransactionHash) {
+        if let Some(undo_log) = &mut self.undo_log {
+            let outgoing_transfers = &self.outgoing_transfers;
+            undo_log
+                .outgoing_transfers
+                .entry(transaction_hash)
+                .or_insert_with(|| outgoing_transfers.contains(&transaction_hash));
+        }
+    }
+
+    /// Put back the logged entries as they were before the transaction.
+    fn revert(&mut self, undo_log: ShardUndoLog) {
+        fn revert_map<K: Eq + std::hash::Hash, V>(
+            map: &mut HashMap<K, V>,
+            log: HashMap<K, Option<V>>,
+        ) {
+            for (key, value) in log {
+                match value {
+                    Some(value) => map.insert(key, value),
+                    None => map.remove(&key),
+                };
+            }
+        }
+        fn revert_set<K: Eq + std::hash::Hash>(set: &mut HashSet<K>, log: HashMap<K, bool>) {
+            for (key, contained) in log {
+                if contained {
+                    set.insert(key);
+                } else {
+                    set.remove(&key);
+                }
+            }
+        }
+        revert_map(&mut self.accounts, undo_log.accounts);
+        revert_map(&mut self.moving_accounts, undo_log.moving_accounts);
+        revert_set(&mut self.yanked_accounts, undo_log.yanked_accounts);
+        revert_map(&mut self.token_contracts, undo_log.token_contracts);
+        revert_map(&mut self.locks, undo_log.locks);
+        revert_set(&mut self.outgoing_transfers, undo_log.outgoing_transfers);
+        revert_set(&mut self.used_receipts, undo_log.used_receipts);
+    }
+
+    /// Return locked funds to the owner, who cannot move while the funds are locked.
+    fn refund(&mut self, lock: &CrossTransferLock) {
+        self.log_account(lock.owner);
+        let account = self
+            .accounts
+            .get_mut(&lock.owner)
+            .expect("the account does not exist");
+        account.balance += lock.amount;
+    }
+
+    fn credit_token(&mut self, addr: Address, token: Address, amount: Wei) {
+        self.log_account(addr);
+        let account = self
+            .accounts
+            .get_mut(&addr)
+            .expect("the account does not exist");
+        *account.token_balances.entry(token).or_insert(0) += amount;
+    }
+
+    /// Returns false if the account does not have enough tokens.
+    fn debit_token(&mut self, addr: Address, token: Address, amount: Wei) -> bool {
+        self.log_account(addr);
+        match self.accounts.get_mut(&addr) {
+            Some(account) if account.get_token_balance(token) >= amount => {
+                *account.token_balances.entry(token).or_insert(0) -= amount;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: Address = 100;
+
+    fn shard_with_accounts(shard_id: usize, addrs: &[Address]) -> Shard {
+        let mut shard = Shard::new(shard_id);
+        for &addr in addrs {
+            let mut account = Account::new(addr, shard.id);
+            account.token_balances.insert(TOKEN, 10);
+            shard.accounts.insert(addr, account);
+        }
+        shard
+    }
+
+    fn function(
+        source: Address,
+        target: Address,
+        ftype: FunctionType,
+        calldata: String,
+    ) -> Function {
+        Function {
+            source,
+            target,
+            ftype,
+            calldata,
+        }
+    }
+
+    fn token_transfer(source: Address, target: Address, amount: Wei) -> Function {
+        function(
+            source,
+            target,
+            FunctionType::TokenTransfer,
+            Calldata::Token {
+                token: TOKEN,
+                amount,
+            }
+            .encode(),
+        )
+    }
+
+    /// Include a single-function transaction in a block and return its receipt.
+    fn execute(
+        shard: &mut Shard,
+        from: Address,
+        nonce: Nonce,
+        function: Function,
+        receipt: Option<&Receipt>,
+    ) -> Receipt {
+        let transaction = Transaction::new(
+            from,
+            function.target,
+            shard.id,
+            vec![function],
+            0,
+            INITIAL_BASE_FEE * 10,
+            nonce,
+        );
+        shard.push_transaction(transaction.clone(), receipt.cloned());
+        shard.process_slot();
+        shard.receipts[&transaction.hash].clone()
+    }
+
+    fn skip_slots_until(shards: &mut [&mut Shard], slot: Slot) {
+        for shard in shards.iter_mut() {
+            while (shard.blocks.len() as Slot) < slot {
+                shard.skip_slot();
+            }
+        }
+    }
+
+    fn batch(functions: Vec<Function>, batch_semantics: BatchSemantics) -> Transaction {
+        let mut transaction = Transaction::new(0, 1, 0, functions, 0, INITIAL_BASE_FEE * 10, 0);
+        transaction.batch_semantics = batch_semantics;
+        transaction
+    }
+
+    fn transfer(from: Address, to: Address, gas_premium: GasPrice) -> Transaction {
+        Transaction::new(
+            from,
+            to,
+            0,
+            vec![function(from, to, FunctionType::Transfer, "".to_string())],
+            gas_premium,
+            INITIAL_BASE_FEE * 10,
+            0,
+        )
+    }
+
+    #[test]
+    fn skipped_slot_carries_the_mempool_and_the_base_fee_over() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.push_transaction(transfer(0, 1, 0), None);
+        shard.skip_slot();
+
+        assert!(!shard.blocks[0].proposed);
+        assert!(shard.blocks[0].executed_transactions.is_empty());
+        assert_eq!(shard.mempool.len(), 1);
+        assert_eq!(shard.get_base_fee(), INITIAL_BASE_FEE);
+
+        shard.process_slot();
+        assert!(shard.blocks[1].proposed);
+        assert_eq!(shard.blocks[1].executed_transactions.len(), 1);
+        assert!(shard.mempool.is_empty());
+    }
+
+    #[test]
+    fn censoring_proposer_leaves_applies_in_the_mempool() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.proposer_behavior = ProposerBehavior::CensorCrossShardApply;
+        let apply = Transaction::new(
+            0,
+            1,
+            0,
+            vec![function(
+                0,
+                1,
+                FunctionType::ApplyCrossTransfer,
+                "".to_string(),
+            )],
+            0,
+            INITIAL_BASE_FEE * 10,
+            0,
+        );
+        shard.push_transaction(apply.clone(), None);
+        shard.push_transaction(transfer(1, 0, 0), None);
+        shard.process_slot();
+
+        let block = shard.blocks.last().unwrap();
+        assert_eq!(block.censored_transaction_num, 1);
+        assert_eq!(block.executed_transactions.len(), 1);
+        assert_eq!(block.executed_transactions[0].from, 1);
+        assert_eq!(shard.mempool.len(), 1);
+        assert_eq!(shard.mempool[0].0.hash, apply.hash);
+    }
+
+    #[test]
+    fn empty_block_proposer_includes_nothing_and_lowers_the_base_fee() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.proposer_behavior = ProposerBehavior::EmptyBlock;
+        shard.push_transaction(transfer(0, 1, 0), None);
+        shard.process_slot();
+
+        assert!(shard
+            .blocks
+            .last()
+            .unwrap()
+            .executed_transactions
+            .is_empty());
+        assert_eq!(shard.mempool.len(), 1);
+        assert!(shard.get_base_fee() < INITIAL_BASE_FEE);
+    }
+
+    #[test]
+    fn base_fee_stuffing_fills_the_block_and_raises_the_base_fee() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.proposer_behavior = ProposerBehavior::BaseFeeStuffing;
+        let gas_limit = shard.get_gas_limit();
+        shard.push_transaction(transfer(0, 1, 0), None);
+        shard.process_slot();
+
+        let block = shard.blocks.last().unwrap();
+        assert_eq!(block.executed_transactions.len(), 1);
+        assert_eq!(block.gas_used, gas_limit);
+        assert_eq!(block.stuffed_gas, gas_limit - GAS_TRANSFER);
+        assert!(shard.get_base_fee() > INITIAL_BASE_FEE);
+    }
+
+    #[test]
+    fn self_preferencing_proposer_includes_its_senders_first() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.proposer_behavior = ProposerBehavior::SelfPreferencing;
+        shard.proposer_senders.insert(1);
+        shard.push_transaction(transfer(0, 1, INITIAL_BASE_FEE), None);
+        shard.push_transaction(transfer(1, 0, 0), None);
+        shard.process_slot();
+
+        let senders = shard
+            .blocks
+            .last()
+            .unwrap()
+            .executed_transactions
+            .iter()
+            .map(|transaction| transaction.from)
+            .collect::<Vec<_>>();
+        assert_eq!(senders, vec![1, 0]);
+    }
+
+    #[test]
+    fn sponsored_transaction_without_a_valid_fee_payer_fails() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        // One pays for itself, the other is paid for by an account on no shard
+        let self_sponsored = transfer(0, 1, 0).into_sponsored(0);
+        let unknown_sponsor = transfer(1, 0, 0).into_sponsored(7);
+        shard.push_transaction(self_sponsored.clone(), None);
+        shard.push_transaction(unknown_sponsor.clone(), None);
+        shard.process_slot();
+
+        assert!(shard.mempool.is_empty());
+        for transaction in [&self_sponsored, &unknown_sponsor] {
+            let receipt = &shard.receipts[&transaction.hash];
+            assert!(!receipt.status);
+            assert_eq!(receipt.gas_used, 0);
+            assert_eq!(shard.function_fee(0, transaction, 0), (0, 0));
+        }
+        assert_eq!(shard.accounts[&1].balance, Account::new(1, 0).balance);
+    }
+
+    #[test]
+    fn cross_call_returns_its_result_to_the_callback_once() {
+        const CONTRACT: Address = 5;
+        let mut origin = shard_with_accounts(0, &[0]);
+        let mut contract_shard = shard_with_accounts(1, &[1]);
+        contract_shard
+            .accounts
+            .insert(CONTRACT, Account::new_contract(CONTRACT, 1, 4));
+
+        let call = Calldata::Call {
+            contract: CONTRACT,
+            origin_shard_id: 0,
+        }
+        .encode();
+        let create_receipt = execute(
+            &mut origin,
+            0,
+            0,
+            function(0, CONTRACT, FunctionType::CreateCrossCall, call),
+            None,
+        );
+        assert!(create_receipt.status);
+
+        skip_slots_until(&mut [&mut contract_shard], 1);
+        let apply = function(0, CONTRACT, FunctionType::ApplyCrossCall, "".to_string());
+        let apply_receipt = execute(&mut contract_shard, 0, 0, apply, Some(&create_receipt));
+        assert!(apply_receipt.status);
+        assert_eq!(
+            Calldata::parse(&FunctionType::ApplyCallback, &apply_receipt.data),
+            Ok(Calldata::CallResult {
+                origin_shard_id: 0,
+                result: 1,
+            })
+        );
+
+        // The result goes back only to the origin shard
+        let callback = function(0, 0, FunctionType::ApplyCallback, "".to_string());
+        assert!(
+            !execute(
+                &mut contract_shard,
+                1,
+                0,
+                callback.clone(),
+                Some(&apply_receipt)
+            )
+            .status
+        );
+
+        skip_slots_until(&mut [&mut origin], 2);
+        assert!(execute(&mut origin, 0, 1, callback.clone(), Some(&apply_receipt)).status);
+        assert!(!execute(&mut origin, 0, 2, callback, Some(&apply_receipt)).status);
+    }
+
+    fn shard_id_function(addr: Address, ftype: FunctionType, shard_id: usize) -> Function {
+        function(addr, addr, ftype, Calldata::ShardId(shard_id).encode())
+    }
+
+    #[test]
+    fn moved_account_is_applied_once_only_on_its_target_shard() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let mut destination = shard_with_accounts(1, &[1]);
+        let mut other = shard_with_accounts(2, &[2]);
+
+        let to_itself = shard_id_function(0, FunctionType::CreateCrossTransferAll, 0);
+        assert!(!execute(&mut source, 0, 0, to_itself, None).status);
+        let create = shard_id_function(0, FunctionType::CreateCrossTransferAll, 1);
+        let create_receipt = execute(&mut source, 0, 1, create, None);
+        assert!(create_receipt.status);
+        assert!(!source.accounts.contains_key(&0));
+
+        skip_slots_until(&mut [&mut destination, &mut other], 2);
+        let apply = shard_id_function(0, FunctionType::ApplyCrossTransferAll, 0);
+        assert!(!execute(&mut other, 0, 0, apply.clone(), Some(&create_receipt)).status);
+        assert!(!other.accounts.contains_key(&0));
+        let other_target = function(
+            0,
+            1,
+            FunctionType::ApplyCrossTransferAll,
+            Calldata::ShardId(0).encode(),
+        );
+        assert!(!execute(&mut destination, 0, 0, other_target, Some(&create_receipt)).status);
+
+        assert!(execute(&mut destination, 0, 1, apply.clone(), Some(&create_receipt)).status);
+        assert_eq!(destination.accounts[&0].shard_id, 1);
+        assert_eq!(
+            destination.accounts[&0].balance,
+            source.moving_accounts[&0].balance
+        );
+        assert!(!execute(&mut destination, 0, 2, apply, Some(&create_receipt)).status);
+    }
+
+    #[test]
+    fn yanked_account_is_pulled_once_only_into_its_designated_shard() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let mut destination = shard_with_accounts(1, &[1]);
+        let mut other = shard_with_accounts(2, &[2]);
+
+        let yank = shard_id_function(0, FunctionType::YankAccount, 1);
+        let yank_receipt = execute(&mut source, 0, 0, yank, None);
+        assert!(yank_receipt.status);
+        assert!(source.yanked_accounts.contains(&0));
+
+        skip_slots_until(&mut [&mut destination, &mut other], 1);
+        let pull = function(0, 0, FunctionType::PullAccount, "".to_string());
+        assert!(!execute(&mut other, 2, 0, pull.clone(), Some(&yank_receipt)).status);
+        // A yank receipt cannot bypass the checks as the receipt of a move either
+        let apply = shard_id_function(0, FunctionType::ApplyCrossTransferAll, 0);
+        assert!(!execute(&mut other, 2, 1, apply.clone(), Some(&yank_receipt)).status);
+        assert!(!other.accounts.contains_key(&0));
+
+        // Anyone can pull it into the designated shard, but only once
+        assert!(execute(&mut destination, 1, 0, pull, Some(&yank_receipt)).status);
+        assert_eq!(destination.accounts[&0].shard_id, 1);
+        assert!(!execute(&mut destination, 1, 1, apply, Some(&yank_receipt)).status);
+    }
+
+    /// A shard with a rich account 0 and a poor account 1, and the rent of an account.
+    fn shard_with_rent(state_rent: StateRent) -> (Shard, Wei) {
+        let mut shard = Shard::new(0);
+        shard.state_rent = StateRent::Evict;
+        for addr in [0, 1] {
+            shard.accounts.insert(addr, Account::new(addr, 0));
+        }
+        let rent = shard.estimate_rent(&shard.accounts[&0]);
+        shard.accounts.get_mut(&0).unwrap().balance = rent * 10;
+        shard.accounts.get_mut(&1).unwrap().balance = rent - 1;
+        shard.state_rent = state_rent;
+        (shard, rent)
+    }
+
+    #[test]
+    fn rent_is_priced_by_the_shard_state_size() {
+        let (_, rent) = shard_with_rent(StateRent::Evict);
+        assert_eq!(
+            rent,
+            ACCOUNT_STATE_SIZE as Wei * RENT_PER_BYTE_PER_EPOCH * 2 * ACCOUNT_STATE_SIZE as Wei
+                / TARGET_SHARD_STATE_SIZE as Wei
+        );
+        let (free_shard, _) = shard_with_rent(StateRent::Free);
+        assert_eq!(free_shard.estimate_rent(&free_shard.accounts[&0]), 0);
+    }
+
+    #[test]
+    fn accounts_that_cannot_pay_the_rent_are_evicted() {
+        let (mut shard, rent) = shard_with_rent(StateRent::Evict);
+        shard.collect_rent();
+
+        assert_eq!(shard.accounts[&0].balance, rent * 9);
+        assert!(!shard.accounts.contains_key(&1));
+        assert!(shard.hibernated_accounts.is_empty());
+        assert_eq!(shard.evicted_account_num, 1);
+    }
+
+    #[test]
+    fn accounts_that_cannot_pay_the_rent_hibernate() {
+        let (mut shard, rent) = shard_with_rent(StateRent::Hibernate);
+        shard.collect_rent();
+
+        assert_eq!(shard.accounts[&0].balance, rent * 9);
+        assert!(!shard.accounts.contains_key(&1));
+        assert_eq!(shard.hibernated_accounts[&1].balance, rent - 1);
+        assert_eq!(shard.evicted_account_num, 1);
+    }
+
+    #[test]
+    fn free_state_charges_no_rent() {
+        let (mut shard, rent) = shard_with_rent(StateRent::Free);
+        shard.collect_rent();
+
+        assert_eq!(shard.accounts[&0].balance, rent * 10);
+        assert_eq!(shard.accounts[&1].balance, rent - 1);
+        assert_eq!(shard.evicted_account_num, 0);
+    }
+
+    /// Include a transaction of a single function on a data shard and return its receipt.
+    fn execute_on_data_shard(shard: &mut Shard, nonce: Nonce, function: Function) -> Receipt {
+        let transaction = Transaction::new(
+            0,
+            function.target,
+            shard.id,
+            vec![function],
+            0,
+            INITIAL_DATA_BASE_FEE * 2,
+            nonce,
+        );
+        shard.push_transaction(transaction.clone(), None);
+        shard.process_slot();
+        shard.receipts[&transaction.hash].clone()
+    }
+
+    #[test]
+    fn data_shard_carries_only_batches_priced_by_their_size() {
+        let mut data_shard = Shard::new(1);
+        data_shard.set_kind(ShardKind::Data);
+        data_shard.accounts.insert(0, Account::new(0, 1));
+        assert_eq!(data_shard.get_base_fee(), INITIAL_DATA_BASE_FEE);
+        assert_eq!(data_shard.get_gas_target(), BLOCK_DATA_TARGET);
+
+        let post_batch = |calldata: String| function(0, 0, FunctionType::PostBatch, calldata);
+        let batch = Calldata::Batch {
+            rollup_id: 0,
+            size: 1_000,
+        }
+        .encode();
+        let receipt = execute_on_data_shard(&mut data_shard, 0, post_batch(batch.clone()));
+        assert!(receipt.status);
+        assert_eq!(receipt.gas_used, BATCH_HEADER_SIZE + 1_000);
+        assert_eq!(data_shard.blocks[0].gas_used, BATCH_HEADER_SIZE + 1_000);
+
+        assert!(!execute_on_data_shard(&mut data_shard, 1, post_batch("1".to_string())).status);
+        let transfer = function(0, 0, FunctionType::Transfer, "".to_string());
+        assert!(!execute_on_data_shard(&mut data_shard, 2, transfer).status);
+
+        // Execution shards do not carry batches
+        let mut execution_shard = shard_with_accounts(0, &[0]);
+        assert!(!execute(&mut execution_shard, 0, 0, post_batch(batch), None).status);
+    }
+
+    #[test]
+    fn all_or_nothing_batch_rolls_back_and_charges_only_executed_functions() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        let transaction = batch(
+            vec![
+                token_transfer(0, 1, 3),
+                function(0, 0, FunctionType::CreateCrossTransfer, "".to_string()),
+                token_transfer(0, 1, 100),
+                token_transfer(0, 1, 1),
+            ],
+            BatchSemantics::AllOrNothing,
+        );
+        shard.push_transaction(transaction.clone(), None);
+        shard.process_slot();
+
+        assert_eq!(shard.accounts[&0].get_token_balance(TOKEN), 10);
+        assert_eq!(shard.accounts[&1].get_token_balance(TOKEN), 10);
+        assert!(!shard
+            .outgoing_transfers
+            .contains(&transaction.function_hash(1)));
+
+        let receipt = &shard.receipts[&transaction.hash];
+        assert!(!receipt.status);
+        let function_receipts = (0..4)
+            .map(|index| &shard.receipts[&transaction.function_hash(index)])
+            .collect::<Vec<_>>();
+        assert!(function_receipts.iter().all(|receipt| !receipt.status));
+        for (index, function_receipt) in function_receipts.iter().enumerate().take(3) {
+            assert_eq!(function_receipt.gas_used, transaction.function_gas(index));
+        }
+        // The last function never ran after the failing one
+        assert_eq!(function_receipts[3].gas_used, 0);
+        assert_eq!(
+            receipt.gas_used,
+            (0..3)
+                .map(|index| transaction.function_gas(index))
+                .sum::<Gas>()
+        );
+    }
+
+    #[test]
+    fn function_fee_is_charged_by_the_gas_in_the_receipt() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        let transaction = batch(
+            vec![
+                token_transfer(0, 1, 3),
+                token_transfer(0, 1, 100),
+                token_transfer(0, 1, 1),
+            ],
+            BatchSemantics::AllOrNothing,
+        );
+        shard.push_transaction(transaction.clone(), None);
+        shard.process_slot();
+
+        let slot = shard.receipts[&transaction.hash].slot_number;
+        let base_fee = shard.states[slot as usize].base_fee;
+        let tip = transaction.tip(base_fee);
+        for index in 0..2 {
+            let gas = transaction.function_gas(index);
+            assert_eq!(
+                shard.function_fee(slot, &transaction, index),
+                (base_fee * gas, tip * gas)
+            );
+        }
+        // The last function never ran after the failing one
+        assert_eq!(shard.function_fee(slot, &transaction, 2), (0, 0));
+
+        // A replayed nonce fails without using gas
+        let replayed = Transaction::new(
+            0,
+            1,
+            0,
+            vec![token_transfer(0, 1, 1)],
+            0,
+            INITIAL_BASE_FEE * 10,
+            0,
+        );
+        let receipt = execute(&mut shard, 0, 0, token_transfer(0, 1, 1), None);
+        assert!(!receipt.status);
+        assert_eq!(
+            shard.function_fee(receipt.slot_number, &replayed, 0),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn failed_batch_uses_the_resources_of_the_executed_functions_only() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        shard.fee_market = FeeMarket::MultiDimensional;
+        let transaction = batch(
+            vec![
+                token_transfer(0, 1, 3),
+                function(0, 0, FunctionType::CreateCrossTransfer, "".to_string()),
+                token_transfer(0, 1, 100),
+                function(0, 0, FunctionType::CreateCrossTransfer, "".to_string()),
+            ],
+            BatchSemantics::AllOrNothing,
+        );
+        shard.push_transaction(transaction.clone(), None);
+        shard.process_slot();
+
+        let executed = (0..3)
+            .map(|index| transaction.function_gas_by_resource(index))
+            .sum::<Resources>();
+        let block = shard.blocks.last().unwrap();
+        assert_eq!(block.gas_used, executed.execution);
+        assert_eq!(block.receipt_out_gas_used, GAS_RECEIPT_OUT_CROSS_TRANSFER);
+        assert_eq!(block.receipt_in_gas_used, 0);
+    }
+
+    #[test]
+    fn best_effort_batch_keeps_the_successful_functions() {
+        let mut shard = shard_with_accounts(0, &[0, 1]);
+        let transaction = batch(
+            vec![
+                token_transfer(0, 1, 3),
+                token_transfer(0, 1, 100),
+                token_transfer(0, 1, 1),
+            ],
+            BatchSemantics::BestEffort,
+        );
+        shard.push_transaction(transaction.clone(), None);
+        shard.process_slot();
+
+        assert_eq!(shard.accounts[&0].get_token_balance(TOKEN), 6);
+        assert_eq!(shard.accounts[&1].get_token_balance(TOKEN), 14);
+        let statuses = (0..3)
+            .map(|index| shard.receipts[&transaction.function_hash(index)].status)
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec![true, false, true]);
+        assert_eq!(
+            shard.receipts[&transaction.hash].gas_used,
+            transaction.gas()
+        );
+    }
+
+    #[test]
+    fn locked_transfer_is_committed_and_finalized_before_the_deadline() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let mut destination = shard_with_accounts(1, &[1]);
+        let balance = source.accounts[&0].balance;
+        let lock = Calldata::Lock {
+            amount: 5,
+            deadline: 10,
+        }
+        .encode();
+        let lock_receipt = execute(
+            &mut source,
+            0,
+            0,
+            function(0, 1, FunctionType::LockCrossTransfer, lock),
+            None,
+        );
+        assert!(lock_receipt.status);
+        assert_eq!(source.accounts[&0].balance, balance - 5);
+
+        let vote_receipt = execute(
+            &mut destination,
+            1,
+            0,
+            function(1, 1, FunctionType::ConfirmCrossTransfer, "".to_string()),
+            Some(&lock_receipt),
+        );
+        assert!(vote_receipt.status);
+        let decision_receipt = execute(
+            &mut source,
+            0,
+            1,
+            function(0, 1, FunctionType::CommitCrossTransfer, "".to_string()),
+            Some(&vote_receipt),
+        );
+        assert!(decision_receipt.status);
+        assert!(source.locks.is_empty());
+        assert_eq!(source.accounts[&0].balance, balance - 5);
+
+        let destination_balance = destination.accounts[&1].balance;
+        let finalize = function(0, 1, FunctionType::FinalizeCrossTransfer, "".to_string());
+        assert!(
+            execute(
+                &mut destination,
+                1,
+                1,
+                finalize.clone(),
+                Some(&decision_receipt)
+            )
+            .status
+        );
+        assert_eq!(destination.accounts[&1].balance, destination_balance + 5);
+        // The decision cannot be applied twice
+        assert!(!execute(&mut destination, 1, 2, finalize, Some(&decision_receipt)).status);
+    }
+
+    #[test]
+    fn locked_transfer_is_aborted_and_refunded_only_after_the_deadline() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let balance = source.accounts[&0].balance;
+        let deadline = 3;
+        let lock = Calldata::Lock {
+            amount: 5,
+            deadline,
+        }
+        .encode();
+        let lock_receipt = execute(
+            &mut source,
+            0,
+            0,
+            function(0, 1, FunctionType::LockCrossTransfer, lock),
+            None,
+        );
+        assert!(lock_receipt.status);
+
+        let abort = function(0, 1, FunctionType::AbortCrossTransfer, "".to_string());
+        assert!(!execute(&mut source, 0, 1, abort.clone(), Some(&lock_receipt)).status);
+        assert_eq!(source.accounts[&0].balance, balance - 5);
+
+        skip_slots_until(&mut [&mut source], deadline + 1);
+        assert!(execute(&mut source, 0, 2, abort.clone(), Some(&lock_receipt)).status);
+        assert_eq!(source.accounts[&0].balance, balance);
+        // The lock is gone, so the funds are refunded only once
+        assert!(!execute(&mut source, 0, 3, abort, Some(&lock_receipt)).status);
+        assert_eq!(source.accounts[&0].balance, balance);
+    }
+
+    #[test]
+    fn receipt_is_applied_before_expiry_but_not_twice() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let mut destination = shard_with_accounts(1, &[1]);
+        let create = function(0, 0, FunctionType::CreateCrossTransfer, "".to_string());
+        let create_receipt = execute(&mut source, 0, 0, create, None);
+        assert!(create_receipt.status);
+
+        let apply = function(0, 1, FunctionType::ApplyCrossTransfer, "".to_string());
+        assert!(execute(&mut destination, 1, 0, apply.clone(), Some(&create_receipt)).status);
+        assert!(!execute(&mut destination, 1, 1, apply, Some(&create_receipt)).status);
+    }
+
+    #[test]
+    fn expired_receipt_is_reclaimed_once_instead_of_applied() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let mut destination = shard_with_accounts(1, &[1]);
+        let create = function(0, 0, FunctionType::CreateCrossTransfer, "".to_string());
+        let create_receipt = execute(&mut source, 0, 0, create, None);
+        assert!(create_receipt.status);
+
+        // The receipt can still be applied, so it cannot be reclaimed yet
+        let reclaim = function(0, 0, FunctionType::ReclaimCrossTransfer, "".to_string());
+        assert!(!execute(&mut source, 0, 1, reclaim.clone(), Some(&create_receipt)).status);
+
+        let expiry_slot = create_receipt.slot_number + RECEIPT_EXPIRY_EPOCHS * SLOTS_PER_EPOCH + 1;
+        skip_slots_until(&mut [&mut source, &mut destination], expiry_slot);
+        assert!(create_receipt.is_expired(source.blocks.len() as Slot));
+
+        let apply = function(0, 1, FunctionType::ApplyCrossTransfer, "".to_string());
+        assert!(!execute(&mut destination, 1, 0, apply, Some(&create_receipt)).status);
+        assert!(execute(&mut source, 0, 2, reclaim.clone(), Some(&create_receipt)).status);
+        assert!(!execute(&mut source, 0, 3, reclaim, Some(&create_receipt)).status);
+    }
+
+    #[test]
+    fn expired_token_receipt_returns_the_tokens_to_the_sender() {
+        let mut source = shard_with_accounts(0, &[0]);
+        let token = Calldata::Token {
+            token: TOKEN,
+            amount: 4,
+        }
+        .encode();
+        let create = function(0, 1, FunctionType::CreateCrossTokenTransfer, token);
+        let create_receipt = execute(&mut source, 0, 0, create, None);
+        assert!(create_receipt.status);
+        assert_eq!(source.accounts[&0].get_token_balance(TOKEN), 6);
+
+        let expiry_slot = create_receipt.slot_number + RECEIPT_EXPIRY_EPOCHS * SLOTS_PER_EPOCH + 1;
+        skip_slots_until(&mut [&mut source], expiry_slot);
+        let reclaim = function(0, 0, FunctionType::ReclaimCrossTransfer, "".to_string());
+        assert!(execute(&mut source, 0, 1, reclaim, Some(&create_receipt)).status);
+        assert_eq!(source.accounts[&0].get_token_balance(TOKEN), 10);
+    }
 }
@@ -3,8 +3,26 @@ use crate::*;
 #[derive(Clone)]
 pub struct ShardBlock {
     pub executed_transactions: Vec<Transaction>,
+    /// All gas in the one-dimensional fee market, or execution gas in the multidimensional one.
     pub gas_used: Gas,
+    pub receipt_out_gas_used: Gas,
+    pub receipt_in_gas_used: Gas,
     pub number: Slot,
+    /// Whether or not the proposer proposed the block. Otherwise, the slot is empty.
+    pub proposed: bool,
+    /// The gas target the proposer votes toward under GasTargetRule::ProposerVoting.
+    pub gas_target_vote: Gas,
+
+    // For statistics
+    pub censored_transaction_num: usize,
+    /// Gas used by the proposer's own transactions for base fee stuffing.
+    pub stuffed_gas: Gas,
+    /// Transactions rejected because their sender's account was yanked.
+    pub locked_transaction_num: usize,
+    /// Transactions passed over because of a gap in their sender's nonces.
+    pub skipped_transactions: Vec<TransactionHash>,
+    /// Transactions removed from the mempool in excess of MEMPOOL_TRANSACTION_NUM.
+    pub dropped_transactions: Vec<TransactionHash>,
 }
 
 impl ShardBlock {
@@ -12,7 +30,25 @@ impl ShardBlock {
         Self {
             executed_transactions: Vec::new(),
             gas_used: 0,
+            receipt_out_gas_used: 0,
+            receipt_in_gas_used: 0,
             number,
+            proposed: true,
+            gas_target_vote: 0,
+            censored_transaction_num: 0,
+            stuffed_gas: 0,
+            locked_transaction_num: 0,
+            skipped_transactions: Vec::new(),
+            dropped_transactions: Vec::new(),
         }
     }
+
+    /// Gas used per resource of the multidimensional fee market.
+    pub fn gas_used_by_resource(&self) -> Resources {
+        Resources::new(
+            self.gas_used,
+            self.receipt_out_gas_used,
+            self.receipt_in_gas_used,
+        )
+    }
 }
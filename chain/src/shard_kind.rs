@@ -0,0 +1,18 @@
+/// What a shard carries.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ShardKind {
+    /// Transactions executed against the shard state.
+    #[default]
+    Execution,
+    /// Only data, e.g. batches of rollups. Gas is counted in bytes, so the base fee is per byte.
+    Data,
+}
+
+impl ShardKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShardKind::Execution => "execution",
+            ShardKind::Data => "data",
+        }
+    }
+}
@@ -3,26 +3,58 @@ use crate::*;
 #[derive(Clone)]
 pub struct ShardState {
     pub base_fee: GasPrice,
+    /// Global component of the base fee, which is non-zero only under the global coupling.
+    pub global_base_fee: GasPrice,
+    pub gas_target: Gas,
+    /// Base fees of cross-shard receipts, which are updated only in the multidimensional fee market.
+    pub receipt_out_base_fee: GasPrice,
+    pub receipt_in_base_fee: GasPrice,
     pub receipts: HashMap<TransactionHash, Receipt>,
 }
 
 impl ShardState {
-    pub fn new(base_fee: GasPrice) -> Self {
+    pub fn new(base_fee: GasPrice, gas_target: Gas) -> Self {
         Self {
             base_fee,
+            global_base_fee: 0,
+            gas_target,
+            receipt_out_base_fee: INITIAL_RECEIPT_BASE_FEE,
+            receipt_in_base_fee: INITIAL_RECEIPT_BASE_FEE,
             receipts: HashMap::new(),
         }
     }
 
-    pub fn compute_updated_gasprice(prev_base_fee: GasPrice, block_gas_used: Gas) -> GasPrice {
-        if block_gas_used > BLOCK_GAS_TARGET {
-            let delta = prev_base_fee * (block_gas_used - BLOCK_GAS_TARGET)
-                / BLOCK_GAS_TARGET as GasPrice
+    /// Base fees per resource.
+    pub fn base_fees(&self) -> Resources {
+        Resources::new(
+            self.base_fee,
+            self.receipt_out_base_fee,
+            self.receipt_in_base_fee,
+        )
+    }
+
+    /// Gas targets per resource, where the receipt ones follow the gas target.
+    pub fn gas_targets(&self) -> Resources {
+        Resources::new(
+            self.gas_target,
+            self.gas_target / RECEIPT_OUT_GAS_TARGET_DENOMINATOR,
+            self.gas_target / RECEIPT_IN_GAS_TARGET_DENOMINATOR,
+        )
+    }
+
+    pub fn compute_updated_gasprice(
+        prev_base_fee: GasPrice,
+        block_gas_used: Gas,
+        gas_target: Gas,
+    ) -> GasPrice {
+        if block_gas_used > gas_target {
+            let delta = prev_base_fee * (block_gas_used - gas_target)
+                / gas_target as GasPrice
                 / BASE_FEE_MAX_CHANGE_DENOMINATOR as GasPrice;
             std::cmp::min(prev_base_fee + delta, MAX_GASPRICE)
         } else {
-            let delta = prev_base_fee * (BLOCK_GAS_TARGET - block_gas_used)
-                / BLOCK_GAS_TARGET as GasPrice
+            let delta = prev_base_fee * (gas_target - block_gas_used)
+                / gas_target as GasPrice
                 / BASE_FEE_MAX_CHANGE_DENOMINATOR as GasPrice;
             std::cmp::max(prev_base_fee, MIN_GASPRICE + delta) - delta
         }
@@ -0,0 +1,14 @@
+use crate::*;
+
+/// The values before the first modification of the entries that functions modify,
+/// to revert a transaction. None and false mean that the entry did not exist.
+#[derive(Default)]
+pub(crate) struct ShardUndoLog {
+    pub(crate) accounts: HashMap<Address, Option<Account>>,
+    pub(crate) moving_accounts: HashMap<Address, Option<Account>>,
+    pub(crate) yanked_accounts: HashMap<Address, bool>,
+    pub(crate) token_contracts: HashMap<Address, Option<TokenContract>>,
+    pub(crate) locks: HashMap<TransactionHash, Option<CrossTransferLock>>,
+    pub(crate) outgoing_transfers: HashMap<TransactionHash, bool>,
+    pub(crate) used_receipts: HashMap<TransactionHash, bool>,
+}
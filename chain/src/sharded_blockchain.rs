@@ -8,6 +8,14 @@ pub struct ShardedBlockchain {
 
     pub addr_to_shard_id: HashMap<Address, usize>,
 
+    pub gas_target_rule: GasTargetRule,
+    /// The sum of the gas targets, within which the gas target rule moves the capacity.
+    pub gas_target_budget: Gas,
+    pub base_fee_coupling: BaseFeeCoupling,
+    pub outages: Vec<Outage>,
+    /// (shard_id, slot) whose proposal is missed.
+    pub missed_proposals: HashSet<(usize, Slot)>,
+
     // For statistics
     pub account_num: usize,
 }
@@ -23,6 +31,11 @@ impl ShardedBlockchain {
             epoch: ShardedBlockchain::GENESIS_EPOCH,
             shards: (0..SHARD_NUM).map(Shard::new).collect(),
             addr_to_shard_id: HashMap::new(),
+            gas_target_rule: GasTargetRule::Fixed,
+            gas_target_budget: GLOBAL_GAS_TARGET_BUDGET,
+            base_fee_coupling: BaseFeeCoupling::Independent,
+            outages: Vec::new(),
+            missed_proposals: HashSet::new(),
             account_num: 0,
         }
     }
@@ -32,7 +45,7 @@ impl ShardedBlockchain {
         assert!(self.slot <= slot);
         while self.slot < slot {
             self.process_slot();
-            if (self.slot + 1) % SLOTS_PER_EPOCH == 0 {
+            if (self.slot + 1).is_multiple_of(SLOTS_PER_EPOCH) {
                 self.process_epoch();
             }
             self.slot += 1;
@@ -41,14 +54,155 @@ impl ShardedBlockchain {
 
     /// Process of a slot.
     pub fn process_slot(&mut self) {
-        self.shards
-            .iter_mut()
-            .for_each(|shard| shard.process_slot());
+        let slot = self.slot;
+        let outages = &self.outages;
+        let missed_proposals = &self.missed_proposals;
+        self.shards.iter_mut().for_each(|shard| {
+            if missed_proposals.contains(&(shard.id, slot))
+                || outages.iter().any(|outage| outage.covers(shard.id, slot))
+            {
+                shard.skip_slot();
+            } else {
+                shard.process_slot();
+            }
+        });
+        self.missed_proposals
+            .retain(|&(_, missed_slot)| missed_slot > slot);
+        self.couple_base_fees();
+    }
+
+    /// Set the base fee coupling before the genesis slot.
+    pub fn set_base_fee_coupling(&mut self, base_fee_coupling: BaseFeeCoupling) {
+        assert_eq!(self.slot, ShardedBlockchain::GENESIS_SLOT);
+        self.base_fee_coupling = base_fee_coupling;
+        if base_fee_coupling == BaseFeeCoupling::Global {
+            self.shards.iter_mut().for_each(|shard| {
+                let genesis_state = shard
+                    .states
+                    .last_mut()
+                    .expect("the genesis state does not exist");
+                genesis_state.global_base_fee = INITIAL_GLOBAL_BASE_FEE;
+            });
+        }
+    }
+
+    /// Overwrite the base fees of the next states, which shards computed independently.
+    /// Data shards, whose gas is counted in bytes, are only used with independent base fees.
+    fn couple_base_fees(&mut self) {
+        match self.base_fee_coupling {
+            BaseFeeCoupling::Independent => {}
+            BaseFeeCoupling::Global => {
+                let prev_states = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.states[shard.states.len() - 2].clone())
+                    .collect::<Vec<_>>();
+                let total_gas_used: Gas = self
+                    .shards
+                    .iter()
+                    .map(|shard| {
+                        shard
+                            .blocks
+                            .last()
+                            .expect("the block does not exist")
+                            .gas_used
+                    })
+                    .sum();
+                let total_gas_target: Gas = prev_states.iter().map(|state| state.gas_target).sum();
+                // All shards share the same global component
+                let global_base_fee = ShardState::compute_updated_gasprice(
+                    prev_states[0].global_base_fee,
+                    total_gas_used,
+                    total_gas_target,
+                );
+                self.shards
+                    .iter_mut()
+                    .zip(prev_states)
+                    .for_each(|(shard, prev_state)| {
+                        let block = shard.blocks.last().expect("the block does not exist");
+                        let prev_local_base_fee = prev_state.base_fee - prev_state.global_base_fee;
+                        let local_base_fee = if block.proposed {
+                            ShardState::compute_updated_gasprice(
+                                prev_local_base_fee,
+                                block.gas_used,
+                                prev_state.gas_target,
+                            )
+                        } else {
+                            prev_local_base_fee
+                        };
+                        let state = shard.states.last_mut().expect("the state does not exist");
+                        state.base_fee =
+                            std::cmp::min(local_base_fee + global_base_fee, MAX_GASPRICE);
+                        state.global_base_fee = std::cmp::min(global_base_fee, state.base_fee);
+                    });
+            }
+            BaseFeeCoupling::NeighborSmoothing => {
+                let base_fees = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.get_base_fee())
+                    .collect::<Vec<_>>();
+                let shard_num = self.shards.len();
+                self.shards.iter_mut().for_each(|shard| {
+                    let neighbor_mean = (base_fees[(shard.id + shard_num - 1) % shard_num]
+                        + base_fees[(shard.id + 1) % shard_num])
+                        / 2;
+                    let state = shard.states.last_mut().expect("the state does not exist");
+                    state.base_fee = (state.base_fee
+                        * (BASE_FEE_SMOOTHING_DENOMINATOR - BASE_FEE_SMOOTHING_NUMERATOR)
+                        + neighbor_mean * BASE_FEE_SMOOTHING_NUMERATOR)
+                        / BASE_FEE_SMOOTHING_DENOMINATOR;
+                });
+            }
+        }
+    }
+
+    /// Make the proposer of the shard miss its proposal at the slot.
+    pub fn schedule_missed_proposal(&mut self, shard_id: usize, slot: Slot) {
+        assert!(self.slot <= slot);
+        self.missed_proposals.insert((shard_id, slot));
     }
 
     /// Process of a epoch.
     pub fn process_epoch(&mut self) {
         self.epoch += 1;
+        self.update_gas_targets();
+        self.shards
+            .iter_mut()
+            .for_each(|shard| shard.collect_rent());
+    }
+
+    /// Move the gas capacity among shards according to the gas target rule.
+    /// The sum of the gas targets is kept at the gas target budget,
+    /// but no gas target falls below 1 / MIN_GAS_TARGET_DENOMINATOR of the configured one.
+    /// Data shards, whose gas is counted in bytes, are only used with the fixed rule.
+    fn update_gas_targets(&mut self) {
+        if self.gas_target_rule == GasTargetRule::Fixed {
+            return;
+        }
+        let gas_target_rule = self.gas_target_rule;
+        let next_gas_targets = self
+            .shards
+            .iter()
+            .map(|shard| {
+                let epoch_start = shard.blocks.len().saturating_sub(SLOTS_PER_EPOCH as usize);
+                gas_target_rule
+                    .compute_next_gas_target(shard.get_gas_target(), &shard.blocks[epoch_start..])
+            })
+            .collect::<Vec<_>>();
+        let total_gas_target: Gas = next_gas_targets.iter().sum();
+        let gas_target_budget = self.gas_target_budget;
+        let min_gas_target =
+            gas_target_budget / self.shards.len() as Gas / MIN_GAS_TARGET_DENOMINATOR;
+        self.shards
+            .iter_mut()
+            .zip(next_gas_targets)
+            .for_each(|(shard, gas_target)| {
+                shard.set_gas_target(std::cmp::max(
+                    gas_target * gas_target_budget / total_gas_target,
+                    min_gas_target,
+                ));
+            });
     }
 
     /// Update value in addr_to_shard_id with key = addr.
@@ -71,6 +225,48 @@ impl ShardedBlockchain {
         }
     }
 
+    /// Replace the shards with `shard_num` genesis shards.
+    pub fn set_shard_num(&mut self, shard_num: usize) {
+        assert_eq!(self.slot, ShardedBlockchain::GENESIS_SLOT);
+        assert!(shard_num > 0);
+        self.shards = (0..shard_num).map(Shard::new).collect();
+        self.gas_target_budget = BLOCK_GAS_TARGET * shard_num as Gas;
+    }
+
+    /// Set the genesis gas target of every shard, which is also the one its proposer prefers.
+    pub fn set_block_gas_target(&mut self, block_gas_target: Gas) {
+        assert_eq!(self.slot, ShardedBlockchain::GENESIS_SLOT);
+        self.shards.iter_mut().for_each(|shard| {
+            shard.set_gas_target(block_gas_target);
+            shard.preferred_gas_target = block_gas_target;
+        });
+        self.gas_target_budget = block_gas_target * self.shards.len() as Gas;
+    }
+
+    /// Make the last `data_shard_num` shards data shards.
+    pub fn set_data_shard_num(&mut self, data_shard_num: usize) {
+        assert!(data_shard_num < self.shards.len());
+        let execution_shard_num = self.shards.len() - data_shard_num;
+        self.shards[execution_shard_num..]
+            .iter_mut()
+            .for_each(|shard| shard.set_kind(ShardKind::Data));
+    }
+
+    /// The number of execution shards, which come before the data shards.
+    pub fn execution_shard_num(&self) -> usize {
+        self.shards
+            .iter()
+            .filter(|shard| shard.kind == ShardKind::Execution)
+            .count()
+    }
+
+    /// Get the receipt of a transaction in any shard.
+    pub fn get_receipt(&self, transaction_hash: TransactionHash) -> Option<&Receipt> {
+        self.shards
+            .iter()
+            .find_map(|shard| shard.receipts.get(&transaction_hash))
+    }
+
     /// Get an account with address = addr.
     pub fn get_account(&self, addr: Address) -> (bool, Option<&Account>) {
         let &shard_id = self
@@ -84,8 +280,8 @@ impl ShardedBlockchain {
         } else if moving_account.is_some() {
             (false, moving_account)
         } else {
-            unreachable!("failed to get account");
-            //(false, None)
+            // Evicted or hibernated
+            (false, None)
         }
     }
 }
@@ -95,3 +291,115 @@ impl Default for ShardedBlockchain {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blockchain(shard_num: usize) -> ShardedBlockchain {
+        let mut blockchain = ShardedBlockchain::new();
+        blockchain.set_shard_num(shard_num);
+        blockchain
+    }
+
+    fn proposed_slots(blockchain: &ShardedBlockchain, shard_id: usize) -> Vec<bool> {
+        blockchain.shards[shard_id]
+            .blocks
+            .iter()
+            .map(|block| block.proposed)
+            .collect()
+    }
+
+    /// Process one slot in which only the first shard fills its block.
+    fn process_slot_with_full_first_shard(blockchain: &mut ShardedBlockchain) {
+        blockchain.shards[0].proposer_behavior = ProposerBehavior::BaseFeeStuffing;
+        blockchain.process_slots(1);
+    }
+
+    #[test]
+    fn global_coupling_shares_the_global_component() {
+        let mut blockchain = blockchain(2);
+        blockchain.set_base_fee_coupling(BaseFeeCoupling::Global);
+        process_slot_with_full_first_shard(&mut blockchain);
+
+        // Half of the total gas target is used, so the global component stays
+        let local_base_fee = INITIAL_BASE_FEE - INITIAL_GLOBAL_BASE_FEE;
+        let expected = [BLOCK_GAS_LIMIT, 0].map(|gas_used| {
+            ShardState::compute_updated_gasprice(local_base_fee, gas_used, BLOCK_GAS_TARGET)
+                + INITIAL_GLOBAL_BASE_FEE
+        });
+        for (shard, expected) in blockchain.shards.iter().zip(expected) {
+            let state = shard.states.last().unwrap();
+            assert_eq!(state.global_base_fee, INITIAL_GLOBAL_BASE_FEE);
+            assert_eq!(state.base_fee, expected);
+        }
+    }
+
+    #[test]
+    fn neighbor_smoothing_pulls_base_fees_toward_the_neighbors() {
+        let mut blockchain = blockchain(3);
+        blockchain.set_base_fee_coupling(BaseFeeCoupling::NeighborSmoothing);
+        process_slot_with_full_first_shard(&mut blockchain);
+
+        let independent = [BLOCK_GAS_LIMIT, 0, 0].map(|gas_used| {
+            ShardState::compute_updated_gasprice(INITIAL_BASE_FEE, gas_used, BLOCK_GAS_TARGET)
+        });
+        let base_fees = blockchain
+            .shards
+            .iter()
+            .map(|shard| shard.get_base_fee())
+            .collect::<Vec<_>>();
+        let smoothed = |shard_id: usize| {
+            let neighbor_mean =
+                (independent[(shard_id + 2) % 3] + independent[(shard_id + 1) % 3]) / 2;
+            (independent[shard_id]
+                * (BASE_FEE_SMOOTHING_DENOMINATOR - BASE_FEE_SMOOTHING_NUMERATOR)
+                + neighbor_mean * BASE_FEE_SMOOTHING_NUMERATOR)
+                / BASE_FEE_SMOOTHING_DENOMINATOR
+        };
+        assert_eq!(base_fees, vec![smoothed(0), smoothed(1), smoothed(2)]);
+        assert!(base_fees[0] < independent[0]);
+        assert!(base_fees[1] > independent[1]);
+    }
+
+    #[test]
+    fn independent_base_fees_are_not_coupled() {
+        let mut blockchain = blockchain(2);
+        process_slot_with_full_first_shard(&mut blockchain);
+
+        let base_fees = blockchain
+            .shards
+            .iter()
+            .map(|shard| shard.get_base_fee())
+            .collect::<Vec<_>>();
+        let expected = [BLOCK_GAS_LIMIT, 0].map(|gas_used| {
+            ShardState::compute_updated_gasprice(INITIAL_BASE_FEE, gas_used, BLOCK_GAS_TARGET)
+        });
+        assert_eq!(base_fees, expected.to_vec());
+    }
+
+    #[test]
+    fn outage_skips_only_its_shard_and_slots() {
+        let mut blockchain = blockchain(2);
+        blockchain.outages.push(Outage::new(1, 1, 2));
+        blockchain.process_slots(4);
+
+        assert_eq!(proposed_slots(&blockchain, 0), vec![true; 4]);
+        assert_eq!(
+            proposed_slots(&blockchain, 1),
+            vec![true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn missed_proposal_skips_one_slot_and_is_pruned() {
+        let mut blockchain = blockchain(2);
+        blockchain.schedule_missed_proposal(0, 1);
+        blockchain.schedule_missed_proposal(1, 5);
+        blockchain.process_slots(3);
+
+        assert_eq!(proposed_slots(&blockchain, 0), vec![true, false, true]);
+        assert_eq!(proposed_slots(&blockchain, 1), vec![true; 3]);
+        assert_eq!(blockchain.missed_proposals, HashSet::from([(1, 5)]));
+    }
+}
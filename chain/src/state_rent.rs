@@ -0,0 +1,37 @@
+use crate::*;
+
+/// Storage pricing of shards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateRent {
+    /// Accounts live on shards for free.
+    #[default]
+    Free,
+    /// Accounts pay rent every epoch and are deleted from the state if they cannot pay.
+    Evict,
+    /// Accounts pay rent every epoch and are set aside, rent-free, if they cannot pay.
+    Hibernate,
+}
+
+impl StateRent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StateRent::Free => "free",
+            StateRent::Evict => "evict",
+            StateRent::Hibernate => "hibernate",
+        }
+    }
+}
+
+impl std::str::FromStr for StateRent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "free" => Ok(StateRent::Free),
+            "evict" => Ok(StateRent::Evict),
+            "hibernate" => Ok(StateRent::Hibernate),
+            _ => Err(format!("unknown state rent: {}", s)),
+        }
+    }
+}
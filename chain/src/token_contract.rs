@@ -0,0 +1,24 @@
+use crate::*;
+
+/// Token contract, which can mint tokens only on its home shard.
+/// Balances are kept in the accounts of the holders.
+/// Ref: https://github.com/ewasm/eth1x64/blob/cfa0317f29cbf5a8ef5f67612944cbb9ba38d5b4/variant1_token_examples.md
+#[derive(Clone, Debug)]
+pub struct TokenContract {
+    pub addr: Address,
+    /// The issuer, the only account allowed to mint.
+    pub owner: Address,
+    pub home_shard_id: usize,
+    pub total_supply: Wei,
+}
+
+impl TokenContract {
+    pub fn new(addr: Address, owner: Address, home_shard_id: usize) -> Self {
+        Self {
+            addr,
+            owner,
+            home_shard_id,
+            total_supply: 0,
+        }
+    }
+}
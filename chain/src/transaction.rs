@@ -12,7 +12,12 @@ pub struct Transaction {
     pub functions: Vec<Function>,
     pub gas_premium: GasPrice,
     pub fee_cap: GasPrice,
+    /// Fee caps of cross-shard receipts in the multidimensional fee market.
+    pub receipt_out_fee_cap: GasPrice,
+    pub receipt_in_fee_cap: GasPrice,
     pub gas_limit: Gas,
+    pub transaction_type: TransactionType,
+    pub batch_semantics: BatchSemantics,
     pub hash: TransactionHash,
 }
 
@@ -33,12 +38,125 @@ impl Transaction {
             functions,
             gas_premium,
             fee_cap,
+            receipt_out_fee_cap: fee_cap,
+            receipt_in_fee_cap: fee_cap,
             gas_limit: Gas::MAX,
+            transaction_type: TransactionType::Eip1559,
+            batch_semantics: BatchSemantics::AllOrNothing,
             nonce,
             hash: Transaction::generate_transaction_hash(from, shard_id, nonce),
         }
     }
 
+    /// Turn the transaction into a legacy one, which bids its whole fee cap as the gas price.
+    pub fn into_legacy(mut self) -> Self {
+        self.gas_premium = self.fee_cap;
+        self.transaction_type = TransactionType::Legacy {
+            gas_price: self.fee_cap,
+        };
+        self
+    }
+
+    /// Set the fee caps of cross-shard receipts in the multidimensional fee market,
+    /// which are the fee cap by default.
+    pub fn with_receipt_fee_caps(
+        mut self,
+        receipt_out_fee_cap: GasPrice,
+        receipt_in_fee_cap: GasPrice,
+    ) -> Self {
+        self.receipt_out_fee_cap = receipt_out_fee_cap;
+        self.receipt_in_fee_cap = receipt_in_fee_cap;
+        self
+    }
+
+    /// Let another account pay the fee of the transaction.
+    pub fn into_sponsored(mut self, fee_payer: Address) -> Self {
+        self.transaction_type = TransactionType::Sponsored { fee_payer };
+        self
+    }
+
+    /// Hash of the receipt of the function at the index.
+    /// A single-function transaction has only the receipt of the transaction.
+    pub fn function_hash(&self, index: usize) -> TransactionHash {
+        if self.functions.len() == 1 {
+            self.hash
+        } else {
+            self.hash
+                .overflowing_mul(31)
+                .0
+                .overflowing_add(index as u64 + 1)
+                .0
+        }
+    }
+
+    /// Gas of the function at the index.
+    /// Functions after the first one share the base gas of the transaction.
+    pub fn function_gas(&self, index: usize) -> Gas {
+        let gas = self.functions[index].gas();
+        if index == 0 {
+            gas
+        } else {
+            gas.saturating_sub(GAS_TRANSACTION_BASE)
+        }
+    }
+
+    /// Gas of the function at the index per resource of the multidimensional fee market.
+    pub fn function_gas_by_resource(&self, index: usize) -> Resources {
+        let mut gas = self.functions[index].gas_by_resource();
+        if index > 0 {
+            gas.execution = gas.execution.saturating_sub(GAS_TRANSACTION_BASE);
+        }
+        gas
+    }
+
+    pub fn gas(&self) -> Gas {
+        (0..self.functions.len())
+            .map(|index| self.function_gas(index))
+            .sum()
+    }
+
+    pub fn gas_by_resource(&self) -> Resources {
+        (0..self.functions.len())
+            .map(|index| self.function_gas_by_resource(index))
+            .sum()
+    }
+
+    /// The account that pays the fee.
+    pub fn fee_payer(&self) -> Address {
+        match self.transaction_type {
+            TransactionType::Sponsored { fee_payer } => fee_payer,
+            _ => self.from,
+        }
+    }
+
+    /// The gas price actually paid under the base fee.
+    pub fn effective_gas_price(&self, base_fee: GasPrice) -> GasPrice {
+        match self.transaction_type {
+            TransactionType::Legacy { gas_price } => gas_price,
+            _ => std::cmp::min(self.fee_cap, self.gas_premium + base_fee),
+        }
+    }
+
+    /// The gas price that goes to the proposer instead of being burned.
+    pub fn tip(&self, base_fee: GasPrice) -> GasPrice {
+        self.effective_gas_price(base_fee).saturating_sub(base_fee)
+    }
+
+    /// Fee caps per resource. The premium is paid only for execution gas.
+    pub fn fee_caps(&self) -> Resources {
+        Resources::new(
+            self.fee_cap,
+            self.receipt_out_fee_cap,
+            self.receipt_in_fee_cap,
+        )
+    }
+
+    /// Set the nonce, which also changes the hash.
+    pub fn set_nonce(&mut self, nonce: Nonce) {
+        self.nonce = nonce;
+        self.hash = Transaction::generate_transaction_hash(self.from, self.shard_id, nonce);
+    }
+
     pub fn generate_transaction_hash(
         from: Address,
         shard_id: usize,
@@ -81,3 +199,42 @@ impl PartialEq for Transaction {
 }
 
 pub type TransactionAndReceipt = (Transaction, Option<Receipt>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(gas_premium: GasPrice, fee_cap: GasPrice) -> Transaction {
+        Transaction::new(0, 1, 0, Vec::new(), gas_premium, fee_cap, 0)
+    }
+
+    #[test]
+    fn eip1559_pays_the_base_fee_and_the_premium_up_to_the_fee_cap() {
+        let transaction = transaction(2, 10);
+        assert_eq!(transaction.effective_gas_price(5), 7);
+        assert_eq!(transaction.tip(5), 2);
+        assert_eq!(transaction.effective_gas_price(9), 10);
+        assert_eq!(transaction.tip(9), 1);
+        assert_eq!(transaction.tip(12), 0);
+        assert_eq!(transaction.fee_payer(), 0);
+    }
+
+    #[test]
+    fn legacy_pays_its_gas_price_whatever_the_base_fee() {
+        let transaction = transaction(2, 10).into_legacy();
+        assert_eq!(transaction.effective_gas_price(5), 10);
+        assert_eq!(transaction.tip(5), 5);
+        assert_eq!(transaction.effective_gas_price(9), 10);
+        assert_eq!(transaction.tip(9), 1);
+        assert_eq!(transaction.fee_payer(), 0);
+    }
+
+    #[test]
+    fn sponsored_is_priced_as_eip1559_and_paid_by_the_fee_payer() {
+        let transaction = transaction(2, 10).into_sponsored(3);
+        assert_eq!(transaction.effective_gas_price(5), 7);
+        assert_eq!(transaction.tip(5), 2);
+        assert_eq!(transaction.effective_gas_price(9), 10);
+        assert_eq!(transaction.fee_payer(), 3);
+    }
+}
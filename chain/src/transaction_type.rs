@@ -0,0 +1,24 @@
+use crate::*;
+
+/// Envelope of a transaction, which decides how its fee is priced and who pays it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TransactionType {
+    /// A single gas price. Everything above the base fee goes to the proposer.
+    Legacy { gas_price: GasPrice },
+    /// A fee cap and a gas premium (EIP-1559).
+    #[default]
+    Eip1559,
+    /// Priced as EIP-1559, but the fee is paid by an account other than the sender.
+    /// The fee payer must live on the shard of the transaction, e.g. a relayer on the destination shard.
+    Sponsored { fee_payer: Address },
+}
+
+impl TransactionType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionType::Legacy { .. } => "legacy",
+            TransactionType::Eip1559 => "eip1559",
+            TransactionType::Sponsored { .. } => "sponsored",
+        }
+    }
+}
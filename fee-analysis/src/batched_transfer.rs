@@ -0,0 +1,10 @@
+use crate::*;
+
+/// A transfer waiting to be bundled with the other transfers of the user into one transaction.
+pub struct BatchedTransfer {
+    pub to: Address,
+    pub function: Function,
+    pub fee_cap: GasPrice,
+    /// The destination shard and the function applying a cross-shard transfer.
+    pub apply: Option<(usize, Function)>,
+}
@@ -0,0 +1,30 @@
+use crate::*;
+
+/// An asynchronous cross-shard contract call issued by a user.
+pub struct CrossCall {
+    pub from: Address,
+    pub origin_shard_id: usize,
+    pub contract_shard_id: usize,
+    /// The slot in which the user sent the call.
+    pub created_slot: Slot,
+    /// Hashes of the call, apply and callback transactions sent so far.
+    pub leg_hashes: Vec<TransactionHash>,
+}
+
+impl CrossCall {
+    pub fn new(
+        from: Address,
+        origin_shard_id: usize,
+        contract_shard_id: usize,
+        created_slot: Slot,
+        call_hash: TransactionHash,
+    ) -> Self {
+        Self {
+            from,
+            origin_shard_id,
+            contract_shard_id,
+            created_slot,
+            leg_hashes: vec![call_hash],
+        }
+    }
+}
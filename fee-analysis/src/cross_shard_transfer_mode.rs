@@ -0,0 +1,34 @@
+use crate::*;
+
+/// How users transfer to accounts on other shards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossShardTransferMode {
+    /// Fire-and-forget receipts: each leg succeeds or fails independently.
+    #[default]
+    Receipt,
+    /// Two-phase protocol: the source locks, the destination confirms, then the source commits
+    /// or aborts.
+    LockAndCommit,
+}
+
+impl CrossShardTransferMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrossShardTransferMode::Receipt => "receipt",
+            CrossShardTransferMode::LockAndCommit => "lock_and_commit",
+        }
+    }
+}
+
+impl std::str::FromStr for CrossShardTransferMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "receipt" => Ok(CrossShardTransferMode::Receipt),
+            "lock_and_commit" => Ok(CrossShardTransferMode::LockAndCommit),
+            _ => Err(format!("unknown cross-shard transfer mode: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::*;
+
+/// A cross-shard transfer issued by a user.
+pub struct CrossTransfer {
+    pub from: Address,
+    pub mode: CrossShardTransferMode,
+    pub source_shard_id: usize,
+    pub destination_shard_id: usize,
+    /// The slot in which the user sent the transfer.
+    pub created_slot: Slot,
+    pub fee_cap: GasPrice,
+    /// The last slot in which a locked transfer can be committed.
+    pub deadline: Option<Slot>,
+    /// Hashes of the transactions of the transfer sent so far, starting from the first leg.
+    pub leg_hashes: Vec<TransactionHash>,
+    /// Whether or not the commit or the abort of a locked transfer has been sent.
+    pub decided: bool,
+    /// Whether or not the sender gave up applying the transfer and reclaimed it.
+    pub reclaimed: bool,
+    /// The base fee burned by the legs included so far.
+    pub burned_fee: Wei,
+    /// The tip paid by the legs included so far.
+    pub tip: Wei,
+}
+
+impl CrossTransfer {
+    pub fn new(
+        first_transaction: &Transaction,
+        first_leg_hash: TransactionHash,
+        mode: CrossShardTransferMode,
+        destination_shard_id: usize,
+        created_slot: Slot,
+        deadline: Option<Slot>,
+    ) -> Self {
+        Self {
+            from: first_transaction.from,
+            mode,
+            source_shard_id: first_transaction.shard_id,
+            destination_shard_id,
+            created_slot,
+            fee_cap: first_transaction.fee_cap,
+            deadline,
+            leg_hashes: vec![first_leg_hash],
+            decided: false,
+            reclaimed: false,
+            burned_fee: 0,
+            tip: 0,
+        }
+    }
+
+    /// The outcome of the transfer given the receipts of its legs included so far, in order,
+    /// and the slot in which it ended, if it did.
+    pub fn outcome(&self, legs: &[&Receipt]) -> (TransferOutcome, Option<Slot>) {
+        let last_leg = legs.last().filter(|_| legs.len() == self.leg_hashes.len());
+        let outcome = match last_leg {
+            // The apply was abandoned, so only the reclaim matters once it is sent and included
+            _ if self.reclaimed => match legs.last() {
+                Some(receipt)
+                    if Some(&receipt.transaction_hash) == self.leg_hashes.last()
+                        && receipt.transaction_hash != self.leg_hashes[0]
+                        && receipt.status =>
+                {
+                    TransferOutcome::Refunded
+                }
+                _ => TransferOutcome::Pending,
+            },
+            _ if legs.iter().any(|receipt| !receipt.status) => TransferOutcome::Failed,
+            // Commit or abort of a locked transfer, with its decision
+            Some(receipt)
+                if matches!(
+                    Calldata::parse(&FunctionType::FinalizeCrossTransfer, &receipt.data),
+                    Ok(Calldata::Decision { commit: false, .. })
+                ) =>
+            {
+                TransferOutcome::Aborted
+            }
+            Some(_)
+                if legs.len()
+                    == match self.mode {
+                        CrossShardTransferMode::Receipt => 2,
+                        CrossShardTransferMode::LockAndCommit => 4,
+                    } =>
+            {
+                TransferOutcome::Completed
+            }
+            _ => TransferOutcome::Pending,
+        };
+        let ended_slot = if outcome == TransferOutcome::Pending {
+            None
+        } else {
+            legs.iter().map(|receipt| receipt.slot_number).max()
+        };
+        (outcome, ended_slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cross_transfer(mode: CrossShardTransferMode, leg_num: usize) -> CrossTransfer {
+        let first_transaction = Transaction::new(
+            0,
+            1,
+            0,
+            vec![Function {
+                source: 0,
+                target: 1,
+                ftype: FunctionType::CreateCrossTransfer,
+                calldata: "".to_string(),
+            }],
+            0,
+            INITIAL_BASE_FEE,
+            0,
+        );
+        let mut cross_transfer =
+            CrossTransfer::new(&first_transaction, first_transaction.hash, mode, 1, 0, None);
+        cross_transfer
+            .leg_hashes
+            .extend((1..leg_num as TransactionHash).map(|i| first_transaction.hash + i));
+        cross_transfer
+    }
+
+    /// The receipts of the legs included in the slots, successful unless the status says otherwise.
+    fn legs(cross_transfer: &CrossTransfer, slots: &[Slot], statuses: &[bool]) -> Vec<Receipt> {
+        slots
+            .iter()
+            .zip(statuses)
+            .enumerate()
+            .map(|(i, (&slot_number, &status))| Receipt {
+                slot_number,
+                from: 0,
+                to: 1,
+                gas_used: GAS_TRANSFER,
+                status,
+                transaction_hash: cross_transfer.leg_hashes[i],
+                data: Data::new(),
+            })
+            .collect()
+    }
+
+    fn outcome(
+        cross_transfer: &CrossTransfer,
+        legs: &[Receipt],
+    ) -> (TransferOutcome, Option<Slot>) {
+        cross_transfer.outcome(&legs.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn receipt_transfer_completes_with_its_apply() {
+        let cross_transfer = cross_transfer(CrossShardTransferMode::Receipt, 2);
+        let included = legs(&cross_transfer, &[3, 5], &[true, true]);
+        assert_eq!(
+            outcome(&cross_transfer, &included[..1]),
+            (TransferOutcome::Pending, None)
+        );
+        assert_eq!(
+            outcome(&cross_transfer, &included),
+            (TransferOutcome::Completed, Some(5))
+        );
+    }
+
+    #[test]
+    fn transfer_with_a_failed_leg_fails() {
+        let cross_transfer = cross_transfer(CrossShardTransferMode::Receipt, 2);
+        let included = legs(&cross_transfer, &[3, 5], &[true, false]);
+        assert_eq!(
+            outcome(&cross_transfer, &included),
+            (TransferOutcome::Failed, Some(5))
+        );
+    }
+
+    #[test]
+    fn locked_transfer_ends_with_its_decision() {
+        let cross_transfer = cross_transfer(CrossShardTransferMode::LockAndCommit, 4);
+        let mut included = legs(&cross_transfer, &[3, 5, 7, 9], &[true; 4]);
+        assert_eq!(
+            outcome(&cross_transfer, &included[..3]),
+            (TransferOutcome::Pending, None)
+        );
+
+        included[3].data = Calldata::Decision {
+            commit: true,
+            amount: 1,
+        }
+        .encode();
+        assert_eq!(
+            outcome(&cross_transfer, &included),
+            (TransferOutcome::Completed, Some(9))
+        );
+
+        included[3].data = Calldata::Decision {
+            commit: false,
+            amount: 1,
+        }
+        .encode();
+        assert_eq!(
+            outcome(&cross_transfer, &included),
+            (TransferOutcome::Aborted, Some(9))
+        );
+    }
+
+    #[test]
+    fn reclaimed_transfer_is_refunded_once_the_reclaim_is_included() {
+        let mut cross_transfer = cross_transfer(CrossShardTransferMode::Receipt, 2);
+        cross_transfer.reclaimed = true;
+        let included = legs(&cross_transfer, &[3, 80], &[true, true]);
+        assert_eq!(
+            outcome(&cross_transfer, &included[..1]),
+            (TransferOutcome::Pending, None)
+        );
+        assert_eq!(
+            outcome(&cross_transfer, &included),
+            (TransferOutcome::Refunded, Some(80))
+        );
+    }
+}
@@ -7,7 +7,58 @@ pub struct Environment {
     pub users: Vec<User>,
     pub user_num_mem: Vec<Vec<usize>>,
     pub mempool_tx_mem: Vec<Vec<usize>>,
+    pub pending_user_num_mem: Vec<Vec<usize>>,
+    pub in_flight_receipt_num_mem: Vec<Vec<usize>>,
+    pub state_size_mem: Vec<Vec<u64>>,
+    pub evicted_account_num_mem: Vec<Vec<usize>>,
     user_num: usize,
+    missed_proposal_probabilities: Vec<f64>,
+    /// Addresses of the token contracts.
+    token_addrs: Vec<Address>,
+    token_issuers: Vec<TokenIssuer>,
+    percentage_of_token_transfers: f64,
+    /// Whether or not users call contracts on other shards asynchronously with callbacks.
+    async_calls: bool,
+    pub cross_calls: Vec<CrossCall>,
+    /// Index of the cross-shard call each leg belongs to.
+    cross_call_index: HashMap<TransactionHash, usize>,
+    cross_shard_transfer_mode: CrossShardTransferMode,
+    lock_timeout: Slot,
+    pub cross_transfers: Vec<CrossTransfer>,
+    /// Index of the cross-shard transfer each leg belongs to.
+    cross_transfer_index: HashMap<TransactionHash, usize>,
+    /// Transfers whose first leg is included and that have not ended.
+    in_flight_cross_transfers: HashSet<usize>,
+    pub cross_transfer_progress_mem: Vec<LegProgress>,
+    /// Locked transfers that are neither committed nor aborted yet.
+    undecided_locked_transfers: Vec<usize>,
+    migration_mechanism: MigrationMechanism,
+    pub migrations: Vec<Migration>,
+    /// Index of the migration each leg belongs to.
+    migration_index: HashMap<TransactionHash, usize>,
+    /// Moves whose account has left the source shard but not arrived on the target shard.
+    in_flight_migrations: HashSet<usize>,
+    pub migration_progress_mem: Vec<LegProgress>,
+    pub rollups: Vec<Rollup>,
+    pub rollup_batches: Vec<RollupBatch>,
+    /// The rollup in which each user transfers, if any.
+    rollup_of_user: Vec<Option<usize>>,
+    pub rollup_pending_transaction_num_mem: Vec<Vec<usize>>,
+    /// The relayer of each execution shard, indexed by shard id, which pays for applying incoming
+    /// transfers.
+    pub relayers: Vec<Address>,
+    /// The maximum number of transfers a user bundles into one transaction.
+    batch_size: usize,
+    batch_semantics: BatchSemantics,
+    /// How many slots a user stays on a shard on average before considering a move.
+    pub average_shard_switching_interval: Slot,
+    /// The slot in which each transaction was sent.
+    pub sent_slots: HashMap<TransactionHash, Slot>,
+    /// Whether or not every transaction sent is traced.
+    trace: bool,
+    pub transaction_traces: Vec<TransactionTrace>,
+    rng: RandomStreams,
+    pub log_level: LogLevel,
 }
 
 impl Environment {
@@ -18,97 +69,363 @@ impl Environment {
             users: Vec::new(),
             user_num_mem: Vec::new(),
             mempool_tx_mem: Vec::new(),
+            pending_user_num_mem: Vec::new(),
+            in_flight_receipt_num_mem: Vec::new(),
+            state_size_mem: Vec::new(),
+            evicted_account_num_mem: Vec::new(),
             user_num: DEFAULT_USER_NUM,
+            missed_proposal_probabilities: vec![
+                DEFAULT_MISSED_PROPOSAL_PROBABILITY;
+                DEFAULT_SHARD_NUM
+            ],
+            token_addrs: Vec::new(),
+            token_issuers: Vec::new(),
+            percentage_of_token_transfers: DEFAULT_PERCENTAGE_OF_TOKEN_TRANSFERS,
+            async_calls: false,
+            cross_calls: Vec::new(),
+            cross_call_index: HashMap::new(),
+            cross_shard_transfer_mode: CrossShardTransferMode::default(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            cross_transfers: Vec::new(),
+            cross_transfer_index: HashMap::new(),
+            in_flight_cross_transfers: HashSet::new(),
+            cross_transfer_progress_mem: Vec::new(),
+            undecided_locked_transfers: Vec::new(),
+            migration_mechanism: MigrationMechanism::default(),
+            migrations: Vec::new(),
+            migration_index: HashMap::new(),
+            in_flight_migrations: HashSet::new(),
+            migration_progress_mem: Vec::new(),
+            rollups: Vec::new(),
+            rollup_batches: Vec::new(),
+            rollup_of_user: Vec::new(),
+            rollup_pending_transaction_num_mem: Vec::new(),
+            relayers: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_semantics: BatchSemantics::default(),
+            average_shard_switching_interval: DEFAULT_AVERAGE_SHARD_SWITCHING_INTERVAL,
+            sent_slots: HashMap::new(),
+            trace: false,
+            transaction_traces: Vec::new(),
+            rng: RandomStreams::new(DEFAULT_SEED),
+            log_level: LogLevel::default(),
         }
     }
 
-    pub fn setup(&mut self, arg_matches: &clap::ArgMatches) {
-        println!("Setup...");
+    pub fn setup(&mut self, scenario: &Scenario) {
+        if self.log_level >= LogLevel::Info {
+            println!("Setup...");
+        }
 
-        let percentage_of_minimum =
-            if let Some(percentage_of_minimum) = arg_matches.value_of("PERCENTAGE_OF_MINIMUM") {
-                percentage_of_minimum
-                    .parse()
-                    .expect("PERCENTAGE_OF_MINIMUM must be a positive real number")
-            } else {
-                DEFAULT_PERCENTAGE_OF_MINIMUM
-            };
+        self.rng = RandomStreams::new(scenario.seed);
 
-        let percentage_of_weighted_random = if let Some(percentage_of_weighted_random) =
-            arg_matches.value_of("PERCENTAGE_OF_WEIGHTED_RANDOM")
-        {
-            percentage_of_weighted_random
-                .parse()
-                .expect("PERCENTAGE_OF_WEIGHTED_RANDOM must be a positive real number")
-        } else {
-            DEFAULT_PERCENTAGE_OF_WEIGHTED_RANDOM
+        self.missed_proposal_probabilities = match scenario.missed_proposal_probability.len() {
+            1 => vec![scenario.missed_proposal_probability[0]; scenario.shard_num],
+            shard_num if shard_num == scenario.shard_num => {
+                scenario.missed_proposal_probability.clone()
+            }
+            _ => panic!("MISSED_PROPOSAL_PROBABILITY must have 1 or SHARD_NUM values"),
         };
 
-        let percentage_of_decreasing_minimum = if let Some(percentage_of_decreasing_minimum) =
-            arg_matches.value_of("PERCENTAGE_OF_DECREASING_MINIMUM")
-        {
-            percentage_of_decreasing_minimum
-                .parse()
-                .expect("PERCENTAGE_OF_DECREASING_MINIMUM must be a positive real number")
-        } else {
-            DEFAULT_PERCENTAGE_OF_DECREASING_MINIMUM
-        };
+        self.blockchain.set_shard_num(scenario.shard_num);
+        self.blockchain
+            .set_block_gas_target(scenario.block_gas_target as Gas);
+        self.average_shard_switching_interval = scenario.average_shard_switching_interval;
 
-        let popular_user_exists = arg_matches.is_present("POPULAR_USER_EXISTS");
-        let popular_user_is_switcher = arg_matches.is_present("POPULAR_USER_IS_SWITCHER");
+        // Data shards come first, because they have their own genesis states
+        self.blockchain.set_data_shard_num(scenario.data_shard_num);
 
-        if let Some(user_num) = arg_matches.value_of("USER_NUM") {
-            self.user_num = user_num
-                .parse()
-                .expect("user_num must be a positive integer");
+        self.blockchain.gas_target_rule = scenario.gas_target_rule;
+        if !scenario.preferred_gas_target.is_empty() {
+            self.blockchain
+                .shards
+                .iter_mut()
+                .enumerate()
+                .for_each(|(shard_id, shard)| {
+                    let gas_target = scenario.preferred_gas_target
+                        [shard_id % scenario.preferred_gas_target.len()];
+                    shard.preferred_gas_target = gas_target as Gas;
+                });
         }
+        self.blockchain
+            .shards
+            .iter_mut()
+            .for_each(|shard| shard.fee_market = scenario.fee_market);
+        self.blockchain
+            .set_base_fee_coupling(scenario.base_fee_coupling);
+
+        self.blockchain
+            .shards
+            .iter_mut()
+            .for_each(|shard| shard.state_rent = scenario.state_rent);
+        self.blockchain.outages = scenario.outages.clone();
 
-        if let Some(past_log_file_name) = arg_matches.value_of("BIG_QUERY_CSV") {
-            self.user_graph = UserGraph::new_from_eth1_data(self.user_num, past_log_file_name);
+        self.percentage_of_token_transfers = scenario.percentage_of_token_transfers;
+        self.async_calls = scenario.async_calls;
+        self.cross_shard_transfer_mode = scenario.cross_shard_transfer_mode;
+        self.migration_mechanism = scenario.migration;
+        self.lock_timeout = scenario.lock_timeout;
+
+        self.batch_size = scenario.batch_size;
+        self.batch_semantics = scenario.batch_semantics;
+        self.trace = scenario.trace;
+
+        self.user_num = scenario.user_num;
+        if let Some(past_log_file_name) = &scenario.big_query_csv {
+            self.user_graph = UserGraph::new_from_eth1_data(
+                self.user_num,
+                past_log_file_name,
+                scenario.shard_num,
+            );
         } else {
-            self.user_graph =
-                UserGraph::new_random(self.user_num, popular_user_exists, popular_user_is_switcher);
+            self.user_graph = UserGraph::new_random(self.user_num, &mut self.rng.graph);
         }
+        // The first user is the popular one, and hub contracts follow the users in the address space
+        let popular_user = scenario.popular_user_exists.then_some(0);
+        let user_num = self.user_graph.nodes.len();
+        let hub_groups = [
+            HubGroup::new(
+                popular_user.into_iter().collect(),
+                PERCENTAGE_OF_USERS_TRANSFERRING_TO_POPULAR_USER,
+                1,
+            ),
+            HubGroup::new(
+                (user_num..user_num + scenario.hub_contract_num).collect(),
+                PERCENTAGE_OF_USERS_INTERACTING_WITH_HUBS,
+                HUB_INTERACTION_WEIGHT,
+            ),
+        ];
+        for hub_group in hub_groups.iter() {
+            self.user_graph
+                .add_hub_group(hub_group, &mut self.rng.graph);
+        }
+        self.user_graph.normalize(scenario.global_transaction_num());
 
+        let percentage_of_minimum = scenario.percentage_of_minimum;
+        let percentage_of_weighted_random = scenario.percentage_of_weighted_random;
+        let percentage_of_decreasing_minimum = scenario.percentage_of_decreasing_minimum;
         (0..self.user_graph.nodes.len()).for_each(|addr| {
             self.deploy_account(addr);
 
             #[allow(clippy::collapsible_if)]
-            if popular_user_exists && addr == POPULAR_USER_ADDRESS {
-                if popular_user_is_switcher {
-                    self.users.push(User::new(addr, StrategyType::Minimum));
+            if popular_user == Some(addr) {
+                if scenario.popular_user_is_switcher {
+                    self.users
+                        .push(User::new(addr, StrategyType::Minimum, scenario.shard_num));
                 } else {
-                    self.users.push(User::new(addr, StrategyType::NonSwitcher));
+                    self.users.push(User::new(
+                        addr,
+                        StrategyType::NonSwitcher,
+                        scenario.shard_num,
+                    ));
                 }
             } else {
                 if addr <= (percentage_of_minimum * self.user_graph.nodes.len() as f64) as usize {
-                    self.users.push(User::new(addr, StrategyType::Minimum));
+                    self.users
+                        .push(User::new(addr, StrategyType::Minimum, scenario.shard_num));
                 } else if addr
                     <= ((percentage_of_minimum + percentage_of_weighted_random)
                         * self.user_graph.nodes.len() as f64) as usize
                 {
-                    self.users
-                        .push(User::new(addr, StrategyType::WeightedRandom));
+                    self.users.push(User::new(
+                        addr,
+                        StrategyType::WeightedRandom,
+                        scenario.shard_num,
+                    ));
                 } else if addr
                     <= ((percentage_of_minimum
                         + percentage_of_weighted_random
                         + percentage_of_decreasing_minimum)
                         * self.user_graph.nodes.len() as f64) as usize
                 {
-                    self.users
-                        .push(User::new(addr, StrategyType::DecreasingMinimum));
+                    self.users.push(User::new(
+                        addr,
+                        StrategyType::DecreasingMinimum,
+                        scenario.shard_num,
+                    ));
                 } else {
-                    self.users.push(User::new(addr, StrategyType::NonSwitcher));
+                    self.users.push(User::new(
+                        addr,
+                        StrategyType::NonSwitcher,
+                        scenario.shard_num,
+                    ));
                 }
             }
         });
 
-        println!("Setup is complete.\n");
+        for _ in 0..scenario.hub_contract_num {
+            let addr = self.blockchain.addr_to_shard_id.len();
+            self.deploy_contract(addr, HUB_CONTRACT_STORAGE_SIZE);
+        }
+
+        self.setup_rollups(scenario.percentage_of_rollup_users);
+        if scenario.percentage_of_legacy_users > 0. {
+            self.setup_legacy_users(scenario.percentage_of_legacy_users);
+        }
+        if scenario.sponsored_applies {
+            self.deploy_relayers();
+        }
+        if self.blockchain.shards[0].state_rent != StateRent::Free {
+            self.setup_balances();
+        }
+        self.setup_adversarial_proposers(scenario.adversary, scenario.adversary_share);
+        if self.percentage_of_token_transfers > 0. {
+            self.deploy_token_contracts();
+        }
+
+        if self.log_level >= LogLevel::Info {
+            println!("Setup is complete.\n");
+        }
+    }
+
+    /// Give users random balances, from which they pay the state rent.
+    fn setup_balances(&mut self) {
+        for user in self.users.iter() {
+            let shard_id = self.blockchain.addr_to_shard_id[&user.account_addr];
+            let balance = self.rng.graph.next_u64() as Wei * MAX_INITIAL_BALANCE_WITH_STATE_RENT
+                / u64::MAX as Wei;
+            self.blockchain.shards[shard_id]
+                .accounts
+                .get_mut(&user.account_addr)
+                .unwrap()
+                .balance = balance;
+        }
+    }
+
+    /// Deploy a sequencer on each data shard, and let a share of users transfer inside the rollups.
+    fn setup_rollups(&mut self, percentage_of_rollup_users: f64) {
+        self.rollup_of_user = vec![None; self.users.len()];
+        for data_shard_id in self.blockchain.execution_shard_num()..self.blockchain.shards.len() {
+            let sequencer = self.blockchain.addr_to_shard_id.len();
+            self.deploy_account_in_shard(sequencer, data_shard_id);
+            self.rollups
+                .push(Rollup::new(self.rollups.len(), sequencer, data_shard_id));
+        }
+
+        if self.rollups.is_empty() || percentage_of_rollup_users == 0. {
+            return;
+        }
+        for addr in 0..self.users.len() {
+            if self.rng.graph.next_u32() < (percentage_of_rollup_users * u32::MAX as f64) as u32 {
+                self.rollup_of_user[addr] = Some(addr % self.rollups.len());
+            }
+        }
+    }
+
+    /// Give a share of users wallets that only send legacy transactions.
+    fn setup_legacy_users(&mut self, percentage_of_legacy_users: f64) {
+        for user in self.users.iter_mut() {
+            user.legacy =
+                self.rng.graph.next_u32() < (percentage_of_legacy_users * u32::MAX as f64) as u32;
+        }
+    }
+
+    /// Deploy a relayer on each execution shard.
+    fn deploy_relayers(&mut self) {
+        for shard_id in 0..self.blockchain.execution_shard_num() {
+            let relayer = self.blockchain.addr_to_shard_id.len();
+            self.deploy_account_in_shard(relayer, shard_id);
+            self.relayers.push(relayer);
+        }
+    }
+
+    /// Make the proposers of the first `adversary_share` of shards adversarial.
+    fn setup_adversarial_proposers(&mut self, adversary: ProposerBehavior, adversary_share: f64) {
+        let adversarial_shard_num =
+            (adversary_share * self.blockchain.shards.len() as f64).round() as usize;
+        for shard in self
+            .blockchain
+            .shards
+            .iter_mut()
+            .take(adversarial_shard_num)
+        {
+            shard.proposer_behavior = adversary;
+        }
+
+        if adversary != ProposerBehavior::SelfPreferencing {
+            return;
+        }
+        for addr in 0..self.user_graph.nodes.len() {
+            let shard_id = self.get_shard_id_from_addr(addr);
+            if shard_id < adversarial_shard_num
+                && self.rng.graph.next_u32()
+                    < (PERCENTAGE_OF_USERS_AFFILIATED_WITH_PROPOSER * u32::MAX as f64) as u32
+            {
+                self.blockchain.shards[shard_id]
+                    .proposer_senders
+                    .insert(addr);
+            }
+        }
+    }
+
+    /// Whether or not the user is not affiliated with any proposer.
+    pub fn is_honest_user(&self, addr: Address) -> bool {
+        self.blockchain
+            .shards
+            .iter()
+            .all(|shard| !shard.proposer_senders.contains(&addr))
+    }
+
+    /// Deploy token contracts and their issuers after the users' accounts,
+    /// and allocate INITIAL_TOKEN_BALANCE of each token to every user at genesis.
+    fn deploy_token_contracts(&mut self) {
+        let user_num = self.user_graph.nodes.len();
+        for _ in 0..TOKEN_CONTRACT_NUM {
+            let token = self.blockchain.addr_to_shard_id.len();
+            let home_shard_id = self.deploy_contract(token, 0);
+            let issuer = self.blockchain.addr_to_shard_id.len();
+            self.deploy_account_in_shard(issuer, home_shard_id);
+            self.blockchain.shards[home_shard_id].deploy_token_contract(TokenContract::new(
+                token,
+                issuer,
+                home_shard_id,
+            ));
+            self.token_addrs.push(token);
+            self.token_issuers
+                .push(TokenIssuer::new(token, issuer, home_shard_id));
+        }
+        for shard in self.blockchain.shards.iter_mut() {
+            for account in shard
+                .accounts
+                .values_mut()
+                .filter(|account| !account.is_contract())
+            {
+                for &token in self.token_addrs.iter() {
+                    account.token_balances.insert(token, INITIAL_TOKEN_BALANCE);
+                }
+            }
+        }
+        for &token in self.token_addrs.iter() {
+            let home_shard_id = self.blockchain.addr_to_shard_id[&token];
+            self.blockchain.shards[home_shard_id]
+                .token_contracts
+                .get_mut(&token)
+                .expect("the token contract does not exist")
+                .total_supply = INITIAL_TOKEN_BALANCE * user_num as Wei;
+        }
+    }
+
+    /// Deploy new contract account, and return its shard id.
+    fn deploy_contract(&mut self, addr: usize, storage_size: usize) -> usize {
+        let shard_id = addr % self.blockchain.execution_shard_num();
+        let account = Account::new_contract(addr, shard_id, storage_size);
+        self.blockchain.account_num += 1;
+        self.blockchain.addr_to_shard_id.insert(addr, shard_id);
+
+        self.blockchain.shards[shard_id]
+            .accounts
+            .entry(addr)
+            .or_insert(account);
+        shard_id
     }
 
     /// Deploy new account.
     fn deploy_account(&mut self, addr: usize) {
-        let shard_id = addr % self.blockchain.shards.len();
+        self.deploy_account_in_shard(addr, addr % self.blockchain.execution_shard_num());
+    }
+
+    /// Deploy new account in the shard.
+    fn deploy_account_in_shard(&mut self, addr: usize, shard_id: usize) {
         let account = Account::new(addr, shard_id);
         self.blockchain.account_num += 1;
         self.blockchain.addr_to_shard_id.insert(addr, shard_id);
@@ -123,13 +440,22 @@ impl Environment {
     pub fn process(&mut self) {
         let transactions = self.generate_transactions_per_slot();
         self.broadcast_transactions_per_slot(transactions);
-        self.blockchain.process_slots(self.blockchain.slot + 1);
+        self.schedule_missed_proposals_per_slot();
+        let slot = self.blockchain.slot;
+        self.blockchain.process_slots(slot + 1);
+        self.record_leg_progress(slot);
 
         let account_num = self
             .blockchain
             .shards
             .iter()
-            .map(|shard| shard.accounts.len())
+            .map(|shard| {
+                shard
+                    .accounts
+                    .values()
+                    .filter(|account| !account.is_contract())
+                    .count()
+            })
             .collect();
         self.user_num_mem.push(account_num);
 
@@ -141,12 +467,153 @@ impl Environment {
             .collect();
         self.mempool_tx_mem.push(mempool_tx_num);
 
-        self.print_statistics();
+        let pending_user_num = (0..self.blockchain.shards.len())
+            .map(|shard_id| {
+                self.users
+                    .iter()
+                    .filter(|user| !user.unconfirmed_transactions_in_shard[shard_id].is_empty())
+                    .count()
+            })
+            .collect();
+        self.pending_user_num_mem.push(pending_user_num);
+
+        let in_flight_receipt_num = (0..self.blockchain.shards.len())
+            .map(|shard_id| {
+                self.users
+                    .iter()
+                    .map(|user| user.unsent_transactions_in_shard[shard_id].len())
+                    .sum()
+            })
+            .collect();
+        self.in_flight_receipt_num_mem.push(in_flight_receipt_num);
+
+        let state_size = self
+            .blockchain
+            .shards
+            .iter()
+            .map(|shard| shard.state_size())
+            .collect();
+        self.state_size_mem.push(state_size);
+
+        let evicted_account_num = self
+            .blockchain
+            .shards
+            .iter()
+            .map(|shard| shard.evicted_account_num)
+            .collect();
+        self.evicted_account_num_mem.push(evicted_account_num);
+
+        let rollup_pending_transaction_num = self
+            .rollups
+            .iter()
+            .map(|rollup| rollup.pending_transactions.len())
+            .collect();
+        self.rollup_pending_transaction_num_mem
+            .push(rollup_pending_transaction_num);
+
+        for user in self.users.iter_mut() {
+            if let Some(&shard_id) = self.blockchain.addr_to_shard_id.get(&user.account_addr) {
+                user.slots_in_shard[shard_id] += 1;
+            }
+        }
+
+        if self.log_level >= LogLevel::Debug {
+            self.print_statistics();
+        }
+    }
+
+    /// Add the fees of the legs included in the slot to their transfers and moves, and record
+    /// how many of them completed in the slot and how many are between legs.
+    fn record_leg_progress(&mut self, slot: Slot) {
+        let mut touched_cross_transfers = HashSet::new();
+        let mut touched_migrations = HashSet::new();
+        for shard in self.blockchain.shards.iter() {
+            for transaction in shard.blocks[slot as usize].executed_transactions.iter() {
+                for index in 0..transaction.functions.len() {
+                    let hash = transaction.function_hash(index);
+                    let (burned_fee, tip) = shard.function_fee(slot, transaction, index);
+                    if let Some(&i) = self.cross_transfer_index.get(&hash) {
+                        self.cross_transfers[i].burned_fee += burned_fee;
+                        self.cross_transfers[i].tip += tip;
+                        touched_cross_transfers.insert(i);
+                    }
+                    if let Some(&i) = self.migration_index.get(&hash) {
+                        self.migrations[i].burned_fee += burned_fee;
+                        self.migrations[i].tip += tip;
+                        touched_migrations.insert(i);
+                    }
+                }
+            }
+        }
+
+        // Only the transfers and moves with a leg included in the slot can change their outcomes
+        let mut progress = LegProgress::default();
+        for i in touched_cross_transfers {
+            let cross_transfer = &self.cross_transfers[i];
+            let legs = cross_transfer
+                .leg_hashes
+                .iter()
+                .filter_map(|hash| self.blockchain.get_receipt(*hash))
+                .collect::<Vec<_>>();
+            match cross_transfer.outcome(&legs).0 {
+                TransferOutcome::Pending => {
+                    self.in_flight_cross_transfers.insert(i);
+                }
+                outcome => {
+                    self.in_flight_cross_transfers.remove(&i);
+                    if outcome == TransferOutcome::Completed {
+                        progress.completed_num += 1;
+                        progress.latency += slot - cross_transfer.created_slot;
+                        progress.fee += cross_transfer.burned_fee + cross_transfer.tip;
+                    }
+                }
+            }
+        }
+        progress.in_flight_num = self.in_flight_cross_transfers.len();
+        self.cross_transfer_progress_mem.push(progress);
+
+        let mut progress = LegProgress::default();
+        for i in touched_migrations {
+            let migration = &self.migrations[i];
+            let legs = migration
+                .leg_hashes
+                .iter()
+                .filter_map(|hash| self.blockchain.get_receipt(*hash))
+                .collect::<Vec<_>>();
+            match migration.outcome(&legs).0 {
+                TransferOutcome::Pending => {
+                    self.in_flight_migrations.insert(i);
+                }
+                outcome => {
+                    self.in_flight_migrations.remove(&i);
+                    if outcome == TransferOutcome::Completed {
+                        progress.completed_num += 1;
+                        progress.latency += slot - migration.created_slot;
+                        progress.fee += migration.burned_fee + migration.tip;
+                    }
+                }
+            }
+        }
+        progress.in_flight_num = self.in_flight_migrations.len();
+        self.migration_progress_mem.push(progress);
+    }
+
+    /// Draw which proposers miss their proposals in the current slot.
+    fn schedule_missed_proposals_per_slot(&mut self) {
+        let slot = self.blockchain.slot;
+        for shard_id in 0..self.blockchain.shards.len() {
+            let probability = self.missed_proposal_probabilities[shard_id];
+            if probability > 0.
+                && self.rng.proposer.next_u32() < (probability * u32::MAX as f64) as u32
+            {
+                self.blockchain.schedule_missed_proposal(shard_id, slot);
+            }
+        }
     }
 
     fn print_statistics(&self) {
         const DEBUG_SHARD_NUM: usize = 10;
-        let debug_shard_num = std::cmp::min(DEBUG_SHARD_NUM, SHARD_NUM);
+        let debug_shard_num = std::cmp::min(DEBUG_SHARD_NUM, self.blockchain.shards.len());
 
         print!("{:>20}", "mempool:");
         (0..debug_shard_num).for_each(|i| {
@@ -190,10 +657,14 @@ impl Environment {
     }
 
     fn get_user_next_shard_ids_and_reduction(&mut self) -> Vec<(usize, Option<GasPrice>)> {
-        self.users
+        let mut rng = self.rng.strategy.clone();
+        let next_shard_ids = self
+            .users
             .iter()
-            .map(|user| user.pick_low_fee_shard_id_and_movement_fee_cap(&self))
-            .collect()
+            .map(|user| user.pick_low_fee_shard_id_and_movement_fee_cap(self, &mut rng))
+            .collect();
+        self.rng.strategy = rng;
+        next_shard_ids
     }
 
     fn get_shard_id_from_addr(&self, shargrila_addr: Address) -> usize {
@@ -208,21 +679,113 @@ impl Environment {
         self.user_graph.get_edge(from, to).fee_cap
     }
 
+    /// The fee caps of the receipts of the transaction, which follow the receipt base fees of its shard
+    /// up to its fee cap.
+    fn determine_receipt_fee_caps(&self, transaction: &Transaction) -> (GasPrice, GasPrice) {
+        let base_fees = self.blockchain.shards[transaction.shard_id].get_base_fees();
+        (
+            std::cmp::min(
+                base_fees.receipt_out * RECEIPT_FEE_CAP_BASE_FEE_MULTIPLIER,
+                transaction.fee_cap,
+            ),
+            std::cmp::min(
+                base_fees.receipt_in * RECEIPT_FEE_CAP_BASE_FEE_MULTIPLIER,
+                transaction.fee_cap,
+            ),
+        )
+    }
+
     /// Broadcast transactions.
     fn broadcast_transactions_per_slot(&mut self, transactions: Vec<TransactionAndReceipt>) {
-        for (transaction, receipt) in transactions {
+        for (mut transaction, receipt) in transactions {
+            // Wrap the transaction in the envelope of the sender's wallet
+            let is_apply = matches!(
+                transaction.functions[0].ftype,
+                FunctionType::ApplyCrossTransfer | FunctionType::ApplyCrossTokenTransfer
+            );
+            let is_legacy = self
+                .users
+                .get(transaction.from)
+                .is_some_and(|user| user.legacy);
+            match self.relayers.get(transaction.shard_id) {
+                Some(&relayer) if is_apply => transaction = transaction.into_sponsored(relayer),
+                _ if is_legacy => transaction = transaction.into_legacy(),
+                _ => {}
+            }
+            if self.blockchain.shards[transaction.shard_id].fee_market
+                == FeeMarket::MultiDimensional
+            {
+                let (receipt_out_fee_cap, receipt_in_fee_cap) =
+                    self.determine_receipt_fee_caps(&transaction);
+                transaction =
+                    transaction.with_receipt_fee_caps(receipt_out_fee_cap, receipt_in_fee_cap);
+            }
+            self.sent_slots
+                .insert(transaction.hash, self.blockchain.slot);
+            if self.trace {
+                self.trace_transaction(&transaction, &receipt);
+            }
             self.blockchain.shards[transaction.shard_id].push_transaction(transaction, receipt);
         }
     }
 
+    /// Record where each function of a transaction comes from and goes to as it is sent.
+    fn trace_transaction(&mut self, transaction: &Transaction, receipt: &Option<Receipt>) {
+        let shard_id = transaction.shard_id;
+        // A later leg comes from the shard that executed the previous leg
+        let source_shard_id = receipt
+            .as_ref()
+            .and_then(|receipt| {
+                self.blockchain
+                    .shards
+                    .iter()
+                    .position(|shard| shard.receipts.contains_key(&receipt.transaction_hash))
+            })
+            .unwrap_or(shard_id);
+        for (index, function) in transaction.functions.iter().enumerate() {
+            let kind = TransactionKind::of(&function.ftype);
+            let hash = transaction.function_hash(index);
+            let destination_shard_id = match kind {
+                TransactionKind::CrossCreate | TransactionKind::MoveOut => {
+                    if let Some(&i) = self.cross_transfer_index.get(&hash) {
+                        self.cross_transfers[i].destination_shard_id
+                    } else if let Some(&i) = self.cross_call_index.get(&hash) {
+                        self.cross_calls[i].contract_shard_id
+                    } else if let Some(&i) = self.migration_index.get(&hash) {
+                        self.migrations[i].target_shard_id
+                    } else {
+                        shard_id
+                    }
+                }
+                _ => shard_id,
+            };
+            self.transaction_traces.push(TransactionTrace {
+                hash,
+                transaction_hash: transaction.hash,
+                index,
+                creator: transaction.from,
+                kind,
+                source_shard_id,
+                destination_shard_id,
+                created_slot: self.blockchain.slot,
+            });
+        }
+    }
+
     /// Generate transactions for one slot.
     fn generate_transactions_per_slot(&mut self) -> Vec<TransactionAndReceipt> {
         let mut transactions: Vec<TransactionAndReceipt> = Vec::new();
 
         let precomputed_next_shard_ids_and_reduction = self.get_user_next_shard_ids_and_reduction();
+        self.abort_timed_out_cross_transfers();
 
         let user_graph_edges = self.user_graph.edges.clone();
         for (from, edges) in user_graph_edges.iter().enumerate() {
+            if self.blockchain.get_account(from).1.is_none() {
+                // Evicted or hibernated
+                continue;
+            }
+
             // Whether or not transactions was executed
             let mut executed_transaction_hashes = HashSet::new();
             let mut moved_account_addr_and_new_shard_id = Vec::new();
@@ -241,13 +804,12 @@ impl Environment {
                             if transaction.hash == executed_transaction.hash {
                                 executed_transaction_hashes.insert(transaction.hash);
                             }
-                            if executed_transaction.functions[0].ftype
-                                == FunctionType::ApplyCrossTransferAll
+                            let function = &executed_transaction.functions[0];
+                            if function.ftype == FunctionType::ApplyCrossTransferAll
+                                || function.ftype == FunctionType::PullAccount
                             {
-                                moved_account_addr_and_new_shard_id.push((
-                                    executed_transaction.from,
-                                    executed_transaction.shard_id,
-                                ));
+                                moved_account_addr_and_new_shard_id
+                                    .push((function.target, executed_transaction.shard_id));
                             }
                         });
                 }
@@ -263,20 +825,25 @@ impl Environment {
             }
 
             // Eliminate confirmed transactions
-            (0..SHARD_NUM).for_each(|shard_id| {
-                self.users[from].unconfirmed_transactions_in_shard[shard_id] = self.users[from]
-                    .unconfirmed_transactions_in_shard[shard_id]
-                    .iter()
-                    .cloned()
-                    .filter(|(_, (transaction, _))| {
+            (0..self.blockchain.shards.len()).for_each(|shard_id| {
+                self.users[from].unconfirmed_transactions_in_shard[shard_id].retain(
+                    |(_, (transaction, _))| {
                         !executed_transaction_hashes.contains(&transaction.hash)
-                    })
-                    .collect();
+                    },
+                );
             });
 
+            self.reclaim_stuck_cross_transfers(from);
+
             // If a waiting transaction can be sent, send it.
             transactions.append(&mut self.get_pending_transactions_per_slot(from));
 
+            if self.rollup_of_user[from].is_some() {
+                // Rollup users stay in their rollups
+                self.generate_rollup_transactions_per_slot(from, edges);
+                continue;
+            }
+
             // Movement
             transactions.append(&mut self.generate_movement_transactions_per_slot(
                 from,
@@ -286,15 +853,19 @@ impl Environment {
             // Transfer
             transactions.append(&mut self.generate_transfer_transactions_per_slot(from, edges));
         }
+        transactions.append(&mut self.post_batches());
+        transactions.append(&mut self.mint_tokens());
 
-        println!("The number of transactions: {}", transactions.len());
+        if self.log_level >= LogLevel::Debug {
+            println!("The number of transactions: {}", transactions.len());
+        }
 
         transactions
     }
 
     fn get_pending_transactions_per_slot(&mut self, from: Address) -> Vec<TransactionAndReceipt> {
         let mut transactions = Vec::new();
-        for shard_id in 0..SHARD_NUM {
+        for shard_id in 0..self.blockchain.shards.len() {
             if self.users[from].unconfirmed_transactions_in_shard[shard_id].is_empty()
                 && !self.users[from].unsent_transactions_in_shard[shard_id].is_empty()
             {
@@ -308,23 +879,41 @@ impl Environment {
                         .push_front((transaction, prev_transaction_hash));
                     continue;
                 }
-                transaction.nonce = self.users[from].nonce_in_shard[shard_id];
+                transaction.set_nonce(self.users[from].nonce_in_shard[shard_id]);
 
-                let receipt = {
-                    let (_, account) = self.blockchain.get_account(from);
-                    if account.is_none() {
-                        continue;
-                    }
-                    let account = account.unwrap();
-                    self.blockchain.shards[account.shard_id]
-                        .receipts
-                        .get(&prev_transaction_hash)
-                };
-                if receipt.is_none() || !receipt.unwrap().status {
+                let receipt = self.blockchain.get_receipt(prev_transaction_hash);
+                if receipt.is_none() {
+                    // The source transaction is not included yet (e.g., the source shard is stalled).
+                    self.users[from].unsent_transactions_in_shard[shard_id]
+                        .push_front((transaction, prev_transaction_hash));
+                    continue;
+                }
+                if !receipt.unwrap().status {
                     continue;
                 }
                 let receipt = Some(receipt.unwrap().clone());
 
+                if let Some(&index) = self.cross_call_index.get(&prev_transaction_hash) {
+                    self.cross_calls[index].leg_hashes.push(transaction.hash);
+                    self.cross_call_index.insert(transaction.hash, index);
+                    if transaction.functions[0].ftype == FunctionType::ApplyCrossCall {
+                        self.send_callback_later(index, &transaction);
+                    }
+                }
+                if let Some(&index) = self.migration_index.get(&prev_transaction_hash) {
+                    self.migrations[index].leg_hashes.push(transaction.hash);
+                    self.migration_index.insert(transaction.hash, index);
+                }
+                if let Some(&index) = self.cross_transfer_index.get(&prev_transaction_hash) {
+                    if !self.prepare_cross_transfer_leg(
+                        index,
+                        &transaction,
+                        receipt.as_ref().unwrap(),
+                    ) {
+                        continue;
+                    }
+                }
+
                 transactions.push((transaction.clone(), receipt.clone()));
                 self.users[from].unconfirmed_transactions_in_shard[shard_id]
                     .push((self.blockchain.slot, (transaction, receipt)));
@@ -334,13 +923,121 @@ impl Environment {
         transactions
     }
 
+    /// Hand the user's transfers to the sequencer of the rollup instead of sending them on-chain.
+    fn generate_rollup_transactions_per_slot(
+        &mut self,
+        from: Address,
+        edges: &HashMap<Address, UserGraphEdge>,
+    ) {
+        let rollup_id = self.rollup_of_user[from].unwrap();
+        let slot = self.blockchain.slot;
+        let mut to_addrs = edges.keys().copied().collect::<Vec<_>>();
+        to_addrs.sort_unstable();
+        for to in to_addrs {
+            let edge = &edges[&to];
+            let p = self.rng.transfer.next_u32() as f64 / u32::MAX as f64;
+            if p <= edge.transfer_probability_in_slot {
+                self.rollups[rollup_id].pending_transactions.push_back(slot);
+            }
+        }
+    }
+
+    /// Let each sequencer post its pending rollup transactions in a batch,
+    /// once the previous batch is included.
+    fn post_batches(&mut self) -> Vec<TransactionAndReceipt> {
+        let mut transactions = Vec::new();
+        for rollup in self.rollups.iter_mut() {
+            if let Some(batch_index) = rollup.posting_batch {
+                if self
+                    .blockchain
+                    .get_receipt(self.rollup_batches[batch_index].hash)
+                    .is_none()
+                {
+                    continue;
+                }
+                rollup.posting_batch = None;
+            }
+            if rollup.pending_transactions.is_empty() {
+                continue;
+            }
+
+            let transaction_num =
+                std::cmp::min(rollup.pending_transactions.len(), MAX_BATCH_TRANSACTION_NUM);
+            let created_slots = rollup
+                .pending_transactions
+                .drain(..transaction_num)
+                .collect::<Vec<_>>();
+            let size = ROLLUP_TRANSACTION_SIZE * transaction_num as Gas;
+            let transaction = Transaction::new(
+                rollup.sequencer,
+                rollup.sequencer,
+                rollup.data_shard_id,
+                vec![Function {
+                    source: rollup.sequencer,
+                    target: rollup.sequencer,
+                    ftype: FunctionType::PostBatch,
+                    calldata: Calldata::Batch {
+                        rollup_id: rollup.id,
+                        size,
+                    }
+                    .encode(),
+                }],
+                DEFAULT_GAS_PREMIUM,
+                SEQUENCER_FEE_CAP,
+                rollup.next_nonce(),
+            );
+            rollup.posting_batch = Some(self.rollup_batches.len());
+            self.rollup_batches.push(RollupBatch {
+                rollup_id: rollup.id,
+                data_shard_id: rollup.data_shard_id,
+                hash: transaction.hash,
+                posted_slot: self.blockchain.slot,
+                created_slots,
+                size,
+            });
+            transactions.push((transaction, None));
+        }
+        transactions
+    }
+
+    /// Let each issuer mint TOKEN_MINT_AMOUNT of its token to itself at the start of each epoch.
+    fn mint_tokens(&mut self) -> Vec<TransactionAndReceipt> {
+        if !self.blockchain.slot.is_multiple_of(SLOTS_PER_EPOCH) {
+            return Vec::new();
+        }
+        self.token_issuers
+            .iter_mut()
+            .map(|issuer| {
+                let transaction = Transaction::new(
+                    issuer.addr,
+                    issuer.addr,
+                    issuer.home_shard_id,
+                    vec![Function {
+                        source: issuer.addr,
+                        target: issuer.addr,
+                        ftype: FunctionType::Mint,
+                        calldata: Calldata::Token {
+                            token: issuer.token,
+                            amount: TOKEN_MINT_AMOUNT,
+                        }
+                        .encode(),
+                    }],
+                    DEFAULT_GAS_PREMIUM,
+                    ISSUER_FEE_CAP,
+                    issuer.next_nonce(),
+                );
+                (transaction, None)
+            })
+            .collect()
+    }
+
     fn generate_movement_transactions_per_slot(
         &mut self,
         from: Address,
         next_shard_ids: &[(usize, Option<GasPrice>)],
     ) -> Vec<TransactionAndReceipt> {
         let mut transactions = Vec::new();
-        if RND.lock().unwrap().next_u32() <= u32::MAX / AVERAGE_SHARD_SWITCHING_INTERVAL as u32
+        if self.rng.movement.next_u32() <= u32::MAX / self.average_shard_switching_interval as u32
             && self.blockchain.slot > INITIAL_SETUP_SLOTS
         {
             let shard_f = self.get_shard_id_from_addr(from);
@@ -360,6 +1057,21 @@ impl Environment {
                 {
                     return Vec::new();
                 }
+                let (create_ftype, create_calldata, apply_ftype, apply_calldata) =
+                    match self.migration_mechanism {
+                        MigrationMechanism::Receipt => (
+                            FunctionType::CreateCrossTransferAll,
+                            Calldata::ShardId(shard_t),
+                            FunctionType::ApplyCrossTransferAll,
+                            Calldata::ShardId(shard_f),
+                        ),
+                        MigrationMechanism::Yank => (
+                            FunctionType::YankAccount,
+                            Calldata::ShardId(shard_t),
+                            FunctionType::PullAccount,
+                            Calldata::Empty,
+                        ),
+                    };
                 let nonce = self.users[from].nonce_in_shard[shard_f];
                 let transaction = Transaction::new(
                     from,
@@ -368,8 +1080,8 @@ impl Environment {
                     vec![Function {
                         source: from,
                         target: from,
-                        ftype: FunctionType::CreateCrossTransferAll,
-                        calldata: "".to_string(),
+                        ftype: create_ftype,
+                        calldata: create_calldata.encode(),
                     }],
                     DEFAULT_GAS_PREMIUM,
                     fee_cap,
@@ -379,7 +1091,16 @@ impl Environment {
                 self.users[from].unconfirmed_transactions_in_shard[shard_f]
                     .push((self.blockchain.slot, (transaction.clone(), None)));
                 self.users[from].nonce_in_shard[shard_f] += 1;
+                self.migration_index
+                    .insert(transaction.hash, self.migrations.len());
+                self.migrations.push(Migration::new(
+                    &transaction,
+                    self.migration_mechanism,
+                    shard_t,
+                    self.blockchain.slot,
+                ));
 
+                // Anyone can pull a yanked account, but the owner does it here.
                 self.users[from].unsent_transactions_in_shard[shard_t].push_back((
                     Transaction::new(
                         from,
@@ -388,8 +1109,8 @@ impl Environment {
                         vec![Function {
                             source: from,
                             target: from,
-                            ftype: FunctionType::ApplyCrossTransferAll,
-                            calldata: shard_f.to_string(),
+                            ftype: apply_ftype,
+                            calldata: apply_calldata.encode(),
                         }],
                         DEFAULT_GAS_PREMIUM,
                         fee_cap,
@@ -402,21 +1123,258 @@ impl Environment {
         transactions
     }
 
+    /// Queue the callback of a cross-shard call on the origin shard.
+    fn send_callback_later(&mut self, index: usize, apply_transaction: &Transaction) {
+        let from = apply_transaction.from;
+        let origin_shard_id = self.cross_calls[index].origin_shard_id;
+        self.users[from].unsent_transactions_in_shard[origin_shard_id].push_back((
+            Transaction::new(
+                from,
+                from,
+                origin_shard_id,
+                vec![Function {
+                    source: from,
+                    target: from,
+                    ftype: FunctionType::ApplyCallback,
+                    calldata: "".to_string(),
+                }],
+                DEFAULT_GAS_PREMIUM,
+                apply_transaction.fee_cap,
+                DUMMY_NONCE, // update when sending
+            ),
+            apply_transaction.hash,
+        ));
+    }
+
+    /// Record a leg of a cross-shard transfer that is being sent and queue the next leg.
+    /// Returns false if the leg is no longer needed.
+    fn prepare_cross_transfer_leg(
+        &mut self,
+        index: usize,
+        transaction: &Transaction,
+        receipt: &Receipt,
+    ) -> bool {
+        let ftype = transaction.functions[0].ftype.clone();
+        if ftype == FunctionType::FinalizeCrossTransfer {
+            // Nothing to receive if the source shard refunded the locked funds
+            match Calldata::parse(&ftype, &receipt.data) {
+                Ok(Calldata::Decision { commit: true, .. }) => {}
+                _ => return false,
+            }
+        }
+
+        self.cross_transfers[index]
+            .leg_hashes
+            .push(transaction.hash);
+        self.cross_transfer_index.insert(transaction.hash, index);
+
+        let cross_transfer = &self.cross_transfers[index];
+        let (from, source_shard_id, destination_shard_id) = (
+            cross_transfer.from,
+            cross_transfer.source_shard_id,
+            cross_transfer.destination_shard_id,
+        );
+        let next_leg = match ftype {
+            FunctionType::ConfirmCrossTransfer => {
+                Some((source_shard_id, FunctionType::CommitCrossTransfer))
+            }
+            FunctionType::CommitCrossTransfer => {
+                self.cross_transfers[index].decided = true;
+                match Calldata::parse(&ftype, &receipt.data) {
+                    Ok(Calldata::Vote { commit: true, .. }) => {
+                        Some((destination_shard_id, FunctionType::FinalizeCrossTransfer))
+                    }
+                    _ => None,
+                }
+            }
+            FunctionType::AbortCrossTransfer => {
+                self.cross_transfers[index].decided = true;
+                None
+            }
+            FunctionType::ReclaimCrossTransfer => {
+                self.cross_transfers[index].reclaimed = true;
+                None
+            }
+            _ => None,
+        };
+        if let Some((shard_id, ftype)) = next_leg {
+            self.users[from].unsent_transactions_in_shard[shard_id].push_back((
+                Transaction::new(
+                    from,
+                    transaction.to,
+                    shard_id,
+                    vec![Function {
+                        source: from,
+                        target: transaction.to,
+                        ftype,
+                        calldata: "".to_string(),
+                    }],
+                    DEFAULT_GAS_PREMIUM,
+                    transaction.fee_cap,
+                    DUMMY_NONCE, // update when sending
+                ),
+                transaction.hash,
+            ));
+        }
+        true
+    }
+
+    /// Give up applying cross-shard transfers whose receipts have expired, e.g. because the apply
+    /// is censored or priced out, and reclaim them on the source shard.
+    fn reclaim_stuck_cross_transfers(&mut self, from: Address) {
+        let slot = self.blockchain.slot;
+        let blockchain = &self.blockchain;
+        let cross_transfers = &self.cross_transfers;
+        let cross_transfer_index = &self.cross_transfer_index;
+        // The index of the transfer if the receipt of its first leg has expired
+        let expired = |hash: &TransactionHash| -> Option<usize> {
+            let &index = cross_transfer_index.get(hash)?;
+            let cross_transfer = &cross_transfers[index];
+            if cross_transfer.mode != CrossShardTransferMode::Receipt
+                || cross_transfer.reclaimed
+                || cross_transfer.leg_hashes[0] != *hash
+            {
+                return None;
+            }
+            let receipt = blockchain.get_receipt(*hash)?;
+            if receipt.status && receipt.is_expired(slot) {
+                Some(index)
+            } else {
+                None
+            }
+        };
+
+        let mut stuck = Vec::new();
+        let user = &mut self.users[from];
+        for shard_id in 0..self.blockchain.shards.len() {
+            user.unsent_transactions_in_shard[shard_id].retain(|(_, prev_transaction_hash)| {
+                match expired(prev_transaction_hash) {
+                    Some(index) => {
+                        stuck.push(index);
+                        false
+                    }
+                    None => true,
+                }
+            });
+            user.unconfirmed_transactions_in_shard[shard_id].retain(|(_, (_, receipt))| {
+                match receipt
+                    .as_ref()
+                    .and_then(|receipt| expired(&receipt.transaction_hash))
+                {
+                    Some(index) => {
+                        stuck.push(index);
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+
+        for index in stuck {
+            let cross_transfer = &self.cross_transfers[index];
+            self.users[from].unsent_transactions_in_shard[cross_transfer.source_shard_id]
+                .push_back((
+                    Transaction::new(
+                        from,
+                        from,
+                        cross_transfer.source_shard_id,
+                        vec![Function {
+                            source: from,
+                            target: from,
+                            ftype: FunctionType::ReclaimCrossTransfer,
+                            calldata: "".to_string(),
+                        }],
+                        DEFAULT_GAS_PREMIUM,
+                        cross_transfer.fee_cap,
+                        DUMMY_NONCE, // update when sending
+                    ),
+                    cross_transfer.leg_hashes[0],
+                ));
+            self.cross_transfers[index].reclaimed = true;
+        }
+    }
+
+    /// Abort the locked transfers whose deadline has passed before they were committed.
+    fn abort_timed_out_cross_transfers(&mut self) {
+        let slot = self.blockchain.slot;
+        let cross_transfers = &self.cross_transfers;
+        let mut timed_out = Vec::new();
+        self.undecided_locked_transfers.retain(|&index| {
+            let cross_transfer = &cross_transfers[index];
+            if cross_transfer.decided {
+                return false;
+            }
+            if slot > cross_transfer.deadline.unwrap() {
+                timed_out.push(index);
+                return false;
+            }
+            true
+        });
+
+        for index in timed_out {
+            let cross_transfer = &self.cross_transfers[index];
+            let from = cross_transfer.from;
+            let source_shard_id = cross_transfer.source_shard_id;
+            let lock_hash = cross_transfer.leg_hashes[0];
+            let leg_hashes = cross_transfer.leg_hashes.clone();
+
+            // Drop the legs that have not been sent yet
+            for shard_id in [source_shard_id, cross_transfer.destination_shard_id] {
+                self.users[from].unsent_transactions_in_shard[shard_id].retain(
+                    |(_, prev_transaction_hash)| !leg_hashes.contains(prev_transaction_hash),
+                );
+            }
+
+            self.users[from].unsent_transactions_in_shard[source_shard_id].push_back((
+                Transaction::new(
+                    from,
+                    from,
+                    source_shard_id,
+                    vec![Function {
+                        source: from,
+                        target: from,
+                        ftype: FunctionType::AbortCrossTransfer,
+                        calldata: "".to_string(),
+                    }],
+                    DEFAULT_GAS_PREMIUM,
+                    cross_transfer.fee_cap,
+                    DUMMY_NONCE, // update when sending
+                ),
+                lock_hash,
+            ));
+            self.cross_transfers[index].decided = true;
+        }
+    }
+
+    /// Pick the token contract of a transfer, or None for an ETH transfer.
+    fn pick_token_of_transfer(&mut self) -> Option<Address> {
+        if self.token_addrs.is_empty()
+            || self.rng.transfer.next_u32()
+                >= (self.percentage_of_token_transfers * u32::MAX as f64) as u32
+        {
+            return None;
+        }
+        let i = self.rng.transfer.next_u32() as usize % self.token_addrs.len();
+        Some(self.token_addrs[i])
+    }
+
     fn generate_transfer_transactions_per_slot(
         &mut self,
         from: Address,
         edges: &HashMap<Address, UserGraphEdge>,
     ) -> Vec<TransactionAndReceipt> {
         let mut transactions = Vec::new();
-        let mut shuffle_to = edges.iter().map(|(&to, _)| to).collect::<Vec<_>>();
-        let mut rng = thread_rng();
-        shuffle_to.shuffle(&mut rng);
+        let mut batch = Vec::new();
+        // Sort first, since the iteration order of HashMap differs between runs
+        let mut shuffle_to = edges.keys().copied().collect::<Vec<_>>();
+        shuffle_to.sort_unstable();
+        shuffle_to.shuffle(&mut self.rng.transfer);
 
         for to in shuffle_to {
             let edge = edges.get(&to).unwrap();
 
-            let p = RND.lock().unwrap().next_u32() as f64 / u32::MAX as f64;
-            let skip = p > edge.transfer_probability_in_slot as f64;
+            let p = self.rng.transfer.next_u32() as f64 / u32::MAX as f64;
+            let skip = p > edge.transfer_probability_in_slot;
             if skip {
                 continue;
             }
@@ -430,81 +1388,330 @@ impl Environment {
                 continue;
             }
 
+            let (_, account_t) = self.blockchain.get_account(to);
+            if self.async_calls
+                && shard_f != shard_t
+                && account_t.is_some_and(|account| account.is_contract())
+            {
+                if let Some(transaction) = self.generate_cross_call(from, to, shard_f, shard_t) {
+                    transactions.push(transaction);
+                }
+                continue;
+            }
+
+            let token = self.pick_token_of_transfer();
+            let calldata = match token {
+                Some(token) => Calldata::Token {
+                    token,
+                    amount: TOKEN_TRANSFER_AMOUNT,
+                }
+                .encode(),
+                None => "".to_string(),
+            };
+
             if shard_f == shard_t {
                 // Intra-shard transfer
+                let ftype = if token.is_some() {
+                    FunctionType::TokenTransfer
+                } else {
+                    FunctionType::Transfer
+                };
 
-                let fee_cap = self.determine_fee_cap(from, to);
+                let fee_cap = self.determine_fee_cap(from, to) * GAS_TRANSFER / ftype.gas();
                 if fee_cap <= self.blockchain.shards[shard_f].get_base_fee() {
                     continue;
                 }
-                let nonce = self.users[from].nonce_in_shard[shard_f];
-                let transaction = Transaction::new(
-                    from,
+                batch.push(BatchedTransfer {
                     to,
-                    shard_f,
-                    vec![Function {
+                    function: Function {
                         source: from,
                         target: to,
-                        ftype: FunctionType::Transfer,
-                        calldata: "".to_string(),
-                    }],
-                    DEFAULT_GAS_PREMIUM,
+                        ftype,
+                        calldata,
+                    },
                     fee_cap,
-                    nonce,
-                );
-                transactions.push((transaction.clone(), None));
-                self.users[from].unconfirmed_transactions_in_shard[shard_f]
-                    .push((self.blockchain.slot, (transaction, None)));
-                self.users[from].nonce_in_shard[shard_f] += 1;
+                    apply: None,
+                });
             } else {
                 // Cross-shard transfer
+                if token.is_none()
+                    && self.cross_shard_transfer_mode == CrossShardTransferMode::LockAndCommit
+                {
+                    if let Some(transaction) =
+                        self.generate_locked_transfer(from, to, shard_f, shard_t)
+                    {
+                        transactions.push(transaction);
+                    }
+                    continue;
+                }
+
+                let (create_ftype, apply_ftype, create_target) = if token.is_some() {
+                    (
+                        FunctionType::CreateCrossTokenTransfer,
+                        FunctionType::ApplyCrossTokenTransfer,
+                        to,
+                    )
+                } else {
+                    (
+                        FunctionType::CreateCrossTransfer,
+                        FunctionType::ApplyCrossTransfer,
+                        from,
+                    )
+                };
                 let fee_cap = self.determine_fee_cap(from, to) * GAS_TRANSFER
-                    / (GAS_CREATE_CROSS_TRANSFER + GAS_APPLY_CROSS_TRANSFER);
+                    / (create_ftype.gas() + apply_ftype.gas());
                 if fee_cap <= self.blockchain.shards[shard_f].get_base_fee()
                     || fee_cap <= self.blockchain.shards[shard_t].get_base_fee()
                 {
                     continue;
                 }
-                let nonce = self.users[from].nonce_in_shard[shard_f];
-                let transaction = Transaction::new(
-                    from,
+                batch.push(BatchedTransfer {
                     to,
-                    shard_f,
-                    vec![Function {
+                    function: Function {
                         source: from,
-                        target: from,
-                        ftype: FunctionType::CreateCrossTransfer,
-                        calldata: "".to_string(),
-                    }],
-                    DEFAULT_GAS_PREMIUM,
+                        target: create_target,
+                        ftype: create_ftype,
+                        calldata: calldata.clone(),
+                    },
                     fee_cap,
-                    nonce,
-                );
-                transactions.push((transaction.clone(), None));
-                self.users[from].unconfirmed_transactions_in_shard[shard_f]
-                    .push((self.blockchain.slot, (transaction.clone(), None)));
-                self.users[from].nonce_in_shard[shard_f] += 1;
+                    apply: Some((
+                        shard_t,
+                        Function {
+                            source: from,
+                            target: to,
+                            ftype: apply_ftype,
+                            calldata,
+                        },
+                    )),
+                });
+            }
+
+            if batch.len() == self.batch_size {
+                transactions.push(self.send_transfer_batch(from, std::mem::take(&mut batch)));
+            }
+        }
+        if !batch.is_empty() {
+            transactions.push(self.send_transfer_batch(from, batch));
+        }
+
+        transactions
+    }
+
+    /// Send transfers in one transaction, and queue the applies of the cross-shard ones.
+    fn send_transfer_batch(
+        &mut self,
+        from: Address,
+        batch: Vec<BatchedTransfer>,
+    ) -> TransactionAndReceipt {
+        let shard_f = self.get_shard_id_from_addr(from);
+        // The lowest fee cap of the transfers
+        let fee_cap = batch.iter().map(|transfer| transfer.fee_cap).min().unwrap();
+        let nonce = self.users[from].nonce_in_shard[shard_f];
+        let mut transaction = Transaction::new(
+            from,
+            batch[0].to,
+            shard_f,
+            batch
+                .iter()
+                .map(|transfer| transfer.function.clone())
+                .collect(),
+            DEFAULT_GAS_PREMIUM,
+            fee_cap,
+            nonce,
+        );
+        transaction.batch_semantics = self.batch_semantics;
+        self.users[from].unconfirmed_transactions_in_shard[shard_f]
+            .push((self.blockchain.slot, (transaction.clone(), None)));
+        self.users[from].nonce_in_shard[shard_f] += 1;
 
+        for (index, transfer) in batch.into_iter().enumerate() {
+            if let Some((shard_t, apply)) = transfer.apply {
+                let create_hash = transaction.function_hash(index);
+                self.record_cross_transfer(
+                    CrossShardTransferMode::Receipt,
+                    &transaction,
+                    create_hash,
+                    shard_t,
+                    None,
+                );
                 self.users[from].unsent_transactions_in_shard[shard_t].push_back((
                     Transaction::new(
                         from,
-                        to,
+                        transfer.to,
                         shard_t,
-                        vec![Function {
-                            source: from,
-                            target: to,
-                            ftype: FunctionType::ApplyCrossTransfer,
-                            calldata: "".to_string(),
-                        }],
+                        vec![apply],
                         DEFAULT_GAS_PREMIUM,
-                        fee_cap,
+                        transfer.fee_cap,
                         DUMMY_NONCE, // update when sending
                     ),
-                    transaction.hash,
+                    create_hash,
                 ));
             }
         }
 
-        transactions
+        (transaction, None)
+    }
+
+    /// Call a contract on another shard, whose result triggers a callback on the origin shard.
+    fn generate_cross_call(
+        &mut self,
+        from: Address,
+        contract: Address,
+        shard_f: usize,
+        shard_t: usize,
+    ) -> Option<TransactionAndReceipt> {
+        let fee_cap = self.determine_fee_cap(from, contract) * GAS_TRANSFER
+            / (GAS_CREATE_CROSS_CALL + GAS_APPLY_CROSS_CALL + GAS_APPLY_CALLBACK);
+        if fee_cap <= self.blockchain.shards[shard_f].get_base_fee()
+            || fee_cap <= self.blockchain.shards[shard_t].get_base_fee()
+        {
+            return None;
+        }
+        let calldata = Calldata::Call {
+            contract,
+            origin_shard_id: shard_f,
+        }
+        .encode();
+        let nonce = self.users[from].nonce_in_shard[shard_f];
+        let transaction = Transaction::new(
+            from,
+            contract,
+            shard_f,
+            vec![Function {
+                source: from,
+                target: contract,
+                ftype: FunctionType::CreateCrossCall,
+                calldata: calldata.clone(),
+            }],
+            DEFAULT_GAS_PREMIUM,
+            fee_cap,
+            nonce,
+        );
+        self.users[from].unconfirmed_transactions_in_shard[shard_f]
+            .push((self.blockchain.slot, (transaction.clone(), None)));
+        self.users[from].nonce_in_shard[shard_f] += 1;
+
+        self.cross_call_index
+            .insert(transaction.hash, self.cross_calls.len());
+        self.cross_calls.push(CrossCall::new(
+            from,
+            shard_f,
+            shard_t,
+            self.blockchain.slot,
+            transaction.hash,
+        ));
+
+        self.users[from].unsent_transactions_in_shard[shard_t].push_back((
+            Transaction::new(
+                from,
+                contract,
+                shard_t,
+                vec![Function {
+                    source: from,
+                    target: contract,
+                    ftype: FunctionType::ApplyCrossCall,
+                    calldata,
+                }],
+                DEFAULT_GAS_PREMIUM,
+                fee_cap,
+                DUMMY_NONCE, // update when sending
+            ),
+            transaction.hash,
+        ));
+
+        Some((transaction, None))
+    }
+
+    fn record_cross_transfer(
+        &mut self,
+        mode: CrossShardTransferMode,
+        first_transaction: &Transaction,
+        first_leg_hash: TransactionHash,
+        destination_shard_id: usize,
+        deadline: Option<Slot>,
+    ) {
+        let index = self.cross_transfers.len();
+        self.cross_transfer_index.insert(first_leg_hash, index);
+        self.cross_transfers.push(CrossTransfer::new(
+            first_transaction,
+            first_leg_hash,
+            mode,
+            destination_shard_id,
+            self.blockchain.slot,
+            deadline,
+        ));
+        if deadline.is_some() {
+            self.undecided_locked_transfers.push(index);
+        }
+    }
+
+    /// Lock funds for a two-phase transfer, which the destination shard confirms later.
+    fn generate_locked_transfer(
+        &mut self,
+        from: Address,
+        to: Address,
+        shard_f: usize,
+        shard_t: usize,
+    ) -> Option<TransactionAndReceipt> {
+        let fee_cap = self.determine_fee_cap(from, to) * GAS_TRANSFER
+            / (GAS_LOCK_CROSS_TRANSFER
+                + GAS_CONFIRM_CROSS_TRANSFER
+                + GAS_COMMIT_CROSS_TRANSFER
+                + GAS_FINALIZE_CROSS_TRANSFER);
+        if fee_cap <= self.blockchain.shards[shard_f].get_base_fee()
+            || fee_cap <= self.blockchain.shards[shard_t].get_base_fee()
+        {
+            return None;
+        }
+        let deadline = self.blockchain.slot + self.lock_timeout;
+        let nonce = self.users[from].nonce_in_shard[shard_f];
+        let transaction = Transaction::new(
+            from,
+            to,
+            shard_f,
+            vec![Function {
+                source: from,
+                target: to,
+                ftype: FunctionType::LockCrossTransfer,
+                calldata: Calldata::Lock {
+                    amount: LOCKED_TRANSFER_AMOUNT,
+                    deadline,
+                }
+                .encode(),
+            }],
+            DEFAULT_GAS_PREMIUM,
+            fee_cap,
+            nonce,
+        );
+        self.users[from].unconfirmed_transactions_in_shard[shard_f]
+            .push((self.blockchain.slot, (transaction.clone(), None)));
+        self.users[from].nonce_in_shard[shard_f] += 1;
+        self.record_cross_transfer(
+            CrossShardTransferMode::LockAndCommit,
+            &transaction,
+            transaction.hash,
+            shard_t,
+            Some(deadline),
+        );
+
+        self.users[from].unsent_transactions_in_shard[shard_t].push_back((
+            Transaction::new(
+                from,
+                to,
+                shard_t,
+                vec![Function {
+                    source: from,
+                    target: to,
+                    ftype: FunctionType::ConfirmCrossTransfer,
+                    calldata: "".to_string(),
+                }],
+                DEFAULT_GAS_PREMIUM,
+                fee_cap,
+                DUMMY_NONCE, // update when sending
+            ),
+            transaction.hash,
+        ));
+
+        Some((transaction, None))
     }
 }
@@ -0,0 +1,40 @@
+use crate::*;
+
+/// Accounts that many users interact with, e.g. DEXes, bridges or a popular user.
+/// Each interacting user picks one of them with the probability proportional to 1 / (index + 1)
+/// (Zipf's law).
+#[derive(Clone, Debug)]
+pub struct HubGroup {
+    pub addrs: Vec<Address>,
+    /// The share of users that interact with one of the hubs.
+    pub percentage_of_users: f64,
+    /// How many times more often users interact with a hub than with another user on average.
+    pub interaction_weight: u32,
+}
+
+impl HubGroup {
+    pub fn new(addrs: Vec<Address>, percentage_of_users: f64, interaction_weight: u32) -> Self {
+        Self {
+            addrs,
+            percentage_of_users,
+            interaction_weight,
+        }
+    }
+
+    /// Pick a hub by Zipf's law.
+    pub fn pick(&self, rng: &mut XorShiftRng) -> Address {
+        let weights = (0..self.addrs.len())
+            .map(|i| 1. / (i + 1) as f64)
+            .collect::<Vec<_>>();
+        let total_weight: f64 = weights.iter().sum();
+        let threshold = rng.next_u32() as f64 / u32::MAX as f64 * total_weight;
+        let mut cumulative_weight = 0.;
+        for (i, weight) in weights.iter().enumerate() {
+            cumulative_weight += weight;
+            if cumulative_weight >= threshold {
+                return self.addrs[i];
+            }
+        }
+        self.addrs[self.addrs.len() - 1]
+    }
+}
@@ -0,0 +1,71 @@
+use crate::*;
+use std::io::{BufWriter, Write};
+
+/// Writes the tidy records as JSON Lines, flushed every slot.
+pub struct JsonLinesWriter {
+    wtr: BufWriter<File>,
+}
+
+impl JsonLinesWriter {
+    pub fn create(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            wtr: BufWriter::new(File::create(file_path)?),
+        })
+    }
+}
+
+impl TidyWriter for JsonLinesWriter {
+    fn write_records(&mut self, records: &[TidyRecord]) -> Result<(), Box<dyn Error>> {
+        for record in records {
+            serde_json::to_writer(&mut self.wtr, record)?;
+            self.wtr.write_all(b"\n")?;
+        }
+        self.wtr.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.wtr.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_json_object_per_record_and_line() {
+        let file_path = std::env::temp_dir().join("json_lines_writer_test.jsonl");
+        let mut wtr = Box::new(JsonLinesWriter::create(&file_path).unwrap());
+        wtr.write_records(&[TidyRecord {
+            slot: 3,
+            shard: Some(1),
+            metric: "base_fee".to_string(),
+            value: 7.5,
+        }])
+        .unwrap();
+        wtr.write_records(&[TidyRecord {
+            slot: 4,
+            shard: None,
+            metric: "functions.transfer".to_string(),
+            value: 2.,
+        }])
+        .unwrap();
+        wtr.finish().unwrap();
+
+        let lines = std::fs::read_to_string(&file_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({"slot": 3, "shard": 1, "metric": "base_fee", "value": 7.5}),
+                serde_json::json!({"slot": 4, "shard": null, "metric": "functions.transfer", "value": 2.0}),
+            ]
+        );
+        std::fs::remove_file(file_path).unwrap();
+    }
+}
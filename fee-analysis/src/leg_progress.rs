@@ -0,0 +1,15 @@
+use crate::*;
+
+/// What happened in a slot to the cross-shard transfers or to the account moves,
+/// which take more than one transaction.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LegProgress {
+    /// How many completed in the slot.
+    pub completed_num: usize,
+    /// The sum of the latencies from creation to completion of those completed in the slot.
+    pub latency: Slot,
+    /// The base fee and tip paid by all the legs of those completed in the slot.
+    pub fee: Wei,
+    /// How many have their first leg included but have not ended by the end of the slot.
+    pub in_flight_num: usize,
+}
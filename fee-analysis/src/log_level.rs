@@ -0,0 +1,24 @@
+/// How much the simulator prints to the console.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum LogLevel {
+    /// Only errors.
+    Error,
+    /// The setup and a summary line every 1 / PROGRESS_LINE_NUM of the run.
+    #[default]
+    Info,
+    /// The statistics of every slot.
+    Debug,
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(LogLevel::Error),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("unknown log level: {}", s)),
+        }
+    }
+}
@@ -1,32 +1,96 @@
-use clap::{App, AppSettings, Arg};
-use lazy_static::lazy_static;
+use clap::{App, AppSettings, Arg, SubCommand};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand_core::{RngCore, SeedableRng};
-use serde::Deserialize;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
 use shargrila_chain::*;
 use std::collections::VecDeque;
 use std::error::Error;
 use std::ffi::OsString;
 use std::fs::File;
-use std::path::Path;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 
-lazy_static! {
-    static ref RND: Mutex<rand_xorshift::XorShiftRng> =
-        Mutex::new(rand_xorshift::XorShiftRng::seed_from_u64(RAND_SEED));
-}
-
+mod batched_transfer;
+mod cross_call;
+mod cross_shard_transfer_mode;
+mod cross_transfer;
 mod environment;
+mod hub_group;
+mod json_lines_writer;
+mod leg_progress;
+mod log_level;
+mod manifest;
+mod migration;
+mod migration_mechanism;
+mod output_format;
 mod parameters;
+#[cfg(feature = "parquet")]
+mod parquet_writer;
+mod random_streams;
+mod replica_statistic;
+mod replicas;
+mod rollup;
+mod rollup_batch;
+mod run_pool;
+mod run_summary;
+mod scenario;
 mod simulator;
+mod slot_columns;
+mod slot_sink;
+#[cfg(feature = "sqlite")]
+mod sqlite_writer;
+mod sweep;
+mod sweep_parameter;
+mod tidy_record;
+mod tidy_writer;
+mod token_issuer;
+mod transaction_kind;
 mod transaction_record;
+mod transaction_trace;
+mod transfer_outcome;
 mod user;
 mod user_graph;
+use batched_transfer::*;
+use cross_call::*;
+use cross_shard_transfer_mode::*;
+use cross_transfer::*;
 use environment::*;
+use hub_group::*;
+use json_lines_writer::*;
+use leg_progress::*;
+use log_level::*;
+use manifest::*;
+use migration::*;
+use migration_mechanism::*;
+use output_format::*;
 use parameters::*;
+#[cfg(feature = "parquet")]
+use parquet_writer::*;
+use random_streams::*;
+use replica_statistic::*;
+use replicas::*;
+use rollup::*;
+use rollup_batch::*;
+use run_pool::*;
+use run_summary::*;
+use scenario::*;
 use simulator::*;
+use slot_columns::*;
+use slot_sink::*;
+#[cfg(feature = "sqlite")]
+use sqlite_writer::*;
+use sweep::*;
+use sweep_parameter::*;
+use tidy_record::*;
+use tidy_writer::*;
+use token_issuer::*;
+use transaction_kind::*;
 use transaction_record::*;
+use transaction_trace::*;
+use transfer_outcome::*;
 use user::*;
 use user_graph::*;
 
@@ -36,6 +100,12 @@ fn main() {
             .version("v0.1.0")
             .about("Sharded blockchain simulator")
             .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("SCENARIO")
+                    .long("scenario")
+                    .help("A TOML or JSON scenario file, whose values the other options override")
+                    .takes_value(true),
+            )
             .arg(
                 Arg::with_name("BIG_QUERY_CSV")
                     .long("csv")
@@ -54,6 +124,24 @@ fn main() {
                     .help("The maximum number of users")
                     .takes_value(true),
             )
+            .arg(
+                Arg::with_name("SHARD_NUM")
+                    .long("shard_num")
+                    .help("The number of shards")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("BLOCK_GAS_TARGET")
+                    .long("block_gas_target")
+                    .help("The genesis gas target of each shard")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("AVERAGE_SHARD_SWITCHING_INTERVAL")
+                    .long("average_shard_switching_interval")
+                    .help("How many slots a user stays on a shard on average before considering a move")
+                    .takes_value(true),
+            )
             .arg(
                 Arg::with_name("PERCENTAGE_OF_MINIMUM")
                     .long("percentage_of_minimum")
@@ -69,6 +157,17 @@ fn main() {
                     .long("percentage_of_decreasing_minimum")
                     .takes_value(true),
             )
+            .arg(
+                Arg::with_name("PERCENTAGE_OF_TOKEN_TRANSFERS")
+                    .long("percentage_of_token_transfers")
+                    .help("The percentage of transfers that are token transfers instead of ETH transfers")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("ASYNC_CALLS")
+                    .long("async_calls")
+                    .help("Whether or not users call contracts on other shards asynchronously with callbacks"),
+            )
             .arg(
                 Arg::with_name("POPULAR_USER_EXISTS")
                     .long("popular_user_exists")
@@ -79,23 +178,232 @@ fn main() {
                     .long("popular_user_is_switcher")
                     .help("Whether or not the popular user is a switcher"),
             )
+            .arg(
+                Arg::with_name("ADVERSARY")
+                    .long("adversary")
+                    .help("The behavior of adversarial proposers (censor_cross_shard_apply, censor_movement, base_fee_stuffing, empty_block or self_preferencing)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("ADVERSARY_SHARE")
+                    .long("adversary_share")
+                    .help("The fraction of shards whose proposer is adversarial")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("GAS_TARGET_RULE")
+                    .long("gas_target_rule")
+                    .help("The rule to adjust the gas target of each shard (fixed, deterministic or proposer_voting)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("PREFERRED_GAS_TARGET")
+                    .long("preferred_gas_target")
+                    .help("The gas target proposers vote toward under proposer_voting, for all shards or comma-separated per shard")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("FEE_MARKET")
+                    .long("fee_market")
+                    .help("The transaction fee mechanism (one_dimensional or multi_dimensional)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("BASE_FEE_COUPLING")
+                    .long("base_fee_coupling")
+                    .help("The rule to couple base fees across shards (independent, global or neighbor_smoothing)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("MISSED_PROPOSAL_PROBABILITY")
+                    .long("missed_proposal_probability")
+                    .help("The probability that a proposer misses its proposal, for all shards or comma-separated per shard")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("OUTAGE")
+                    .long("outage")
+                    .help("A shard outage during which no blocks are produced (shard_id:from_slot:to_slot)")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("HUB_CONTRACT_NUM")
+                    .long("hub_contract_num")
+                    .help("The number of immovable hub contracts (e.g. DEXes and bridges) users interact with")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("CROSS_SHARD_TRANSFER_MODE")
+                    .long("cross_shard_transfer_mode")
+                    .help("How users transfer ETH to other shards (receipt or lock_and_commit)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("LOCK_TIMEOUT")
+                    .long("lock_timeout")
+                    .help("The number of slots after which a locked cross-shard transfer can be aborted")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("MIGRATION")
+                    .long("migration")
+                    .help("How users move their accounts to other shards (receipt or yank)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("STATE_RENT")
+                    .long("state_rent")
+                    .help("The storage pricing of shards (free, evict or hibernate)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("DATA_SHARD_NUM")
+                    .long("data_shard_num")
+                    .help("The number of shards that only carry the data of rollups")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("PERCENTAGE_OF_ROLLUP_USERS")
+                    .long("percentage_of_rollup_users")
+                    .help("The percentage of users whose transfers are aggregated by rollups on data shards")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("PERCENTAGE_OF_LEGACY_USERS")
+                    .long("percentage_of_legacy_users")
+                    .help("The percentage of users who send legacy transactions with a single gas price")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("SEED")
+                    .long("seed")
+                    .help("The seed from which all the random numbers of the run are drawn")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("SPONSORED_APPLIES")
+                    .long("sponsored_applies")
+                    .help("Whether or not relayers on destination shards pay for applying cross-shard transfers"),
+            )
+            .arg(
+                Arg::with_name("BATCH_SIZE")
+                    .long("batch_size")
+                    .help("The maximum number of transfers a user bundles into one transaction")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("BATCH_SEMANTICS")
+                    .long("batch_semantics")
+                    .help("What happens to a batched transaction when one of its transfers fails (all_or_nothing or best_effort)")
+                    .takes_value(true),
+            )
             .arg(
                 Arg::with_name("OUTPUT_DIR_PATH")
                     .long("output_dir_path")
                     .help("The path of the output directory")
                     .takes_value(true),
             )
+            .arg(
+                Arg::with_name("OUTPUT_FORMAT")
+                    .long("output_format")
+                    .help("Comma-separated formats of the per-slot outputs (csv, json_lines, sqlite or parquet)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("TRACE")
+                    .long("trace")
+                    .help("Whether or not every transaction is written into transactions.csv"),
+            )
+            .arg(
+                Arg::with_name("REPLICAS")
+                    .long("replicas")
+                    .help("The number of runs with consecutive seeds, whose outputs are aggregated")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("LOG_LEVEL")
+                    .long("log_level")
+                    .help("How much is printed to the console (error, info or debug)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("JOBS")
+                    .long("jobs")
+                    .help("The number of runs at the same time (defaults to the number of cores)")
+                    .takes_value(true),
+            )
+            .subcommand(
+                SubCommand::with_name("sweep")
+                    .about("Runs the scenario over a grid of parameter values in parallel")
+                    .arg(
+                        Arg::with_name("PARAM")
+                            .long("param")
+                            .help("A scenario parameter and its values (name=v1,v2,... or name=start:end:step)")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .required(true),
+                    ),
+            )
             .get_matches()
     };
     println!("Hello, Shargri-La!");
 
-    let end_slot = if let Some(end_slot) = arg_matches.value_of("END_SLOT") {
-        end_slot
-            .parse()
-            .expect("END_SLOT must be a positive integer")
+    let mut scenario = if let Some(scenario_path) = arg_matches.value_of("SCENARIO") {
+        Scenario::from_file(scenario_path).expect("SCENARIO must be a TOML or JSON scenario file")
+    } else {
+        Scenario::default()
+    };
+    scenario.apply_arg_matches(&arg_matches);
+    if let Err(e) = scenario.validate() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    let job_num = if let Some(job_num) = arg_matches.value_of("JOBS") {
+        job_num.parse().expect("JOBS must be a positive integer")
+    } else {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    };
+    if job_num == 0 {
+        eprintln!("Error: JOBS must be a positive integer");
+        std::process::exit(1);
+    }
+
+    if let Some(sweep_matches) = arg_matches.subcommand_matches("sweep") {
+        let result = sweep_matches
+            .values_of("PARAM")
+            .unwrap()
+            .map(|parameter| parameter.parse())
+            .collect::<Result<Vec<SweepParameter>, String>>()
+            .and_then(|parameters| {
+                Sweep::new(scenario, parameters, job_num)
+                    .run()
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if scenario.replicas > 1 {
+        if Replicas::new(scenario).run(job_num).is_none() {
+            eprintln!("Error: some replicas failed");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let log_level = if let Some(log_level) = arg_matches.value_of("LOG_LEVEL") {
+        log_level.parse().expect("LOG_LEVEL must be a log level")
     } else {
-        DEFAULT_END_SLOT
+        LogLevel::default()
     };
-    let mut sim = Simulator::new(end_slot);
-    sim.run(&arg_matches);
+    let mut sim = Simulator::new(scenario);
+    sim.set_log_level(log_level);
+    sim.run();
 }
@@ -0,0 +1,84 @@
+use crate::*;
+
+/// What a run was and what it wrote, written to `manifest.json` in the output directory.
+/// Written when the run starts and rewritten when it finishes, so that an unfinished run
+/// is told by the missing end time.
+#[derive(Serialize)]
+pub struct Manifest {
+    /// The version of the simulator.
+    pub version: String,
+    pub seed: u64,
+    /// RFC 3339 in UTC.
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// The resolved scenario.
+    pub parameters: Scenario,
+    /// Filenames of the outputs written so far.
+    pub outputs: Vec<String>,
+}
+
+impl Manifest {
+    pub fn new(scenario: &Scenario) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed: scenario.seed,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            parameters: scenario.clone(),
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    }
+
+    pub fn write(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_MANIFEST_FILENAME);
+        std::fs::write(file_path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_the_run_and_is_finished_by_the_end_time() {
+        let scenario = Scenario {
+            seed: 11,
+            end_slot: 5,
+            ..Scenario::default()
+        };
+        let mut manifest = Manifest::new(&scenario);
+        manifest.outputs.push(OUTPUT_USERS_CSV_FILENAME.to_string());
+
+        let output_dir_path = std::env::temp_dir().join("manifest_test");
+        std::fs::create_dir_all(&output_dir_path).unwrap();
+        let read = || -> serde_json::Value {
+            let contents =
+                std::fs::read_to_string(output_dir_path.join(OUTPUT_MANIFEST_FILENAME)).unwrap();
+            serde_json::from_str(&contents).unwrap()
+        };
+
+        manifest.write(&output_dir_path).unwrap();
+        let json = read();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["seed"], 11);
+        assert_eq!(json["parameters"]["end_slot"], 5);
+        assert_eq!(
+            json["outputs"],
+            serde_json::json!([OUTPUT_USERS_CSV_FILENAME])
+        );
+        assert!(chrono::DateTime::parse_from_rfc3339(json["started_at"].as_str().unwrap()).is_ok());
+        assert!(json["finished_at"].is_null());
+
+        manifest.finish();
+        manifest.write(&output_dir_path).unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(read()["finished_at"].as_str().unwrap()).is_ok()
+        );
+        std::fs::remove_dir_all(output_dir_path).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use crate::*;
+
+/// A move of a user's account to another shard.
+pub struct Migration {
+    pub from: Address,
+    pub mechanism: MigrationMechanism,
+    pub source_shard_id: usize,
+    pub target_shard_id: usize,
+    /// The slot in which the user started the move.
+    pub created_slot: Slot,
+    /// Hashes of the transactions of the move sent so far, starting from the source shard.
+    pub leg_hashes: Vec<TransactionHash>,
+    /// The base fee burned by the legs included so far.
+    pub burned_fee: Wei,
+    /// The tip paid by the legs included so far.
+    pub tip: Wei,
+}
+
+impl Migration {
+    pub fn new(
+        first_transaction: &Transaction,
+        mechanism: MigrationMechanism,
+        target_shard_id: usize,
+        created_slot: Slot,
+    ) -> Self {
+        Self {
+            from: first_transaction.from,
+            mechanism,
+            source_shard_id: first_transaction.shard_id,
+            target_shard_id,
+            created_slot,
+            leg_hashes: vec![first_transaction.hash],
+            burned_fee: 0,
+            tip: 0,
+        }
+    }
+
+    /// The outcome of the move given the receipts of its legs included so far, in order,
+    /// and the slot in which it ended, if it did.
+    pub fn outcome(&self, legs: &[&Receipt]) -> (TransferOutcome, Option<Slot>) {
+        let outcome = if legs.iter().any(|receipt| !receipt.status) {
+            TransferOutcome::Failed
+        } else if legs.len() == 2 {
+            TransferOutcome::Completed
+        } else {
+            TransferOutcome::Pending
+        };
+        let ended_slot = if outcome == TransferOutcome::Pending {
+            None
+        } else {
+            legs.iter().map(|receipt| receipt.slot_number).max()
+        };
+        (outcome, ended_slot)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::*;
+
+/// How users move their accounts to other shards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationMechanism {
+    /// The whole account is sent in a receipt and applied on the target shard by its owner.
+    #[default]
+    Receipt,
+    /// The account is locked on the source shard and pulled into the target shard.
+    Yank,
+}
+
+impl MigrationMechanism {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MigrationMechanism::Receipt => "receipt",
+            MigrationMechanism::Yank => "yank",
+        }
+    }
+}
+
+impl std::str::FromStr for MigrationMechanism {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "receipt" => Ok(MigrationMechanism::Receipt),
+            "yank" => Ok(MigrationMechanism::Yank),
+            _ => Err(format!("unknown migration mechanism: {}", s)),
+        }
+    }
+}
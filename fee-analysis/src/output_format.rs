@@ -0,0 +1,79 @@
+use crate::*;
+
+/// A format of the outputs with one record per slot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// One wide CSV file per output, with one column per shard or label.
+    #[default]
+    Csv,
+    /// One JSON object per line in the tidy schema.
+    JsonLines,
+    /// A table in the tidy schema (needs `--features sqlite`).
+    Sqlite,
+    /// A Parquet file in the tidy schema (needs `--features parquet`).
+    Parquet,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::JsonLines => "json_lines",
+            OutputFormat::Sqlite => "sqlite",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+
+    /// The file of the tidy outputs of this format, or None for the CSV files.
+    pub fn filename(&self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Csv => None,
+            OutputFormat::JsonLines => Some(OUTPUT_JSON_LINES_FILENAME),
+            OutputFormat::Sqlite => Some(OUTPUT_SQLITE_FILENAME),
+            OutputFormat::Parquet => Some(OUTPUT_PARQUET_FILENAME),
+        }
+    }
+
+    /// Create the writer of the tidy outputs of this format, or None for the CSV files.
+    pub fn create_tidy_writer(
+        &self,
+        output_dir_path: &Path,
+    ) -> Result<Option<Box<dyn TidyWriter>>, Box<dyn Error>> {
+        match self {
+            OutputFormat::Csv => Ok(None),
+            OutputFormat::JsonLines => Ok(Some(Box::new(JsonLinesWriter::create(
+                &output_dir_path.join(OUTPUT_JSON_LINES_FILENAME),
+            )?))),
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => Ok(Some(Box::new(SqliteWriter::create(
+                &output_dir_path.join(OUTPUT_SQLITE_FILENAME),
+            )?))),
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => Ok(Some(Box::new(ParquetWriter::create(
+                &output_dir_path.join(OUTPUT_PARQUET_FILENAME),
+            )?))),
+            #[allow(unreachable_patterns)]
+            _ => Err(format!(
+                "the {} output format is not built in: rebuild with --features {}",
+                self.name(),
+                self.name()
+            )
+            .into()),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(OutputFormat::Csv),
+            "json_lines" => Ok(OutputFormat::JsonLines),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            "parquet" => Ok(OutputFormat::Parquet),
+            _ => Err(format!("unknown output format: {}", s)),
+        }
+    }
+}
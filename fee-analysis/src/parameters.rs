@@ -2,30 +2,72 @@ use shargrila_chain::*;
 
 pub const DEFAULT_END_SLOT: Slot = 100;
 pub const DEFAULT_USER_NUM: usize = 10000;
+pub const DEFAULT_SHARD_NUM: usize = SHARD_NUM;
+pub const DEFAULT_BLOCK_GAS_TARGET: Gas = BLOCK_GAS_TARGET;
 pub const DEFAULT_PERCENTAGE_OF_MINIMUM: f64 = 0.;
 pub const DEFAULT_PERCENTAGE_OF_WEIGHTED_RANDOM: f64 = 0.;
 pub const DEFAULT_PERCENTAGE_OF_DECREASING_MINIMUM: f64 = 0.;
+pub const DEFAULT_ADVERSARY_SHARE: f64 = 0.;
+pub const DEFAULT_MISSED_PROPOSAL_PROBABILITY: f64 = 0.;
+pub const DEFAULT_PERCENTAGE_OF_TOKEN_TRANSFERS: f64 = 0.;
+pub const DEFAULT_HUB_CONTRACT_NUM: usize = 0;
+pub const DEFAULT_LOCK_TIMEOUT: Slot = 16;
+pub const DEFAULT_PERCENTAGE_OF_ROLLUP_USERS: f64 = 0.;
+pub const DEFAULT_PERCENTAGE_OF_LEGACY_USERS: f64 = 0.;
+pub const DEFAULT_BATCH_SIZE: usize = 1;
+pub const DEFAULT_DATA_SHARD_NUM: usize = 0;
+pub const DEFAULT_REPLICAS: usize = 1;
 pub const INITIAL_SETUP_SLOTS: Slot = 10;
 
+// Console
+/// The number of progress lines printed over a run at the info log level.
+pub const PROGRESS_LINE_NUM: Slot = 20;
+
 // User behavior
-pub const AVERAGE_SHARD_SWITCHING_INTERVAL: Slot = 100;
+pub const DEFAULT_AVERAGE_SHARD_SWITCHING_INTERVAL: Slot = 100;
 pub const DEFAULT_GAS_PREMIUM: GasPrice = 1_000_000_000;
+/// Wallets bid this multiple of the receipt base fees in the multidimensional fee market.
+pub const RECEIPT_FEE_CAP_BASE_FEE_MULTIPLIER: GasPrice = 2;
+
+// Adversarial proposers
+pub const PERCENTAGE_OF_USERS_AFFILIATED_WITH_PROPOSER: f64 = 0.1;
+
+// Token contracts
+pub const TOKEN_CONTRACT_NUM: usize = 4;
+pub const INITIAL_TOKEN_BALANCE: Wei = 1_000_000;
+pub const TOKEN_TRANSFER_AMOUNT: Wei = 1;
+/// The amount each issuer mints to itself every epoch.
+pub const TOKEN_MINT_AMOUNT: Wei = INITIAL_TOKEN_BALANCE;
+pub const ISSUER_FEE_CAP: GasPrice = MAX_FEE_CAP;
+
+// State rent
+pub const MAX_INITIAL_BALANCE_WITH_STATE_RENT: Wei = 1_000_000_000_000_000;
+
+// Two-phase cross-shard transfers
+pub const LOCKED_TRANSFER_AMOUNT: Wei = 1_000_000_000_000_000;
+
+// Rollups
+/// The compressed size of a rollup transaction in bytes.
+pub const ROLLUP_TRANSACTION_SIZE: Gas = 12;
+pub const MAX_BATCH_TRANSACTION_NUM: usize =
+    ((BLOCK_DATA_TARGET - BATCH_HEADER_SIZE) / ROLLUP_TRANSACTION_SIZE) as usize;
+pub const SEQUENCER_FEE_CAP: GasPrice = INITIAL_DATA_BASE_FEE * 200;
 
 // Transaction generation
 pub const TRANSACTION_OCCUPANCY: f64 = 2.0;
 pub const AVERAGE_GAS_PER_TRANSACTION: Gas =
     (GAS_CREATE_CROSS_TRANSFER + GAS_APPLY_CROSS_TRANSFER) / 2;
-pub const GLOBAL_GAS_TARGET: Gas = BLOCK_GAS_TARGET * SHARD_NUM as Gas;
-pub const GLOBAL_TRANSACTION_GAS_PER_SLOT: Gas =
-    (TRANSACTION_OCCUPANCY * GLOBAL_GAS_TARGET as f64) as Gas;
-pub const GLOBAL_TRANSACTION_NUM: usize =
-    (GLOBAL_TRANSACTION_GAS_PER_SLOT / AVERAGE_GAS_PER_TRANSACTION) as usize;
 
 // Constants in UserGraph::new_random()
 pub const MAX_FEE_CAP: GasPrice = INITIAL_BASE_FEE * 200;
 pub const MAX_TARGET_USER_NUM: usize = 15;
-pub const POPULAR_USER_ADDRESS: Address = 0;
+
+// Hub groups
 pub const PERCENTAGE_OF_USERS_TRANSFERRING_TO_POPULAR_USER: f64 = 0.1;
+pub const PERCENTAGE_OF_USERS_INTERACTING_WITH_HUBS: f64 = 0.5;
+/// How many times more often users interact with a hub contract than with another user on average.
+pub const HUB_INTERACTION_WEIGHT: u32 = 5;
+pub const HUB_CONTRACT_STORAGE_SIZE: usize = 16;
 
 // Path
 pub const DEFAULT_OUTPUT_DIR_PATH: &str = "data";
@@ -34,7 +76,35 @@ pub const OUTPUT_ACTIVE_USER_NUM_CSV_FILENAME: &str = "active_user_num.csv";
 pub const OUTPUT_USERS_CSV_FILENAME: &str = "users.csv";
 pub const OUTPUT_FUNCTION_NUM_CSV_FILENAME: &str = "function_num.csv";
 pub const OUTPUT_MEMPOOL_CSV_FILENAME: &str = "mempool.csv";
+pub const OUTPUT_ADVERSARY_CSV_FILENAME: &str = "adversary.csv";
+pub const OUTPUT_GAS_USED_CSV_FILENAME: &str = "gas_used.csv";
+pub const OUTPUT_RECEIPT_OUT_BASE_FEE_CSV_FILENAME: &str = "receipt_out_base_fee.csv";
+pub const OUTPUT_RECEIPT_IN_BASE_FEE_CSV_FILENAME: &str = "receipt_in_base_fee.csv";
+pub const OUTPUT_GAS_TARGET_CSV_FILENAME: &str = "gas_target.csv";
+pub const OUTPUT_MISSED_SLOT_CSV_FILENAME: &str = "missed_slot.csv";
+pub const OUTPUT_PENDING_USER_NUM_CSV_FILENAME: &str = "pending_user_num.csv";
+pub const OUTPUT_IN_FLIGHT_RECEIPT_NUM_CSV_FILENAME: &str = "in_flight_receipt_num.csv";
+pub const OUTPUT_CROSS_CALL_CSV_FILENAME: &str = "cross_call.csv";
+pub const OUTPUT_CROSS_TRANSFER_CSV_FILENAME: &str = "cross_transfer.csv";
+pub const OUTPUT_MIGRATION_CSV_FILENAME: &str = "migration.csv";
+pub const OUTPUT_CROSS_TRANSFER_PROGRESS_CSV_FILENAME: &str = "cross_transfer_progress.csv";
+pub const OUTPUT_MIGRATION_PROGRESS_CSV_FILENAME: &str = "migration_progress.csv";
+pub const OUTPUT_LOCKED_TRANSACTION_NUM_CSV_FILENAME: &str = "locked_transaction_num.csv";
+pub const OUTPUT_STATE_SIZE_CSV_FILENAME: &str = "state_size.csv";
+pub const OUTPUT_EVICTED_ACCOUNT_NUM_CSV_FILENAME: &str = "evicted_account_num.csv";
+pub const OUTPUT_ROLLUP_BATCH_CSV_FILENAME: &str = "rollup_batch.csv";
+pub const OUTPUT_ROLLUP_PENDING_TRANSACTION_NUM_CSV_FILENAME: &str =
+    "rollup_pending_transaction_num.csv";
+pub const OUTPUT_TRANSACTION_TYPE_NUM_CSV_FILENAME: &str = "transaction_type_num.csv";
+pub const OUTPUT_RELAYER_CSV_FILENAME: &str = "relayer.csv";
+pub const OUTPUT_TRANSACTIONS_CSV_FILENAME: &str = "transactions.csv";
+pub const OUTPUT_SCENARIO_FILENAME: &str = "scenario.toml";
+pub const OUTPUT_MANIFEST_FILENAME: &str = "manifest.json";
+pub const OUTPUT_JSON_LINES_FILENAME: &str = "outputs.jsonl";
+pub const OUTPUT_SQLITE_FILENAME: &str = "outputs.sqlite";
+pub const OUTPUT_PARQUET_FILENAME: &str = "outputs.parquet";
+pub const OUTPUT_SWEEP_INDEX_CSV_FILENAME: &str = "index.csv";
 
 // No need to change
-pub const RAND_SEED: u64 = 1337;
+pub const DEFAULT_SEED: u64 = 1337;
 pub const DUMMY_NONCE: Nonce = 1337;
@@ -0,0 +1,110 @@
+use crate::*;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use std::sync::Arc;
+
+/// Writes the tidy records into a Parquet file.
+/// Unlike the other formats, the file is only readable after the footer is written at the end of the run.
+pub struct ParquetWriter {
+    wtr: ArrowWriter<File>,
+    schema: Arc<Schema>,
+}
+
+impl ParquetWriter {
+    pub fn create(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("shard", DataType::UInt64, true),
+            Field::new("metric", DataType::Utf8, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+        let wtr = ArrowWriter::try_new(File::create(file_path)?, schema.clone(), None)?;
+        Ok(Self { wtr, schema })
+    }
+}
+
+impl TidyWriter for ParquetWriter {
+    fn write_records(&mut self, records: &[TidyRecord]) -> Result<(), Box<dyn Error>> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                records.iter().map(|record| record.slot),
+            )),
+            Arc::new(
+                records
+                    .iter()
+                    .map(|record| record.shard.map(|shard| shard as u64))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(StringArray::from_iter_values(
+                records.iter().map(|record| record.metric.as_str()),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                records.iter().map(|record| record.value),
+            )),
+        ];
+        // Row groups are buffered by the writer
+        self.wtr
+            .write(&RecordBatch::try_new(self.schema.clone(), columns)?)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.wtr.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn records_round_trip_through_the_file() {
+        let file_path = std::env::temp_dir().join("parquet_writer_test.parquet");
+        let mut wtr = Box::new(ParquetWriter::create(&file_path).unwrap());
+        wtr.write_records(&[
+            TidyRecord {
+                slot: 3,
+                shard: Some(1),
+                metric: "base_fee".to_string(),
+                value: 7.5,
+            },
+            TidyRecord {
+                slot: 4,
+                shard: None,
+                metric: "functions.transfer".to_string(),
+                value: 2.,
+            },
+        ])
+        .unwrap();
+        wtr.finish().unwrap();
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&file_path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let slot = column("slot");
+        let slot = slot.as_any().downcast_ref::<UInt64Array>().unwrap();
+        let shard = column("shard");
+        let shard = shard.as_any().downcast_ref::<UInt64Array>().unwrap();
+        let metric = column("metric");
+        let metric = metric.as_any().downcast_ref::<StringArray>().unwrap();
+        let value = column("value");
+        let value = value.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(slot.values().to_vec(), vec![3, 4]);
+        assert_eq!(shard.value(0), 1);
+        assert!(shard.is_null(1));
+        assert_eq!(metric.value(0), "base_fee");
+        assert_eq!(metric.value(1), "functions.transfer");
+        assert_eq!(value.values().to_vec(), vec![7.5, 2.]);
+        std::fs::remove_file(file_path).unwrap();
+    }
+}
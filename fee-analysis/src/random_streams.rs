@@ -0,0 +1,75 @@
+use crate::*;
+
+/// Seeded random number streams, one per component of the simulation,
+/// so that drawing more numbers in one component does not shift the draws of the others.
+#[derive(Clone, Debug)]
+pub struct RandomStreams {
+    /// Generating the user graph and assigning roles to users.
+    pub graph: XorShiftRng,
+    /// Whether, to whom and what users transfer.
+    pub transfer: XorShiftRng,
+    /// Whether users move their accounts.
+    pub movement: XorShiftRng,
+    /// The randomized strategies of users.
+    pub strategy: XorShiftRng,
+    /// Which proposers miss their proposals.
+    pub proposer: XorShiftRng,
+}
+
+impl RandomStreams {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            graph: Self::stream(seed, 0),
+            transfer: Self::stream(seed, 1),
+            movement: Self::stream(seed, 2),
+            strategy: Self::stream(seed, 3),
+            proposer: Self::stream(seed, 4),
+        }
+    }
+
+    /// Derive the seed of each stream from the seed of the run (SplitMix64).
+    fn stream(seed: u64, index: u64) -> XorShiftRng {
+        let mut z = seed.wrapping_add((index + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        XorShiftRng::seed_from_u64(z ^ (z >> 31))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draws(rng: &mut XorShiftRng) -> Vec<u32> {
+        (0..8).map(|_| rng.next_u32()).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_draws() {
+        let mut a = RandomStreams::new(7);
+        let mut b = RandomStreams::new(7);
+        assert_eq!(draws(&mut a.graph), draws(&mut b.graph));
+        assert_eq!(draws(&mut a.transfer), draws(&mut b.transfer));
+        assert_eq!(draws(&mut a.movement), draws(&mut b.movement));
+        assert_eq!(draws(&mut a.strategy), draws(&mut b.strategy));
+        assert_eq!(draws(&mut a.proposer), draws(&mut b.proposer));
+    }
+
+    #[test]
+    fn streams_differ_from_each_other_and_across_seeds() {
+        let mut a = RandomStreams::new(7);
+        let mut b = RandomStreams::new(8);
+        let transfer = draws(&mut a.transfer);
+        assert_ne!(draws(&mut a.graph), transfer);
+        assert_ne!(draws(&mut a.movement), transfer);
+        assert_ne!(draws(&mut b.transfer), transfer);
+    }
+
+    #[test]
+    fn drawing_from_one_stream_does_not_shift_the_others() {
+        let mut a = RandomStreams::new(7);
+        let mut b = RandomStreams::new(7);
+        draws(&mut a.transfer);
+        assert_eq!(draws(&mut a.movement), draws(&mut b.movement));
+    }
+}
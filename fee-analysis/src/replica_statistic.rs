@@ -0,0 +1,89 @@
+/// A statistic over the replicas of a scenario, computed for each cell of an output.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplicaStatistic {
+    Mean,
+    /// The sample standard deviation.
+    StandardDeviation,
+    /// The p-th percentile, interpolated linearly between the closest ranks.
+    Percentile(f64),
+}
+
+impl ReplicaStatistic {
+    pub const ALL: [ReplicaStatistic; 5] = [
+        ReplicaStatistic::Mean,
+        ReplicaStatistic::StandardDeviation,
+        ReplicaStatistic::Percentile(5.),
+        ReplicaStatistic::Percentile(50.),
+        ReplicaStatistic::Percentile(95.),
+    ];
+
+    /// The name of the directory to which the statistic is written.
+    pub fn name(&self) -> String {
+        match self {
+            ReplicaStatistic::Mean => "mean".to_string(),
+            ReplicaStatistic::StandardDeviation => "std".to_string(),
+            ReplicaStatistic::Percentile(p) => format!("p{:02}", p),
+        }
+    }
+
+    pub fn compute(&self, values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        match self {
+            ReplicaStatistic::Mean => mean,
+            ReplicaStatistic::StandardDeviation => {
+                let variance = values
+                    .iter()
+                    .map(|value| (value - mean).powi(2))
+                    .sum::<f64>()
+                    / std::cmp::max(values.len() - 1, 1) as f64;
+                variance.sqrt()
+            }
+            ReplicaStatistic::Percentile(p) => {
+                let mut sorted_values = values.to_vec();
+                sorted_values.sort_by(|a, b| a.total_cmp(b));
+                let rank = p / 100. * (sorted_values.len() - 1) as f64;
+                let lower = rank.floor() as usize;
+                let upper = rank.ceil() as usize;
+                sorted_values[lower]
+                    + (sorted_values[upper] - sorted_values[lower]) * (rank - lower as f64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: [f64; 5] = [4., 1., 3., 5., 2.];
+
+    #[test]
+    fn mean_and_sample_standard_deviation() {
+        assert_eq!(ReplicaStatistic::Mean.compute(&VALUES), 3.);
+        assert_eq!(
+            ReplicaStatistic::StandardDeviation.compute(&VALUES),
+            2.5f64.sqrt()
+        );
+        // A single replica has no spread
+        assert_eq!(ReplicaStatistic::StandardDeviation.compute(&[7.]), 0.);
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_the_closest_ranks() {
+        assert_eq!(ReplicaStatistic::Percentile(0.).compute(&VALUES), 1.);
+        assert_eq!(ReplicaStatistic::Percentile(50.).compute(&VALUES), 3.);
+        assert_eq!(ReplicaStatistic::Percentile(100.).compute(&VALUES), 5.);
+        assert_eq!(ReplicaStatistic::Percentile(5.).compute(&VALUES), 1.2);
+        assert_eq!(ReplicaStatistic::Percentile(95.).compute(&VALUES), 4.8);
+        assert_eq!(ReplicaStatistic::Percentile(95.).compute(&[7.]), 7.);
+    }
+
+    #[test]
+    fn names_of_the_statistics() {
+        let names = ReplicaStatistic::ALL
+            .iter()
+            .map(|statistic| statistic.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["mean", "std", "p05", "p50", "p95"]);
+    }
+}
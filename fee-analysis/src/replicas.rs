@@ -0,0 +1,165 @@
+use crate::*;
+
+/// Runs of the same scenario with different seeds, whose outputs are aggregated cell by cell.
+pub struct Replicas {
+    scenario: Scenario,
+}
+
+impl Replicas {
+    /// The aggregated outputs and the number of their leading columns that identify a row
+    /// (e.g. the slot, or the address and the strategy of a user) and are copied instead of aggregated.
+    pub const AGGREGATED_OUTPUTS: [(&'static str, usize); 9] = [
+        (OUTPUT_BASE_FEE_CSV_FILENAME, 1),
+        (OUTPUT_ACTIVE_USER_NUM_CSV_FILENAME, 1),
+        (OUTPUT_MEMPOOL_CSV_FILENAME, 1),
+        (OUTPUT_FUNCTION_NUM_CSV_FILENAME, 1),
+        (OUTPUT_USERS_CSV_FILENAME, 2),
+        (OUTPUT_GAS_USED_CSV_FILENAME, 1),
+        (OUTPUT_GAS_TARGET_CSV_FILENAME, 1),
+        (OUTPUT_PENDING_USER_NUM_CSV_FILENAME, 1),
+        (OUTPUT_STATE_SIZE_CSV_FILENAME, 1),
+    ];
+
+    pub fn new(scenario: Scenario) -> Self {
+        Self { scenario }
+    }
+
+    fn replica_dir_name(i: usize) -> String {
+        format!("replica_{:02}", i)
+    }
+
+    /// The scenario of each replica, seeded with seed, seed + 1, ...
+    fn replica_scenarios(&self) -> Vec<Scenario> {
+        let output_dir_path = Path::new(&self.scenario.output_dir_path);
+        (0..self.scenario.replicas)
+            .map(|i| {
+                let mut scenario = self.scenario.clone();
+                scenario.replicas = 1;
+                scenario.seed = self.scenario.seed.wrapping_add(i as u64);
+                scenario.output_dir_path = output_dir_path
+                    .join(Self::replica_dir_name(i))
+                    .to_string_lossy()
+                    .into_owned();
+                scenario
+            })
+            .collect()
+    }
+
+    /// Run the replicas and write the statistics of their outputs.
+    /// Return the mean summary of the replicas, or None if any of them failed.
+    pub fn run(&self, job_num: usize) -> Option<RunSummary> {
+        let output_dir_path = PathBuf::from(&self.scenario.output_dir_path);
+        if let Err(e) = std::fs::create_dir_all(&output_dir_path) {
+            eprintln!("Error: {:?}", e)
+        }
+        if let Err(e) = self.scenario.write(&output_dir_path) {
+            eprintln!("Error: {:?}", e)
+        }
+
+        let scenarios = self.replica_scenarios();
+        let summaries = run_in_parallel(scenarios.len(), job_num, |i| {
+            let mut sim = Simulator::new(scenarios[i].clone());
+            sim.set_log_level(LogLevel::Error);
+            sim.run();
+            println!("{} is complete.", Self::replica_dir_name(i));
+            sim.summary()
+        })
+        .into_iter()
+        .collect::<Option<Vec<_>>>()?;
+
+        // The per-slot outputs are aggregated from their CSV files
+        let writes_csv = self.scenario.output_formats.contains(&OutputFormat::Csv);
+        for &(filename, key_column_num) in Self::AGGREGATED_OUTPUTS.iter() {
+            if !self.scenario.selects_output(filename)
+                || (!writes_csv && filename != OUTPUT_USERS_CSV_FILENAME)
+            {
+                continue;
+            }
+            if let Err(e) = self.output_csv_statistics(&output_dir_path, filename, key_column_num) {
+                eprintln!("Error: {:?}", e)
+            }
+        }
+        Some(RunSummary::mean(&summaries))
+    }
+
+    /// Write each statistic of the output of the replicas, in the same schema and with the same
+    /// header as the output, to the directory of the statistic.
+    fn output_csv_statistics(
+        &self,
+        output_dir_path: &Path,
+        filename: &str,
+        key_column_num: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut header = None;
+        let mut replica_records = Vec::new();
+        for i in 0..self.scenario.replicas {
+            let file_path = output_dir_path
+                .join(Self::replica_dir_name(i))
+                .join(filename);
+            let mut rdr = csv::Reader::from_path(file_path)?;
+            let replica_header = rdr.headers()?.clone();
+            if header.get_or_insert_with(|| replica_header.clone()) != &replica_header {
+                return Err(format!("the replicas of {} differ in header", filename).into());
+            }
+            let records = rdr
+                .records()
+                .map(|record| Ok(record?.iter().map(|cell| cell.to_string()).collect()))
+                .collect::<Result<Vec<Vec<String>>, csv::Error>>()?;
+            replica_records.push(records);
+        }
+        let first_records = &replica_records[0];
+        let same_shape = replica_records.iter().all(|records| {
+            records.len() == first_records.len()
+                && records
+                    .iter()
+                    .zip(first_records)
+                    .all(|(record, first_record)| record.len() == first_record.len())
+        });
+        if !same_shape {
+            return Err(format!("the replicas of {} differ in shape", filename).into());
+        }
+
+        for statistic in ReplicaStatistic::ALL.iter() {
+            let statistic_dir_path = output_dir_path.join(statistic.name());
+            std::fs::create_dir_all(&statistic_dir_path)?;
+            let file = File::create(statistic_dir_path.join(filename))?;
+            let mut wtr = csv::Writer::from_writer(file);
+            if let Some(header) = header.as_ref() {
+                wtr.write_record(header)?;
+            }
+            for (row, first_record) in first_records.iter().enumerate() {
+                let record = first_record
+                    .iter()
+                    .enumerate()
+                    .map(|(column, first_cell)| {
+                        if column < key_column_num {
+                            return first_cell.clone();
+                        }
+                        let values = replica_records
+                            .iter()
+                            .map(|records| Self::parse_cell(&records[row][column]))
+                            .collect::<Option<Vec<_>>>();
+                        match values {
+                            Some(values) => statistic.compute(&values).to_string(),
+                            None => first_cell.clone(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if let Err(e) = wtr.write_record(record) {
+                    eprintln!("Error: {:?}", e)
+                };
+            }
+            wtr.flush()?;
+        }
+        Ok(())
+    }
+
+    /// A number, or a boolean as 0 or 1, so that the mean of a flag is the share of replicas.
+    fn parse_cell(cell: &str) -> Option<f64> {
+        match cell {
+            "true" => Some(1.),
+            "false" => Some(0.),
+            cell => cell.parse().ok(),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::*;
+
+/// A rollup whose sequencer aggregates the transfers of its users into batches on a data shard.
+pub struct Rollup {
+    pub id: usize,
+    pub sequencer: Address,
+    pub data_shard_id: usize,
+    /// Creation slots of the rollup transactions that are not posted yet.
+    pub pending_transactions: VecDeque<Slot>,
+    /// Index of the batch waiting for inclusion.
+    pub posting_batch: Option<usize>,
+    nonce: Nonce,
+}
+
+impl Rollup {
+    pub fn new(id: usize, sequencer: Address, data_shard_id: usize) -> Self {
+        Self {
+            id,
+            sequencer,
+            data_shard_id,
+            pending_transactions: VecDeque::new(),
+            posting_batch: None,
+            nonce: 0,
+        }
+    }
+
+    pub fn next_nonce(&mut self) -> Nonce {
+        self.nonce += 1;
+        self.nonce - 1
+    }
+}
@@ -0,0 +1,13 @@
+use crate::*;
+
+/// A batch posted by the sequencer of a rollup.
+pub struct RollupBatch {
+    pub rollup_id: usize,
+    pub data_shard_id: usize,
+    pub hash: TransactionHash,
+    pub posted_slot: Slot,
+    /// Creation slots of the rollup transactions in the batch.
+    pub created_slots: Vec<Slot>,
+    /// The size in bytes, excluding the header.
+    pub size: Gas,
+}
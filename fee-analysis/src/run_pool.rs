@@ -0,0 +1,27 @@
+use crate::*;
+
+/// Run the tasks 0..task_num with `job_num` of them at the same time.
+/// A task that panics gives None, instead of stopping the others.
+pub fn run_in_parallel<T, F>(task_num: usize, job_num: usize, task: F) -> Vec<Option<T>>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    assert!(job_num > 0, "JOBS must be a positive integer");
+    let next_task = AtomicUsize::new(0);
+    let results = Mutex::new((0..task_num).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..std::cmp::min(job_num, task_num) {
+            scope.spawn(|| loop {
+                let i = next_task.fetch_add(1, Ordering::SeqCst);
+                if i >= task_num {
+                    break;
+                }
+                if let Ok(result) = std::panic::catch_unwind(AssertUnwindSafe(|| task(i))) {
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
@@ -0,0 +1,50 @@
+/// Summary metrics of a run, for comparing the runs of a sweep.
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    /// The mean base fee over slots and execution shards (Gwei).
+    pub mean_base_fee: f64,
+    /// The mean standard deviation of the base fees across execution shards (Gwei).
+    pub base_fee_std: f64,
+    /// Gas used over the gas target, summed over slots and execution shards.
+    pub gas_utilization: f64,
+    pub executed_transaction_num: usize,
+    pub migration_num: usize,
+    pub cross_transfer_num: usize,
+}
+
+impl RunSummary {
+    pub const FIELD_NAMES: [&'static str; 6] = [
+        "mean_base_fee",
+        "base_fee_std",
+        "gas_utilization",
+        "executed_transaction_num",
+        "migration_num",
+        "cross_transfer_num",
+    ];
+
+    /// The mean over runs, with the counts rounded.
+    pub fn mean(summaries: &[RunSummary]) -> Self {
+        let n = summaries.len() as f64;
+        let mean = |f: fn(&RunSummary) -> f64| summaries.iter().map(f).sum::<f64>() / n;
+        Self {
+            mean_base_fee: mean(|summary| summary.mean_base_fee),
+            base_fee_std: mean(|summary| summary.base_fee_std),
+            gas_utilization: mean(|summary| summary.gas_utilization),
+            executed_transaction_num: mean(|summary| summary.executed_transaction_num as f64)
+                .round() as usize,
+            migration_num: mean(|summary| summary.migration_num as f64).round() as usize,
+            cross_transfer_num: mean(|summary| summary.cross_transfer_num as f64).round() as usize,
+        }
+    }
+
+    pub fn to_record(&self) -> Vec<String> {
+        vec![
+            self.mean_base_fee.to_string(),
+            self.base_fee_std.to_string(),
+            self.gas_utilization.to_string(),
+            self.executed_transaction_num.to_string(),
+            self.migration_num.to_string(),
+            self.cross_transfer_num.to_string(),
+        ]
+    }
+}
@@ -0,0 +1,615 @@
+use crate::*;
+
+/// Everything a simulation run depends on.
+/// Read from a TOML or JSON file, where omitted fields take their default values,
+/// and overridden by the command-line flags.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// The number of slots.
+    pub end_slot: Slot,
+    /// The seed of all the random numbers, so that the same scenario gives the same outputs.
+    pub seed: u64,
+    /// The number of runs with the seeds seed, seed + 1, ..., whose outputs are aggregated.
+    pub replicas: usize,
+
+    // Users
+    /// The maximum number of users.
+    pub user_num: usize,
+    /// How many slots a user stays on a shard on average before considering a move.
+    pub average_shard_switching_interval: Slot,
+    pub percentage_of_minimum: f64,
+    pub percentage_of_weighted_random: f64,
+    pub percentage_of_decreasing_minimum: f64,
+    pub popular_user_exists: bool,
+    pub popular_user_is_switcher: bool,
+    pub percentage_of_token_transfers: f64,
+    pub async_calls: bool,
+    pub cross_shard_transfer_mode: CrossShardTransferMode,
+    pub lock_timeout: Slot,
+    pub migration: MigrationMechanism,
+    pub percentage_of_legacy_users: f64,
+    pub percentage_of_rollup_users: f64,
+    pub batch_size: usize,
+    pub batch_semantics: BatchSemantics,
+
+    // User graph
+    /// A BigQuery Ethereum transactions csv file to build the user graph from, instead of a random graph.
+    pub big_query_csv: Option<String>,
+    pub hub_contract_num: usize,
+
+    // Protocol
+    pub shard_num: usize,
+    /// The genesis gas target of each shard (u64, since TOML has no wider integers).
+    pub block_gas_target: u64,
+    pub gas_target_rule: GasTargetRule,
+    /// The gas target the proposers vote toward under proposer_voting, for all shards or per shard.
+    /// The genesis gas target if empty.
+    pub preferred_gas_target: Vec<u64>,
+    pub fee_market: FeeMarket,
+    pub base_fee_coupling: BaseFeeCoupling,
+    pub state_rent: StateRent,
+    pub data_shard_num: usize,
+    pub sponsored_applies: bool,
+
+    // Faults
+    pub adversary: ProposerBehavior,
+    pub adversary_share: f64,
+    /// For all shards, or per shard.
+    pub missed_proposal_probability: Vec<f64>,
+
+    // Outputs
+    pub output_dir_path: String,
+    /// Filenames of the outputs to write. All outputs are written if empty.
+    pub outputs: Vec<String>,
+    /// Formats of the outputs with one record per slot.
+    pub output_formats: Vec<OutputFormat>,
+    /// Whether or not every transaction is written into transactions.csv.
+    pub trace: bool,
+
+    // Tables come last in TOML
+    pub outages: Vec<Outage>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            end_slot: DEFAULT_END_SLOT,
+            seed: DEFAULT_SEED,
+            replicas: DEFAULT_REPLICAS,
+            user_num: DEFAULT_USER_NUM,
+            average_shard_switching_interval: DEFAULT_AVERAGE_SHARD_SWITCHING_INTERVAL,
+            percentage_of_minimum: DEFAULT_PERCENTAGE_OF_MINIMUM,
+            percentage_of_weighted_random: DEFAULT_PERCENTAGE_OF_WEIGHTED_RANDOM,
+            percentage_of_decreasing_minimum: DEFAULT_PERCENTAGE_OF_DECREASING_MINIMUM,
+            popular_user_exists: false,
+            popular_user_is_switcher: false,
+            percentage_of_token_transfers: DEFAULT_PERCENTAGE_OF_TOKEN_TRANSFERS,
+            async_calls: false,
+            cross_shard_transfer_mode: CrossShardTransferMode::default(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            migration: MigrationMechanism::default(),
+            percentage_of_legacy_users: DEFAULT_PERCENTAGE_OF_LEGACY_USERS,
+            percentage_of_rollup_users: DEFAULT_PERCENTAGE_OF_ROLLUP_USERS,
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_semantics: BatchSemantics::default(),
+            big_query_csv: None,
+            hub_contract_num: DEFAULT_HUB_CONTRACT_NUM,
+            shard_num: DEFAULT_SHARD_NUM,
+            block_gas_target: DEFAULT_BLOCK_GAS_TARGET as u64,
+            gas_target_rule: GasTargetRule::default(),
+            preferred_gas_target: Vec::new(),
+            fee_market: FeeMarket::default(),
+            base_fee_coupling: BaseFeeCoupling::default(),
+            state_rent: StateRent::default(),
+            data_shard_num: DEFAULT_DATA_SHARD_NUM,
+            sponsored_applies: false,
+            adversary: ProposerBehavior::default(),
+            adversary_share: DEFAULT_ADVERSARY_SHARE,
+            missed_proposal_probability: vec![DEFAULT_MISSED_PROPOSAL_PROBABILITY],
+            output_dir_path: DEFAULT_OUTPUT_DIR_PATH.to_string(),
+            outputs: Vec::new(),
+            output_formats: vec![OutputFormat::default()],
+            trace: false,
+            outages: Vec::new(),
+        }
+    }
+}
+
+impl Scenario {
+    /// Read a scenario file, whose format is decided by the extension (.toml or .json).
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        match Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            _ => Err(format!("unknown scenario format: {}", path).into()),
+        }
+    }
+
+    /// Override the scenario with the flags given on the command line.
+    pub fn apply_arg_matches(&mut self, arg_matches: &clap::ArgMatches) {
+        if let Some(end_slot) = arg_matches.value_of("END_SLOT") {
+            self.end_slot = end_slot
+                .parse()
+                .expect("END_SLOT must be a positive integer");
+        }
+        if let Some(seed) = arg_matches.value_of("SEED") {
+            self.seed = seed.parse().expect("SEED must be a non-negative integer");
+        }
+        if let Some(replicas) = arg_matches.value_of("REPLICAS") {
+            self.replicas = replicas
+                .parse()
+                .expect("REPLICAS must be a positive integer");
+        }
+        if let Some(user_num) = arg_matches.value_of("USER_NUM") {
+            self.user_num = user_num
+                .parse()
+                .expect("user_num must be a positive integer");
+        }
+        if let Some(average_shard_switching_interval) =
+            arg_matches.value_of("AVERAGE_SHARD_SWITCHING_INTERVAL")
+        {
+            self.average_shard_switching_interval = average_shard_switching_interval
+                .parse()
+                .expect("AVERAGE_SHARD_SWITCHING_INTERVAL must be a positive integer");
+        }
+        if let Some(percentage_of_minimum) = arg_matches.value_of("PERCENTAGE_OF_MINIMUM") {
+            self.percentage_of_minimum = percentage_of_minimum
+                .parse()
+                .expect("PERCENTAGE_OF_MINIMUM must be a positive real number");
+        }
+        if let Some(percentage_of_weighted_random) =
+            arg_matches.value_of("PERCENTAGE_OF_WEIGHTED_RANDOM")
+        {
+            self.percentage_of_weighted_random = percentage_of_weighted_random
+                .parse()
+                .expect("PERCENTAGE_OF_WEIGHTED_RANDOM must be a positive real number");
+        }
+        if let Some(percentage_of_decreasing_minimum) =
+            arg_matches.value_of("PERCENTAGE_OF_DECREASING_MINIMUM")
+        {
+            self.percentage_of_decreasing_minimum = percentage_of_decreasing_minimum
+                .parse()
+                .expect("PERCENTAGE_OF_DECREASING_MINIMUM must be a positive real number");
+        }
+        if arg_matches.is_present("POPULAR_USER_EXISTS") {
+            self.popular_user_exists = true;
+        }
+        if arg_matches.is_present("POPULAR_USER_IS_SWITCHER") {
+            self.popular_user_is_switcher = true;
+        }
+        if let Some(percentage_of_token_transfers) =
+            arg_matches.value_of("PERCENTAGE_OF_TOKEN_TRANSFERS")
+        {
+            self.percentage_of_token_transfers = percentage_of_token_transfers
+                .parse()
+                .expect("PERCENTAGE_OF_TOKEN_TRANSFERS must be a positive real number");
+        }
+        if arg_matches.is_present("ASYNC_CALLS") {
+            self.async_calls = true;
+        }
+        if let Some(cross_shard_transfer_mode) = arg_matches.value_of("CROSS_SHARD_TRANSFER_MODE") {
+            self.cross_shard_transfer_mode = cross_shard_transfer_mode
+                .parse()
+                .expect("CROSS_SHARD_TRANSFER_MODE must be a cross-shard transfer mode");
+        }
+        if let Some(lock_timeout) = arg_matches.value_of("LOCK_TIMEOUT") {
+            self.lock_timeout = lock_timeout
+                .parse()
+                .expect("LOCK_TIMEOUT must be a positive integer");
+        }
+        if let Some(migration) = arg_matches.value_of("MIGRATION") {
+            self.migration = migration
+                .parse()
+                .expect("MIGRATION must be a migration mechanism");
+        }
+        if let Some(percentage_of_legacy_users) = arg_matches.value_of("PERCENTAGE_OF_LEGACY_USERS")
+        {
+            self.percentage_of_legacy_users = percentage_of_legacy_users
+                .parse()
+                .expect("PERCENTAGE_OF_LEGACY_USERS must be a positive real number");
+        }
+        if let Some(percentage_of_rollup_users) = arg_matches.value_of("PERCENTAGE_OF_ROLLUP_USERS")
+        {
+            self.percentage_of_rollup_users = percentage_of_rollup_users
+                .parse()
+                .expect("PERCENTAGE_OF_ROLLUP_USERS must be a positive real number");
+        }
+        if let Some(batch_size) = arg_matches.value_of("BATCH_SIZE") {
+            self.batch_size = batch_size
+                .parse()
+                .expect("BATCH_SIZE must be a positive integer");
+        }
+        if let Some(batch_semantics) = arg_matches.value_of("BATCH_SEMANTICS") {
+            self.batch_semantics = batch_semantics
+                .parse()
+                .expect("BATCH_SEMANTICS must be batch semantics");
+        }
+        if let Some(big_query_csv) = arg_matches.value_of("BIG_QUERY_CSV") {
+            self.big_query_csv = Some(big_query_csv.to_string());
+        }
+        if let Some(hub_contract_num) = arg_matches.value_of("HUB_CONTRACT_NUM") {
+            self.hub_contract_num = hub_contract_num
+                .parse()
+                .expect("HUB_CONTRACT_NUM must be a positive integer");
+        }
+        if let Some(shard_num) = arg_matches.value_of("SHARD_NUM") {
+            self.shard_num = shard_num
+                .parse()
+                .expect("SHARD_NUM must be a positive integer");
+        }
+        if let Some(block_gas_target) = arg_matches.value_of("BLOCK_GAS_TARGET") {
+            self.block_gas_target = block_gas_target
+                .parse()
+                .expect("BLOCK_GAS_TARGET must be a positive integer");
+        }
+        if let Some(gas_target_rule) = arg_matches.value_of("GAS_TARGET_RULE") {
+            self.gas_target_rule = gas_target_rule
+                .parse()
+                .expect("GAS_TARGET_RULE must be a gas target rule");
+        }
+        if let Some(fee_market) = arg_matches.value_of("FEE_MARKET") {
+            self.fee_market = fee_market.parse().expect("FEE_MARKET must be a fee market");
+        }
+        if let Some(base_fee_coupling) = arg_matches.value_of("BASE_FEE_COUPLING") {
+            self.base_fee_coupling = base_fee_coupling
+                .parse()
+                .expect("BASE_FEE_COUPLING must be a base fee coupling");
+        }
+        if let Some(state_rent) = arg_matches.value_of("STATE_RENT") {
+            self.state_rent = state_rent.parse().expect("STATE_RENT must be a state rent");
+        }
+        if let Some(data_shard_num) = arg_matches.value_of("DATA_SHARD_NUM") {
+            self.data_shard_num = data_shard_num
+                .parse()
+                .expect("DATA_SHARD_NUM must be an integer less than SHARD_NUM");
+        }
+        if arg_matches.is_present("SPONSORED_APPLIES") {
+            self.sponsored_applies = true;
+        }
+        if let Some(adversary) = arg_matches.value_of("ADVERSARY") {
+            self.adversary = adversary
+                .parse()
+                .expect("ADVERSARY must be a proposer behavior");
+        }
+        if let Some(adversary_share) = arg_matches.value_of("ADVERSARY_SHARE") {
+            self.adversary_share = adversary_share
+                .parse()
+                .expect("ADVERSARY_SHARE must be a positive real number");
+        }
+        if let Some(preferred_gas_target) = arg_matches.value_of("PREFERRED_GAS_TARGET") {
+            self.preferred_gas_target = preferred_gas_target
+                .split(',')
+                .map(|gas_target| {
+                    gas_target
+                        .parse()
+                        .expect("PREFERRED_GAS_TARGET must be positive integers")
+                })
+                .collect();
+        }
+        if let Some(missed_proposal_probability) =
+            arg_matches.value_of("MISSED_PROPOSAL_PROBABILITY")
+        {
+            self.missed_proposal_probability = missed_proposal_probability
+                .split(',')
+                .map(|probability| {
+                    probability
+                        .parse()
+                        .expect("MISSED_PROPOSAL_PROBABILITY must be real numbers in [0, 1]")
+                })
+                .collect();
+        }
+        if let Some(outages) = arg_matches.values_of("OUTAGE") {
+            self.outages = outages
+                .map(|outage| {
+                    outage
+                        .parse()
+                        .expect("OUTAGE must be shard_id:from_slot:to_slot")
+                })
+                .collect();
+        }
+        if let Some(output_dir_path) = arg_matches.value_of("OUTPUT_DIR_PATH") {
+            self.output_dir_path = output_dir_path.to_string();
+        }
+        if let Some(output_formats) = arg_matches.value_of("OUTPUT_FORMAT") {
+            self.output_formats = output_formats
+                .split(',')
+                .map(|output_format| {
+                    output_format
+                        .parse()
+                        .expect("OUTPUT_FORMAT must be comma-separated output formats")
+                })
+                .collect();
+        }
+        if arg_matches.is_present("TRACE") {
+            self.trace = true;
+        }
+    }
+
+    /// Check the values that the types of the fields do not restrict.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("REPLICAS", self.replicas as u64),
+            (
+                "AVERAGE_SHARD_SWITCHING_INTERVAL",
+                self.average_shard_switching_interval,
+            ),
+            ("BATCH_SIZE", self.batch_size as u64),
+            ("SHARD_NUM", self.shard_num as u64),
+            ("BLOCK_GAS_TARGET", self.block_gas_target),
+        ] {
+            if value == 0 {
+                return Err(format!("{} must be a positive integer", name));
+            }
+        }
+        if self.data_shard_num >= self.shard_num {
+            return Err(format!(
+                "DATA_SHARD_NUM must be less than SHARD_NUM: {}",
+                self.data_shard_num
+            ));
+        }
+        // Percentages are fractions of users or transfers
+        for (name, percentage) in [
+            ("PERCENTAGE_OF_MINIMUM", self.percentage_of_minimum),
+            (
+                "PERCENTAGE_OF_WEIGHTED_RANDOM",
+                self.percentage_of_weighted_random,
+            ),
+            (
+                "PERCENTAGE_OF_DECREASING_MINIMUM",
+                self.percentage_of_decreasing_minimum,
+            ),
+            (
+                "PERCENTAGE_OF_TOKEN_TRANSFERS",
+                self.percentage_of_token_transfers,
+            ),
+            (
+                "PERCENTAGE_OF_LEGACY_USERS",
+                self.percentage_of_legacy_users,
+            ),
+            (
+                "PERCENTAGE_OF_ROLLUP_USERS",
+                self.percentage_of_rollup_users,
+            ),
+        ] {
+            if !(0. ..=1.).contains(&percentage) {
+                return Err(format!("{} must be in [0, 1]: {}", name, percentage));
+            }
+        }
+        let probability_num = self.missed_proposal_probability.len();
+        if probability_num != 1 && probability_num != self.shard_num {
+            return Err(format!(
+                "MISSED_PROPOSAL_PROBABILITY must have 1 or {} values",
+                self.shard_num
+            ));
+        }
+        if let Some(probability) = self
+            .missed_proposal_probability
+            .iter()
+            .find(|probability| !(0. ..=1.).contains(*probability))
+        {
+            return Err(format!(
+                "MISSED_PROPOSAL_PROBABILITY must be in [0, 1]: {}",
+                probability
+            ));
+        }
+        let gas_target_num = self.preferred_gas_target.len();
+        if gas_target_num > 1 && gas_target_num != self.shard_num {
+            return Err(format!(
+                "PREFERRED_GAS_TARGET must have 1 or {} values",
+                self.shard_num
+            ));
+        }
+        if self.data_shard_num > 0
+            && (self.gas_target_rule != GasTargetRule::Fixed
+                || self.base_fee_coupling != BaseFeeCoupling::Independent)
+        {
+            return Err(
+                "DATA_SHARD_NUM requires the fixed gas target rule and independent base fees"
+                    .to_string(),
+            );
+        }
+        if let Some(outage) = self
+            .outages
+            .iter()
+            .find(|outage| outage.shard_id >= self.shard_num)
+        {
+            return Err(format!(
+                "OUTAGE must be on a shard below {}: {}",
+                self.shard_num, outage.shard_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// The number of transactions users send per slot, in proportion to the global gas target.
+    pub fn global_transaction_num(&self) -> usize {
+        let global_gas_target = self.block_gas_target as Gas * self.shard_num as Gas;
+        let global_transaction_gas_per_slot =
+            (TRANSACTION_OCCUPANCY * global_gas_target as f64) as Gas;
+        (global_transaction_gas_per_slot / AVERAGE_GAS_PER_TRANSACTION) as usize
+    }
+
+    /// Whether or not the output of the filename is written.
+    pub fn selects_output(&self, filename: &str) -> bool {
+        self.outputs.is_empty() || self.outputs.iter().any(|output| output == filename)
+    }
+
+    /// Write the resolved scenario, so that the run can be reproduced from its outputs.
+    pub fn write(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_SCENARIO_FILENAME);
+        std::fs::write(file_path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_scenario_file(filename: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(filename);
+        std::fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn from_file_reads_toml_and_json_by_the_extension() {
+        let toml_path = write_scenario_file(
+            "scenario_test.toml",
+            "end_slot = 42\nshard_num = 8\nfee_market = \"multi_dimensional\"\n",
+        );
+        let json_path = write_scenario_file(
+            "scenario_test.json",
+            r#"{"end_slot": 42, "shard_num": 8, "fee_market": "multi_dimensional"}"#,
+        );
+        for path in [toml_path, json_path] {
+            let scenario = Scenario::from_file(&path).unwrap();
+            assert_eq!(scenario.end_slot, 42);
+            assert_eq!(scenario.shard_num, 8);
+            assert_eq!(scenario.fee_market, FeeMarket::MultiDimensional);
+            // Omitted fields take their default values
+            assert_eq!(scenario.user_num, DEFAULT_USER_NUM);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn from_file_rejects_unknown_formats_and_fields() {
+        let yaml_path = write_scenario_file("scenario_test.yaml", "end_slot: 42\n");
+        let unknown_field_path = write_scenario_file("scenario_test_unknown.toml", "slots = 42\n");
+        for path in [yaml_path, unknown_field_path] {
+            assert!(Scenario::from_file(&path).is_err());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn validate_accepts_the_default_scenario() {
+        assert!(Scenario::default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_zeros_that_would_stop_a_run() {
+        for scenario in [
+            Scenario {
+                replicas: 0,
+                ..Scenario::default()
+            },
+            Scenario {
+                average_shard_switching_interval: 0,
+                ..Scenario::default()
+            },
+            Scenario {
+                batch_size: 0,
+                ..Scenario::default()
+            },
+            Scenario {
+                shard_num: 0,
+                ..Scenario::default()
+            },
+            Scenario {
+                block_gas_target: 0,
+                ..Scenario::default()
+            },
+        ] {
+            assert!(scenario.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn validate_requires_an_execution_shard() {
+        let scenario = Scenario {
+            shard_num: 2,
+            data_shard_num: 2,
+            ..Scenario::default()
+        };
+        assert!(scenario.validate().is_err());
+        assert!(Scenario {
+            data_shard_num: 1,
+            ..scenario
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_percentages_outside_the_unit_interval() {
+        for percentage in [-0.1, 1.1, f64::NAN] {
+            for scenario in [
+                Scenario {
+                    percentage_of_minimum: percentage,
+                    ..Scenario::default()
+                },
+                Scenario {
+                    percentage_of_token_transfers: percentage,
+                    ..Scenario::default()
+                },
+                Scenario {
+                    percentage_of_rollup_users: percentage,
+                    ..Scenario::default()
+                },
+            ] {
+                assert!(scenario.validate().is_err());
+            }
+        }
+        assert!(Scenario {
+            percentage_of_minimum: 1.,
+            ..Scenario::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_probabilities_and_outages_that_do_not_fit_the_shards() {
+        for scenario in [
+            Scenario {
+                missed_proposal_probability: vec![0.; 3],
+                ..Scenario::default()
+            },
+            Scenario {
+                missed_proposal_probability: vec![1.5],
+                ..Scenario::default()
+            },
+            Scenario {
+                outages: vec!["99:0:10".parse().unwrap()],
+                ..Scenario::default()
+            },
+        ] {
+            assert!(scenario.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn data_shards_require_fixed_gas_targets_and_independent_base_fees() {
+        let scenario = Scenario {
+            data_shard_num: 1,
+            ..Scenario::default()
+        };
+        assert!(scenario.validate().is_ok());
+        for scenario in [
+            Scenario {
+                gas_target_rule: GasTargetRule::Deterministic,
+                ..scenario.clone()
+            },
+            Scenario {
+                base_fee_coupling: BaseFeeCoupling::Global,
+                ..scenario.clone()
+            },
+        ] {
+            assert!(scenario.validate().is_err());
+            // Without data shards, the same scenario is valid
+            assert!(Scenario {
+                data_shard_num: 0,
+                ..scenario
+            }
+            .validate()
+            .is_ok());
+        }
+    }
+}
@@ -1,71 +1,449 @@
 use crate::*;
 
+/// Writes an output into the output directory at the end of the run.
+type OutputWriter = fn(&Simulator, &Path) -> Result<(), Box<dyn Error>>;
+
 /// Sharded blockchain simulator.
 pub struct Simulator {
     environment: Environment,
+    scenario: Scenario,
     duration: Slot,
+    log_level: LogLevel,
 }
 
 impl Simulator {
-    pub fn new(duration: Slot) -> Self {
+    pub fn new(scenario: Scenario) -> Self {
         Self {
             environment: Environment::new(),
-            duration,
+            duration: scenario.end_slot,
+            scenario,
+            log_level: LogLevel::default(),
         }
     }
 
+    pub fn set_log_level(&mut self, log_level: LogLevel) {
+        self.log_level = log_level;
+        self.environment.log_level = log_level;
+    }
+
     /// Runs from slot 0 to slot (duration - 1).
-    pub fn run(&mut self, arg_matches: &clap::ArgMatches) {
-        self.environment.setup(&arg_matches);
+    pub fn run(&mut self) {
+        let mut manifest = Manifest::new(&self.scenario);
+        self.environment.setup(&self.scenario);
 
-        let output_dir_path = Path::new(
-            arg_matches
-                .value_of("OUTPUT_DIR_PATH")
-                .unwrap_or(DEFAULT_OUTPUT_DIR_PATH),
-        );
+        let output_dir_path = PathBuf::from(&self.scenario.output_dir_path);
+        let output_dir_path = output_dir_path.as_path();
 
         if let Err(e) = std::fs::create_dir(output_dir_path) {
             if e.kind() != std::io::ErrorKind::AlreadyExists {
                 eprintln!("Error: {:?}", e)
             }
         }
+        match self.scenario.write(output_dir_path) {
+            Ok(()) => manifest.outputs.push(OUTPUT_SCENARIO_FILENAME.to_string()),
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
 
-        (0..self.duration).for_each(|_| {
-            println!();
-            println!("slot: {}", self.environment.blockchain.slot);
+        let mut slot_sinks = self.create_slot_sinks(output_dir_path);
+        manifest.outputs.extend(
+            slot_sinks
+                .iter()
+                .filter_map(|slot_sink| slot_sink.csv_filename())
+                .map(|filename| filename.to_string()),
+        );
+        let mut tidy_writers = self.create_tidy_writers(output_dir_path, &mut manifest);
+        if let Err(e) = manifest.write(output_dir_path) {
+            eprintln!("Error: {:?}", e)
+        }
+        (0..self.duration).for_each(|slot| {
+            if self.log_level >= LogLevel::Debug {
+                println!();
+                println!("slot: {}", self.environment.blockchain.slot);
+            }
             self.environment.process();
+            let mut tidy_records = Vec::new();
+            for slot_sink in slot_sinks.iter_mut() {
+                let tidy_records = if tidy_writers.is_empty() {
+                    None
+                } else {
+                    Some(&mut tidy_records)
+                };
+                if let Err(e) = slot_sink.append(self, slot, tidy_records) {
+                    eprintln!("Error: {:?}", e)
+                }
+            }
+            for tidy_writer in tidy_writers.iter_mut() {
+                if let Err(e) = tidy_writer.write_records(&tidy_records) {
+                    eprintln!("Error: {:?}", e)
+                }
+            }
+            if self.log_level == LogLevel::Info {
+                self.print_progress(slot);
+            }
         });
+        for tidy_writer in tidy_writers {
+            if let Err(e) = tidy_writer.finish() {
+                eprintln!("Error: {:?}", e)
+            }
+        }
 
-        if let Err(e) = self.output_csv_base_fee(output_dir_path) {
-            eprintln!("Error: {:?}", e)
+        let mut outputs: Vec<(&str, OutputWriter)> = vec![
+            (OUTPUT_USERS_CSV_FILENAME, Self::output_csv_users),
+            (OUTPUT_ADVERSARY_CSV_FILENAME, Self::output_csv_adversary),
+            (OUTPUT_CROSS_CALL_CSV_FILENAME, Self::output_csv_cross_call),
+            (
+                OUTPUT_CROSS_TRANSFER_CSV_FILENAME,
+                Self::output_csv_cross_transfer,
+            ),
+            (OUTPUT_MIGRATION_CSV_FILENAME, Self::output_csv_migration),
+            (
+                OUTPUT_ROLLUP_BATCH_CSV_FILENAME,
+                Self::output_csv_rollup_batch,
+            ),
+            (OUTPUT_RELAYER_CSV_FILENAME, Self::output_csv_relayer),
+        ];
+        if self.scenario.trace {
+            outputs.push((
+                OUTPUT_TRANSACTIONS_CSV_FILENAME,
+                Self::output_csv_transactions,
+            ));
         }
-        if let Err(e) = self.output_csv_active_user_num(output_dir_path) {
-            eprintln!("Error: {:?}", e)
+        for (filename, output) in outputs {
+            if !self.scenario.selects_output(filename) {
+                continue;
+            }
+            match output(self, output_dir_path) {
+                Ok(()) => manifest.outputs.push(filename.to_string()),
+                Err(e) => eprintln!("Error: {:?}", e),
+            }
         }
-        if let Err(e) = self.output_csv_users(output_dir_path) {
+
+        manifest.finish();
+        if let Err(e) = manifest.write(output_dir_path) {
             eprintln!("Error: {:?}", e)
         }
-        if let Err(e) = self.output_csv_function_num(output_dir_path) {
-            eprintln!("Error: {:?}", e)
+    }
+
+    /// Open the outputs with one record per slot.
+    fn create_slot_sinks(&self, output_dir_path: &Path) -> Vec<SlotSink> {
+        let shard_num = self.environment.blockchain.shards.len();
+        let execution_shard_num = self.environment.blockchain.execution_shard_num();
+        let shards = || SlotColumns::Shards {
+            first_shard_id: 0,
+            shard_num,
+        };
+        let function_labels = ["non_switcher", "switcher"]
+            .iter()
+            .flat_map(|user_class| {
+                FunctionType::ALL
+                    .iter()
+                    .map(move |ftype| format!("{}.{}", user_class, ftype.name()))
+            })
+            .collect();
+        let transaction_type_labels = [
+            TransactionType::Legacy { gas_price: 0 },
+            TransactionType::Eip1559,
+            TransactionType::Sponsored { fee_payer: 0 },
+        ]
+        .iter()
+        .map(|transaction_type| transaction_type.name().to_string())
+        .collect();
+        let leg_progress_labels = || {
+            ["completed_num", "mean_latency", "mean_fee", "in_flight_num"]
+                .iter()
+                .map(|label| label.to_string())
+                .collect()
+        };
+        let slot_records: Vec<(&str, SlotRecord, SlotColumns)> = vec![
+            (
+                OUTPUT_BASE_FEE_CSV_FILENAME,
+                Self::slot_record_base_fee,
+                shards(),
+            ),
+            (
+                OUTPUT_ACTIVE_USER_NUM_CSV_FILENAME,
+                Self::slot_record_active_user_num,
+                shards(),
+            ),
+            (
+                OUTPUT_FUNCTION_NUM_CSV_FILENAME,
+                Self::slot_record_function_num,
+                SlotColumns::Labels(function_labels),
+            ),
+            (
+                OUTPUT_MEMPOOL_CSV_FILENAME,
+                Self::slot_record_mempool,
+                shards(),
+            ),
+            (
+                OUTPUT_GAS_USED_CSV_FILENAME,
+                Self::slot_record_gas_used,
+                shards(),
+            ),
+            (
+                OUTPUT_RECEIPT_OUT_BASE_FEE_CSV_FILENAME,
+                |sim, slot| {
+                    sim.slot_record_receipt_base_fee(slot, |state| state.receipt_out_base_fee)
+                },
+                shards(),
+            ),
+            (
+                OUTPUT_RECEIPT_IN_BASE_FEE_CSV_FILENAME,
+                |sim, slot| {
+                    sim.slot_record_receipt_base_fee(slot, |state| state.receipt_in_base_fee)
+                },
+                shards(),
+            ),
+            (
+                OUTPUT_GAS_TARGET_CSV_FILENAME,
+                Self::slot_record_gas_target,
+                shards(),
+            ),
+            (
+                OUTPUT_MISSED_SLOT_CSV_FILENAME,
+                Self::slot_record_missed_slot,
+                shards(),
+            ),
+            (
+                OUTPUT_PENDING_USER_NUM_CSV_FILENAME,
+                Self::slot_record_pending_user_num,
+                shards(),
+            ),
+            (
+                OUTPUT_IN_FLIGHT_RECEIPT_NUM_CSV_FILENAME,
+                Self::slot_record_in_flight_receipt_num,
+                shards(),
+            ),
+            (
+                OUTPUT_LOCKED_TRANSACTION_NUM_CSV_FILENAME,
+                Self::slot_record_locked_transaction_num,
+                shards(),
+            ),
+            (
+                OUTPUT_STATE_SIZE_CSV_FILENAME,
+                Self::slot_record_state_size,
+                shards(),
+            ),
+            (
+                OUTPUT_EVICTED_ACCOUNT_NUM_CSV_FILENAME,
+                Self::slot_record_evicted_account_num,
+                shards(),
+            ),
+            // One rollup per data shard, which follow the execution shards
+            (
+                OUTPUT_ROLLUP_PENDING_TRANSACTION_NUM_CSV_FILENAME,
+                Self::slot_record_rollup_pending_transaction_num,
+                SlotColumns::Shards {
+                    first_shard_id: execution_shard_num,
+                    shard_num: shard_num - execution_shard_num,
+                },
+            ),
+            (
+                OUTPUT_TRANSACTION_TYPE_NUM_CSV_FILENAME,
+                Self::slot_record_transaction_type_num,
+                SlotColumns::Labels(transaction_type_labels),
+            ),
+            (
+                OUTPUT_CROSS_TRANSFER_PROGRESS_CSV_FILENAME,
+                |sim, slot| {
+                    Self::slot_record_leg_progress(
+                        &sim.environment.cross_transfer_progress_mem[slot as usize],
+                    )
+                },
+                SlotColumns::Labels(leg_progress_labels()),
+            ),
+            (
+                OUTPUT_MIGRATION_PROGRESS_CSV_FILENAME,
+                |sim, slot| {
+                    Self::slot_record_leg_progress(
+                        &sim.environment.migration_progress_mem[slot as usize],
+                    )
+                },
+                SlotColumns::Labels(leg_progress_labels()),
+            ),
+        ];
+        let writes_csv = self.scenario.output_formats.contains(&OutputFormat::Csv);
+        slot_records
+            .into_iter()
+            .filter(|(filename, _, _)| self.scenario.selects_output(filename))
+            .filter_map(|(filename, record, columns)| {
+                match SlotSink::create(output_dir_path, filename, record, columns, writes_csv) {
+                    Ok(slot_sink) => Some(slot_sink),
+                    Err(e) => {
+                        eprintln!("Error: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Open the writers of the per-slot outputs in the tidy schema, one per format.
+    /// Their files are added to the outputs of the manifest.
+    fn create_tidy_writers(
+        &self,
+        output_dir_path: &Path,
+        manifest: &mut Manifest,
+    ) -> Vec<Box<dyn TidyWriter>> {
+        let mut tidy_writers = Vec::new();
+        for output_format in self.scenario.output_formats.iter() {
+            match output_format.create_tidy_writer(output_dir_path) {
+                Ok(Some(tidy_writer)) => {
+                    tidy_writers.push(tidy_writer);
+                    manifest.outputs.extend(
+                        output_format
+                            .filename()
+                            .map(|filename| filename.to_string()),
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Error: {:?}", e),
+            }
         }
-        if let Err(e) = self.output_csv_mempool(output_dir_path) {
-            eprintln!("Error: {:?}", e)
+        tidy_writers
+    }
+
+    /// Print a summary line every 1 / PROGRESS_LINE_NUM of the run, and at the last slot.
+    fn print_progress(&self, slot: Slot) {
+        let interval = std::cmp::max(self.duration / PROGRESS_LINE_NUM, 1);
+        if !(slot + 1).is_multiple_of(interval) && slot + 1 != self.duration {
+            return;
         }
+        let shards = &self.environment.blockchain.shards;
+        let execution_shards = &shards[..self.environment.blockchain.execution_shard_num()];
+        let mean_base_fee = execution_shards
+            .iter()
+            .map(|shard| shard.states[slot as usize].base_fee as f64)
+            .sum::<f64>()
+            / execution_shards.len() as f64
+            / 1_000_000_000.;
+        let mempool_tx_num: usize = self.environment.mempool_tx_mem[slot as usize].iter().sum();
+        let moving_account_num: usize =
+            shards.iter().map(|shard| shard.moving_accounts.len()).sum();
+        println!(
+            "slot {:>6}/{} ({:3}%): mean base fee {:.4} Gwei, mempool {}, moving accounts {}",
+            slot + 1,
+            self.duration,
+            (slot + 1) * 100 / self.duration,
+            mean_base_fee,
+            mempool_tx_num,
+            moving_account_num
+        );
+    }
+
+    fn slot_record_base_fee(&self, slot: Slot) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                self.environment.blockchain.shards[shard_id].states[slot as usize]
+                    .base_fee
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn slot_record_active_user_num(&self, slot: Slot) -> Vec<String> {
+        self.environment.user_num_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
     }
 
-    fn output_csv_base_fee(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
-        let file_path = output_dir_path.join(OUTPUT_BASE_FEE_CSV_FILENAME);
+    /// One row per user: the fees the user paid, as the total and as the base fee burned and the
+    /// tip of intra-shard, cross-shard and movement transactions, the numbers of transactions and
+    /// moves, the total inclusion delay, whether the user is honest and the slots spent per shard.
+    fn output_csv_users(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_USERS_CSV_FILENAME);
         let file = File::create(file_path)?;
         let mut wtr = csv::Writer::from_writer(file);
+        let mut header = [
+            "address",
+            "strategy",
+            "total_fee",
+            "intra_burned_fee",
+            "intra_tip",
+            "cross_burned_fee",
+            "cross_tip",
+            "move_burned_fee",
+            "move_tip",
+            "transaction_num",
+            "move_num",
+            "inclusion_delay",
+            "is_honest",
+        ]
+        .iter()
+        .map(|column| column.to_string())
+        .collect::<Vec<_>>();
+        header.extend(
+            (0..self.environment.blockchain.shards.len())
+                .map(|shard_id| format!("shard_{}_slots", shard_id)),
+        );
+        wtr.write_record(header)?;
+
+        // Intra-shard, cross-shard and movement transactions
+        let mut burned_fee = vec![[0; 3]; self.environment.users.len()];
+        let mut tip = vec![[0; 3]; self.environment.users.len()];
+        let mut transaction_num = vec![0; self.environment.users.len()];
+        let mut move_num = vec![0; self.environment.users.len()];
+        let mut inclusion_delay = vec![0; self.environment.users.len()];
+
         (0..self.duration).for_each(|slot| {
-            let record = (0..SHARD_NUM)
-                .map(|shard_id| {
-                    self.environment.blockchain.shards[shard_id].states[slot as usize]
-                        .base_fee
-                        .to_string()
-                })
-                .collect::<Vec<_>>();
+            self.environment.blockchain.shards.iter().for_each(|shard| {
+                shard.blocks[slot as usize]
+                    .executed_transactions
+                    .iter()
+                    .for_each(|transaction| {
+                        // Sequencers and relayers are not users
+                        let fee_payer = transaction.fee_payer();
+                        if fee_payer < burned_fee.len() {
+                            // A batch may mix kinds, so each function is booked by its own
+                            for (index, function) in transaction.functions.iter().enumerate() {
+                                let class = match TransactionKind::of(&function.ftype) {
+                                    TransactionKind::Intra => 0,
+                                    TransactionKind::CrossCreate | TransactionKind::CrossApply => 1,
+                                    TransactionKind::MoveOut | TransactionKind::MoveIn => 2,
+                                };
+                                let (burned, paid_tip) =
+                                    shard.function_fee(slot, transaction, index);
+                                burned_fee[fee_payer][class] += burned;
+                                tip[fee_payer][class] += paid_tip;
+                            }
+                        }
+                        if transaction.from >= transaction_num.len() {
+                            return;
+                        }
+                        transaction_num[transaction.from] += 1;
+                        if transaction.functions.iter().any(|function| {
+                            TransactionKind::of(&function.ftype) == TransactionKind::MoveOut
+                        }) {
+                            move_num[transaction.from] += 1;
+                        }
+                        if let Some(sent_slot) = self.environment.sent_slots.get(&transaction.hash)
+                        {
+                            inclusion_delay[transaction.from] += slot - sent_slot;
+                        }
+                    });
+            });
+        });
 
+        self.environment.users.iter().for_each(|user| {
+            let addr = user.account_addr;
+            let total_fee = burned_fee[addr].iter().sum::<Wei>() + tip[addr].iter().sum::<Wei>();
+            let mut record = vec![
+                addr.to_string(),
+                user.user_type.name().to_string(),
+                total_fee.to_string(),
+            ];
+            (0..3).for_each(|class| {
+                record.push(burned_fee[addr][class].to_string());
+                record.push(tip[addr][class].to_string());
+            });
+            record.extend([
+                transaction_num[addr].to_string(),
+                move_num[addr].to_string(),
+                inclusion_delay[addr].to_string(),
+                self.environment.is_honest_user(addr).to_string(),
+            ]);
+            record.extend(user.slots_in_shard.iter().map(|slots| slots.to_string()));
             if let Err(e) = wtr.write_record(record) {
                 eprintln!("Error: {:?}", e)
             };
@@ -75,18 +453,198 @@ impl Simulator {
         Ok(())
     }
 
-    fn output_csv_active_user_num(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
-        let file_path = output_dir_path.join(OUTPUT_ACTIVE_USER_NUM_CSV_FILENAME);
+    fn slot_record_function_num(&self, slot: Slot) -> Vec<String> {
+        let mut function_cnt = [0; FunctionType::NUM * 2];
+        self.environment.blockchain.shards.iter().for_each(|shard| {
+            shard.blocks[slot as usize]
+                .executed_transactions
+                .iter()
+                .for_each(|transaction| {
+                    // Sequencers are not users
+                    let is_switcher = self
+                        .environment
+                        .users
+                        .get(transaction.from)
+                        .is_some_and(|user| user.user_type.is_switcher());
+                    transaction.functions.iter().for_each(|function| {
+                        if is_switcher {
+                            function_cnt[function.ftype.clone() as usize + FunctionType::NUM] += 1;
+                        } else {
+                            function_cnt[function.ftype.clone() as usize] += 1;
+                        }
+                    });
+                });
+        });
+        function_cnt.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn slot_record_mempool(&self, slot: Slot) -> Vec<String> {
+        self.environment.mempool_tx_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn output_csv_adversary(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_ADVERSARY_CSV_FILENAME);
         let file = File::create(file_path)?;
         let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "shard",
+            "proposer_behavior",
+            "censored_transaction_num",
+            "stuffed_gas",
+            "stuffing_cost",
+        ])?;
 
-        (0..self.duration).for_each(|slot| {
-            let user_num = self.environment.user_num_mem[slot as usize]
+        self.environment.blockchain.shards.iter().for_each(|shard| {
+            let censored_transaction_num: usize = shard
+                .blocks
+                .iter()
+                .map(|block| block.censored_transaction_num)
+                .sum();
+            let stuffed_gas: Gas = shard.blocks.iter().map(|block| block.stuffed_gas).sum();
+            // The proposer burns the base fee of its own transactions
+            let stuffing_cost: Wei = shard
+                .blocks
+                .iter()
+                .enumerate()
+                .map(|(slot, block)| block.stuffed_gas * shard.states[slot].base_fee)
+                .sum();
+            let record = vec![
+                shard.id.to_string(),
+                shard.proposer_behavior.name().to_string(),
+                censored_transaction_num.to_string(),
+                stuffed_gas.to_string(),
+                stuffing_cost.to_string(),
+            ];
+            if let Err(e) = wtr.write_record(record) {
+                eprintln!("Error: {:?}", e)
+            };
+        });
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn slot_record_gas_used(&self, slot: Slot) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                self.environment.blockchain.shards[shard_id].blocks[slot as usize]
+                    .gas_used
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn slot_record_receipt_base_fee(
+        &self,
+        slot: Slot,
+        receipt_base_fee: fn(&ShardState) -> GasPrice,
+    ) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                receipt_base_fee(
+                    &self.environment.blockchain.shards[shard_id].states[slot as usize],
+                )
+                .to_string()
+            })
+            .collect()
+    }
+
+    fn slot_record_gas_target(&self, slot: Slot) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                self.environment.blockchain.shards[shard_id].states[slot as usize]
+                    .gas_target
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn slot_record_missed_slot(&self, slot: Slot) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                let block = &self.environment.blockchain.shards[shard_id].blocks[slot as usize];
+                (!block.proposed as usize).to_string()
+            })
+            .collect()
+    }
+
+    fn slot_record_pending_user_num(&self, slot: Slot) -> Vec<String> {
+        self.environment.pending_user_num_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn slot_record_state_size(&self, slot: Slot) -> Vec<String> {
+        self.environment.state_size_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn slot_record_evicted_account_num(&self, slot: Slot) -> Vec<String> {
+        self.environment.evicted_account_num_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn slot_record_in_flight_receipt_num(&self, slot: Slot) -> Vec<String> {
+        self.environment.in_flight_receipt_num_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One row per cross-shard call: the slots in which each leg is included, the round-trip
+    /// latency and the base fee paid over all legs.
+    fn output_csv_cross_call(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_CROSS_CALL_CSV_FILENAME);
+        let file = File::create(file_path)?;
+        let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "from",
+            "origin_shard",
+            "contract_shard",
+            "created_slot",
+            "call_slot",
+            "apply_slot",
+            "callback_slot",
+            "latency",
+            "fee",
+        ])?;
+
+        let executed = self.executed_transaction_slots_and_fees();
+
+        self.environment.cross_calls.iter().for_each(|call| {
+            let legs = call
+                .leg_hashes
                 .iter()
-                .map(|x| x.to_string())
+                .filter_map(|hash| executed.get(hash))
                 .collect::<Vec<_>>();
+            let mut record = vec![
+                call.from.to_string(),
+                call.origin_shard_id.to_string(),
+                call.contract_shard_id.to_string(),
+                call.created_slot.to_string(),
+            ];
+            (0..3).for_each(|i| {
+                record.push(
+                    legs.get(i)
+                        .map_or("".to_string(), |(slot, _)| slot.to_string()),
+                )
+            });
+            record.push(if legs.len() == 3 {
+                (legs[2].0 - call.created_slot).to_string()
+            } else {
+                "".to_string()
+            });
+            record.push(legs.iter().map(|(_, fee)| fee).sum::<Wei>().to_string());
 
-            if let Err(e) = wtr.write_record(user_num) {
+            if let Err(e) = wtr.write_record(record) {
                 eprintln!("Error: {:?}", e)
             };
         });
@@ -95,36 +653,128 @@ impl Simulator {
         Ok(())
     }
 
-    fn output_csv_users(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
-        let file_path = output_dir_path.join(OUTPUT_USERS_CSV_FILENAME);
+    /// One row per cross-shard transfer: the slot in which its first leg was included, the number of
+    /// legs included, the slot in which the transfer completed, aborted, failed or was refunded,
+    /// the latency, the slots it spent between legs, the outcome, and the base fee and tip paid.
+    fn output_csv_cross_transfer(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_CROSS_TRANSFER_CSV_FILENAME);
         let file = File::create(file_path)?;
         let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "from",
+            "mode",
+            "source_shard",
+            "destination_shard",
+            "created_slot",
+            "first_leg_slot",
+            "included_leg_num",
+            "completed_slot",
+            "latency",
+            "in_flight_slots",
+            "outcome",
+            "fee",
+            "tip",
+        ])?;
 
-        let mut total_fee = vec![0; self.environment.users.len()];
-        let mut transaction_num = vec![0; self.environment.users.len()];
+        let executed = self.executed_transaction_slots_and_fees();
 
-        (0..self.duration).for_each(|slot| {
-            self.environment.blockchain.shards.iter().for_each(|shard| {
-                let base_fee = shard.states[slot as usize].base_fee;
-                shard.blocks[slot as usize]
-                    .executed_transactions
+        self.environment
+            .cross_transfers
+            .iter()
+            .for_each(|cross_transfer| {
+                let legs = cross_transfer
+                    .leg_hashes
                     .iter()
-                    .for_each(|transaction| {
-                        total_fee[transaction.from] +=
-                            base_fee * transaction.functions.first().unwrap().gas();
-                        transaction_num[transaction.from] += 1;
-                    });
+                    .filter(|hash| executed.contains_key(hash))
+                    .map(|hash| self.environment.blockchain.get_receipt(*hash).unwrap())
+                    .collect::<Vec<_>>();
+                let (outcome, completed_slot) = cross_transfer.outcome(&legs);
+                let first_leg_slot = executed
+                    .get(&cross_transfer.leg_hashes[0])
+                    .map(|&(slot, _)| slot);
+
+                let record = vec![
+                    cross_transfer.from.to_string(),
+                    cross_transfer.mode.name().to_string(),
+                    cross_transfer.source_shard_id.to_string(),
+                    cross_transfer.destination_shard_id.to_string(),
+                    cross_transfer.created_slot.to_string(),
+                    first_leg_slot.map_or("".to_string(), |slot| slot.to_string()),
+                    legs.len().to_string(),
+                    completed_slot.map_or("".to_string(), |slot| slot.to_string()),
+                    completed_slot.map_or("".to_string(), |slot| {
+                        (slot - cross_transfer.created_slot).to_string()
+                    }),
+                    self.in_flight_slots(first_leg_slot, completed_slot),
+                    outcome.name().to_string(),
+                    cross_transfer.burned_fee.to_string(),
+                    cross_transfer.tip.to_string(),
+                ];
+                if let Err(e) = wtr.write_record(record) {
+                    eprintln!("Error: {:?}", e)
+                };
             });
-        });
 
-        self.environment.users.iter().for_each(|user| {
-            let user = vec![
-                user.account_addr.to_string(),
-                (user.user_type as usize).to_string(),
-                total_fee[user.account_addr].to_string(),
-                transaction_num[user.account_addr].to_string()
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// One row per account move: the slot in which it left the source shard, the number of legs
+    /// included, the slot in which the account arrived on the target shard or the move failed,
+    /// the latency, the slots the account spent moving, the outcome, and the base fee and tip.
+    fn output_csv_migration(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_MIGRATION_CSV_FILENAME);
+        let file = File::create(file_path)?;
+        let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "from",
+            "mechanism",
+            "source_shard",
+            "target_shard",
+            "created_slot",
+            "first_leg_slot",
+            "included_leg_num",
+            "completed_slot",
+            "latency",
+            "in_flight_slots",
+            "outcome",
+            "fee",
+            "tip",
+        ])?;
+
+        let executed = self.executed_transaction_slots_and_fees();
+
+        self.environment.migrations.iter().for_each(|migration| {
+            let legs = migration
+                .leg_hashes
+                .iter()
+                .filter(|hash| executed.contains_key(hash))
+                .map(|hash| self.environment.blockchain.get_receipt(*hash).unwrap())
+                .collect::<Vec<_>>();
+
+            let (outcome, completed_slot) = migration.outcome(&legs);
+            let first_leg_slot = executed
+                .get(&migration.leg_hashes[0])
+                .map(|&(slot, _)| slot);
+
+            let record = vec![
+                migration.from.to_string(),
+                migration.mechanism.name().to_string(),
+                migration.source_shard_id.to_string(),
+                migration.target_shard_id.to_string(),
+                migration.created_slot.to_string(),
+                first_leg_slot.map_or("".to_string(), |slot| slot.to_string()),
+                legs.len().to_string(),
+                completed_slot.map_or("".to_string(), |slot| slot.to_string()),
+                completed_slot.map_or("".to_string(), |slot| {
+                    (slot - migration.created_slot).to_string()
+                }),
+                self.in_flight_slots(first_leg_slot, completed_slot),
+                outcome.name().to_string(),
+                migration.burned_fee.to_string(),
+                migration.tip.to_string(),
             ];
-            if let Err(e) = wtr.write_record(user) {
+            if let Err(e) = wtr.write_record(record) {
                 eprintln!("Error: {:?}", e)
             };
         });
@@ -133,33 +783,70 @@ impl Simulator {
         Ok(())
     }
 
-    fn output_csv_function_num(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
-        let file_path = output_dir_path.join(OUTPUT_FUNCTION_NUM_CSV_FILENAME);
+    /// The slots between the inclusion of the first leg and the end of a transfer or move,
+    /// or the last slot of the run if it has not ended. Empty if the first leg is not included.
+    fn in_flight_slots(&self, first_leg_slot: Option<Slot>, ended_slot: Option<Slot>) -> String {
+        first_leg_slot.map_or("".to_string(), |first_leg_slot| {
+            (ended_slot.unwrap_or(self.duration - 1) - first_leg_slot).to_string()
+        })
+    }
+
+    fn slot_record_locked_transaction_num(&self, slot: Slot) -> Vec<String> {
+        (0..self.environment.blockchain.shards.len())
+            .map(|shard_id| {
+                self.environment.blockchain.shards[shard_id].blocks[slot as usize]
+                    .locked_transaction_num
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn output_csv_rollup_batch(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_ROLLUP_BATCH_CSV_FILENAME);
         let file = File::create(file_path)?;
         let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "rollup",
+            "data_shard",
+            "posted_slot",
+            "included_slot",
+            "transaction_num",
+            "size",
+            "latency",
+            "outcome",
+            "fee",
+        ])?;
 
-        (0..self.duration).for_each(|slot| {
-            let mut function_cnt = vec![0; 10];
+        let executed = self.executed_transaction_slots_and_fees();
 
-            self.environment.blockchain.shards.iter().for_each(|shard| {
-                shard.blocks[slot as usize]
-                    .executed_transactions
+        self.environment.rollup_batches.iter().for_each(|batch| {
+            let included = executed.get(&batch.hash);
+            let outcome = match self.environment.blockchain.get_receipt(batch.hash) {
+                Some(receipt) if receipt.status => "completed",
+                Some(_) => "failed",
+                None => "pending",
+            };
+            // Average latency of the rollup transactions until their data is on-chain
+            let latency = included.map(|&(slot, _)| {
+                batch
+                    .created_slots
                     .iter()
-                    .for_each(|transaction| {
-                        let user = &self.environment.users[transaction.from];
-                        transaction.functions.iter().for_each(|function| {
-                            if user.user_type.is_switcher() {
-                                function_cnt[function.ftype.clone() as usize + 5] += 1;
-                            } else {
-                                function_cnt[function.ftype.clone() as usize] += 1;
-                            }
-                        });
-                    });
+                    .map(|created_slot| (slot - created_slot) as f64)
+                    .sum::<f64>()
+                    / batch.created_slots.len() as f64
             });
-            let record = function_cnt
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<_>>();
+
+            let record = vec![
+                batch.rollup_id.to_string(),
+                batch.data_shard_id.to_string(),
+                batch.posted_slot.to_string(),
+                included.map_or("".to_string(), |(slot, _)| slot.to_string()),
+                batch.created_slots.len().to_string(),
+                batch.size.to_string(),
+                latency.map_or("".to_string(), |latency| latency.to_string()),
+                outcome.to_string(),
+                included.map_or(0, |&(_, fee)| fee).to_string(),
+            ];
             if let Err(e) = wtr.write_record(record) {
                 eprintln!("Error: {:?}", e)
             };
@@ -169,23 +856,484 @@ impl Simulator {
         Ok(())
     }
 
-    fn output_csv_mempool(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
-        let file_path = output_dir_path.join(OUTPUT_MEMPOOL_CSV_FILENAME);
+    fn slot_record_rollup_pending_transaction_num(&self, slot: Slot) -> Vec<String> {
+        self.environment.rollup_pending_transaction_num_mem[slot as usize]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn slot_record_transaction_type_num(&self, slot: Slot) -> Vec<String> {
+        // Legacy, EIP-1559 and sponsored
+        let mut transaction_type_cnt = [0; 3];
+        self.environment.blockchain.shards.iter().for_each(|shard| {
+            shard.blocks[slot as usize]
+                .executed_transactions
+                .iter()
+                .for_each(|transaction| match transaction.transaction_type {
+                    TransactionType::Legacy { .. } => transaction_type_cnt[0] += 1,
+                    TransactionType::Eip1559 => transaction_type_cnt[1] += 1,
+                    TransactionType::Sponsored { .. } => transaction_type_cnt[2] += 1,
+                });
+        });
+        transaction_type_cnt.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// The number of transfers or moves completed in the slot, their mean latency and mean fee
+    /// (0 if none completed), and the number between legs.
+    fn slot_record_leg_progress(progress: &LegProgress) -> Vec<String> {
+        let mean = |sum: f64| {
+            if progress.completed_num == 0 {
+                0.
+            } else {
+                sum / progress.completed_num as f64
+            }
+        };
+        vec![
+            progress.completed_num.to_string(),
+            mean(progress.latency as f64).to_string(),
+            mean(progress.fee as f64).to_string(),
+            progress.in_flight_num.to_string(),
+        ]
+    }
+
+    /// One row per relayer: the execution shard it relays on, the number of transactions it
+    /// sponsored there, and the base fee burned and the tip it paid for them.
+    fn output_csv_relayer(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_RELAYER_CSV_FILENAME);
         let file = File::create(file_path)?;
         let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "address",
+            "shard",
+            "sponsored_transaction_num",
+            "burned_fee",
+            "tip",
+        ])?;
 
+        // The relayers are indexed by the id of their execution shard
+        self.environment
+            .relayers
+            .iter()
+            .zip(self.environment.blockchain.shards.iter())
+            .for_each(|(&relayer, shard)| {
+                let mut sponsored_transaction_num = 0;
+                let mut burned_fee = 0;
+                let mut tip = 0;
+                for (slot, block) in shard.blocks.iter().enumerate() {
+                    for transaction in block
+                        .executed_transactions
+                        .iter()
+                        .filter(|transaction| transaction.fee_payer() == relayer)
+                    {
+                        sponsored_transaction_num += 1;
+                        for index in 0..transaction.functions.len() {
+                            let (burned, paid_tip) =
+                                shard.function_fee(slot as Slot, transaction, index);
+                            burned_fee += burned;
+                            tip += paid_tip;
+                        }
+                    }
+                }
+                let record = vec![
+                    relayer.to_string(),
+                    shard.id.to_string(),
+                    sponsored_transaction_num.to_string(),
+                    burned_fee.to_string(),
+                    tip.to_string(),
+                ];
+                if let Err(e) = wtr.write_record(record) {
+                    eprintln!("Error: {:?}", e)
+                };
+            });
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// One row per function of each transaction sent: the shards it goes between, the slots in
+    /// which it was created and included, the gas price and fee paid, and whether it was included,
+    /// failed, skipped because of a nonce gap or dropped from the mempool.
+    fn output_csv_transactions(&self, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_TRANSACTIONS_CSV_FILENAME);
+        let file = File::create(file_path)?;
+        let mut wtr = csv::Writer::from_writer(file);
+        wtr.write_record([
+            "hash",
+            "transaction_hash",
+            "creator",
+            "kind",
+            "source_shard",
+            "destination_shard",
+            "created_slot",
+            "included_slot",
+            "effective_gas_price",
+            "fee",
+            "outcome",
+        ])?;
+
+        let mut included = HashMap::new();
+        let mut skipped = HashSet::new();
+        let mut dropped = HashSet::new();
+        (0..self.duration).for_each(|slot| {
+            self.environment.blockchain.shards.iter().for_each(|shard| {
+                let block = &shard.blocks[slot as usize];
+                block.executed_transactions.iter().for_each(|transaction| {
+                    included.insert(transaction.hash, (slot, shard, transaction));
+                });
+                skipped.extend(block.skipped_transactions.iter().copied());
+                dropped.extend(block.dropped_transactions.iter().copied());
+            });
+        });
+
+        self.environment
+            .transaction_traces
+            .iter()
+            .for_each(|trace| {
+                let mut record = vec![
+                    trace.hash.to_string(),
+                    trace.transaction_hash.to_string(),
+                    trace.creator.to_string(),
+                    trace.kind.name().to_string(),
+                    trace.source_shard_id.to_string(),
+                    trace.destination_shard_id.to_string(),
+                    trace.created_slot.to_string(),
+                ];
+                match included.get(&trace.transaction_hash) {
+                    Some(&(slot, shard, transaction)) => {
+                        let (burned, tip) = shard.function_fee(slot, transaction, trace.index);
+                        let status = shard
+                            .receipts
+                            .get(&trace.hash)
+                            .is_some_and(|receipt| receipt.status);
+                        record.extend([
+                            slot.to_string(),
+                            transaction
+                                .effective_gas_price(shard.states[slot as usize].base_fee)
+                                .to_string(),
+                            (burned + tip).to_string(),
+                            if status { "included" } else { "failed" }.to_string(),
+                        ]);
+                    }
+                    None => {
+                        // A skipped transaction stays in the mempool, so it may be dropped later
+                        let outcome = if dropped.contains(&trace.transaction_hash) {
+                            "dropped"
+                        } else if skipped.contains(&trace.transaction_hash) {
+                            "skipped"
+                        } else {
+                            "pending"
+                        };
+                        record.extend([
+                            "".to_string(),
+                            "".to_string(),
+                            "0".to_string(),
+                            outcome.to_string(),
+                        ]);
+                    }
+                }
+                if let Err(e) = wtr.write_record(record) {
+                    eprintln!("Error: {:?}", e)
+                };
+            });
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Summarize the run over the execution shards.
+    pub fn summary(&self) -> RunSummary {
+        let execution_shards = &self.environment.blockchain.shards
+            [..self.environment.blockchain.execution_shard_num()];
+        let mut summary = RunSummary::default();
+        let mut gas_used: Gas = 0;
+        let mut gas_target: Gas = 0;
         (0..self.duration).for_each(|slot| {
-            let mempool_tx_num = self.environment.mempool_tx_mem[slot as usize]
+            let base_fees = execution_shards
                 .iter()
-                .map(|x| x.to_string())
+                .map(|shard| shard.states[slot as usize].base_fee as f64 / 1_000_000_000.)
                 .collect::<Vec<_>>();
+            let mean = base_fees.iter().sum::<f64>() / base_fees.len() as f64;
+            let variance = base_fees
+                .iter()
+                .map(|base_fee| (base_fee - mean).powi(2))
+                .sum::<f64>()
+                / base_fees.len() as f64;
+            summary.mean_base_fee += mean / self.duration as f64;
+            summary.base_fee_std += variance.sqrt() / self.duration as f64;
 
-            if let Err(e) = wtr.write_record(mempool_tx_num) {
-                eprintln!("Error: {:?}", e)
-            };
+            execution_shards.iter().for_each(|shard| {
+                let block = &shard.blocks[slot as usize];
+                gas_used += block.gas_used;
+                gas_target += shard.states[slot as usize].gas_target;
+                summary.executed_transaction_num += block.executed_transactions.len();
+            });
         });
+        if gas_target > 0 {
+            summary.gas_utilization = gas_used as f64 / gas_target as f64;
+        }
+        summary.migration_num = self.environment.migrations.len();
+        summary.cross_transfer_num = self.environment.cross_transfers.len();
+        summary
+    }
 
-        wtr.flush()?;
-        Ok(())
+    /// Inclusion slot and base fee cost of each executed function, by the hash of its receipt.
+    /// The receipt of a single-function transaction is the receipt of the transaction.
+    fn executed_transaction_slots_and_fees(&self) -> HashMap<TransactionHash, (Slot, Wei)> {
+        let mut executed = HashMap::new();
+        (0..self.duration).for_each(|slot| {
+            self.environment.blockchain.shards.iter().for_each(|shard| {
+                shard.blocks[slot as usize]
+                    .executed_transactions
+                    .iter()
+                    .for_each(|transaction| {
+                        (0..transaction.functions.len()).for_each(|index| {
+                            let (fee, _) = shard.function_fee(slot, transaction, index);
+                            executed.insert(transaction.function_hash(index), (slot, fee));
+                        });
+                    });
+            });
+        });
+        executed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a small scenario into its own directory under the temporary directory.
+    fn run(name: &str, scenario: Scenario) -> (Simulator, PathBuf) {
+        let output_dir_path = std::env::temp_dir().join(name);
+        let scenario = Scenario {
+            output_dir_path: output_dir_path.to_string_lossy().into_owned(),
+            ..scenario
+        };
+        let mut sim = Simulator::new(scenario);
+        sim.set_log_level(LogLevel::Error);
+        sim.run();
+        (sim, output_dir_path)
+    }
+
+    fn small_scenario() -> Scenario {
+        Scenario {
+            end_slot: 5,
+            user_num: 100,
+            shard_num: 2,
+            ..Scenario::default()
+        }
+    }
+
+    fn read_csv(file_path: &Path) -> Vec<csv::StringRecord> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(file_path)
+            .unwrap();
+        rdr.records().map(|record| record.unwrap()).collect()
+    }
+
+    #[test]
+    fn every_output_is_listed_in_the_manifest_and_has_a_header() {
+        let (_, output_dir_path) = run("simulator_test_headers", small_scenario());
+        let contents =
+            std::fs::read_to_string(output_dir_path.join(OUTPUT_MANIFEST_FILENAME)).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert!(manifest["finished_at"].is_string());
+
+        let outputs = manifest["outputs"].as_array().unwrap();
+        assert!(outputs.contains(&serde_json::json!(OUTPUT_SCENARIO_FILENAME)));
+        assert!(outputs.contains(&serde_json::json!(OUTPUT_USERS_CSV_FILENAME)));
+        for output in outputs {
+            let filename = output.as_str().unwrap();
+            assert!(output_dir_path.join(filename).exists(), "{}", filename);
+            if filename.ends_with(".csv") {
+                let records = read_csv(&output_dir_path.join(filename));
+                // No column is named by a number
+                assert!(
+                    records[0].iter().all(|name| name.parse::<f64>().is_err()),
+                    "{}",
+                    filename
+                );
+            }
+        }
+
+        let base_fee = read_csv(&output_dir_path.join(OUTPUT_BASE_FEE_CSV_FILENAME));
+        assert_eq!(&base_fee[0], vec!["slot", "shard_0", "shard_1"]);
+        assert_eq!(base_fee.len(), 1 + 5);
+        let function_num = read_csv(&output_dir_path.join(OUTPUT_FUNCTION_NUM_CSV_FILENAME));
+        assert_eq!(function_num[0].len(), 1 + 2 * FunctionType::ALL.len());
+        assert!(function_num[0]
+            .iter()
+            .any(|name| name == format!("switcher.{}", FunctionType::Transfer.name())));
+
+        // Strategies are written by their names
+        let users = read_csv(&output_dir_path.join(OUTPUT_USERS_CSV_FILENAME));
+        assert!(users.len() > 1);
+        let strategy_column = users[0].iter().position(|name| name == "strategy").unwrap();
+        assert!(users[1..].iter().all(|user| [
+            StrategyType::NonSwitcher,
+            StrategyType::WeightedRandom,
+            StrategyType::Minimum,
+            StrategyType::DecreasingMinimum,
+        ]
+        .iter()
+        .any(|strategy| strategy.name() == &user[strategy_column])));
+        std::fs::remove_dir_all(output_dir_path).unwrap();
+    }
+
+    fn transfer(from: Address, to: Address, gas_premium: GasPrice, nonce: Nonce) -> Transaction {
+        Transaction::new(
+            from,
+            to,
+            0,
+            vec![Function {
+                source: from,
+                target: to,
+                ftype: FunctionType::Transfer,
+                calldata: "".to_string(),
+            }],
+            gas_premium,
+            INITIAL_BASE_FEE * 10,
+            nonce,
+        )
+    }
+
+    fn trace(transaction: &Transaction) -> TransactionTrace {
+        TransactionTrace {
+            hash: transaction.function_hash(0),
+            transaction_hash: transaction.hash,
+            index: 0,
+            creator: transaction.from,
+            kind: TransactionKind::Intra,
+            source_shard_id: 0,
+            destination_shard_id: 0,
+            created_slot: 0,
+        }
+    }
+
+    #[test]
+    fn trace_tells_included_failed_skipped_dropped_and_pending_transactions_apart() {
+        let mut sim = Simulator::new(Scenario {
+            end_slot: 1,
+            ..Scenario::default()
+        });
+        let mut shard = Shard::new(0);
+        for addr in 0..5 {
+            shard.accounts.insert(addr, Account::new(addr, 0));
+        }
+
+        let included = transfer(0, 1, 0, 0);
+        // Nobody else pays for a transaction sponsored by its sender
+        let failed = transfer(1, 0, 0, 0).into_sponsored(1);
+        // Nonce gaps keep the rest in the mempool, which is full of them
+        let skipped = transfer(2, 0, 1, 1);
+        let dropped = transfer(3, 0, 0, 1);
+        let pending = transfer(4, 0, 0, 0);
+        for transaction in [&included, &failed, &skipped, &dropped] {
+            shard.push_transaction(transaction.clone(), None);
+        }
+        for nonce in 2..=MEMPOOL_TRANSACTION_NUM as Nonce {
+            shard.push_transaction(transfer(2, 0, 1, nonce), None);
+        }
+        shard.process_slot();
+        sim.environment.blockchain.shards = vec![shard];
+        sim.environment.transaction_traces = [&included, &failed, &skipped, &dropped, &pending]
+            .iter()
+            .copied()
+            .map(trace)
+            .collect();
+
+        let output_dir_path = std::env::temp_dir().join("simulator_test_trace");
+        std::fs::create_dir_all(&output_dir_path).unwrap();
+        sim.output_csv_transactions(&output_dir_path).unwrap();
+        let records = read_csv(&output_dir_path.join(OUTPUT_TRANSACTIONS_CSV_FILENAME));
+        let column = |name: &str| records[0].iter().position(|column| column == name).unwrap();
+        let (included_slot, fee, outcome) =
+            (column("included_slot"), column("fee"), column("outcome"));
+        let rows = records[1..]
+            .iter()
+            .map(|record| {
+                (
+                    record[outcome].to_string(),
+                    record[included_slot].to_string(),
+                    record[fee].parse::<Wei>().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].0, "included");
+        assert_eq!(rows[0].1, "0");
+        assert_eq!(rows[0].2, INITIAL_BASE_FEE * GAS_TRANSFER as Wei);
+        assert_eq!(rows[1], ("failed".to_string(), "0".to_string(), 0));
+        for (row, outcome) in rows[2..].iter().zip(["skipped", "dropped", "pending"]) {
+            assert_eq!(row, &(outcome.to_string(), "".to_string(), 0));
+        }
+        std::fs::remove_dir_all(output_dir_path).unwrap();
+    }
+
+    #[test]
+    fn users_pay_the_burned_fee_and_the_tip_of_each_class_they_pay_for() {
+        let mut sim = Simulator::new(Scenario {
+            end_slot: 1,
+            ..Scenario::default()
+        });
+        let mut shard = Shard::new(0);
+        for addr in 0..3 {
+            shard.accounts.insert(addr, Account::new(addr, 0));
+            sim.environment
+                .users
+                .push(User::new(addr, StrategyType::NonSwitcher, 1));
+        }
+
+        // A batch of an intra-shard transfer and the start of a cross-shard transfer
+        let mut batch = transfer(0, 1, 1, 0);
+        batch.functions.push(Function {
+            source: 0,
+            target: 0,
+            ftype: FunctionType::CreateCrossTransfer,
+            calldata: "".to_string(),
+        });
+        // A transfer of user 1 paid for by user 2
+        let sponsored = transfer(1, 0, 1, 0).into_sponsored(2);
+        shard.push_transaction(batch.clone(), None);
+        shard.push_transaction(sponsored, None);
+        shard.process_slot();
+        sim.environment.blockchain.shards = vec![shard];
+
+        let output_dir_path = std::env::temp_dir().join("simulator_test_users");
+        std::fs::create_dir_all(&output_dir_path).unwrap();
+        sim.output_csv_users(&output_dir_path).unwrap();
+        let records = read_csv(&output_dir_path.join(OUTPUT_USERS_CSV_FILENAME));
+        let value = |addr: usize, name: &str| -> Wei {
+            let column = records[0].iter().position(|column| column == name).unwrap();
+            records[1 + addr][column].parse().unwrap()
+        };
+
+        let intra_gas = batch.function_gas(0) as Wei;
+        let cross_gas = batch.function_gas(1) as Wei;
+        assert_eq!(value(0, "intra_burned_fee"), INITIAL_BASE_FEE * intra_gas);
+        assert_eq!(value(0, "intra_tip"), intra_gas);
+        assert_eq!(value(0, "cross_burned_fee"), INITIAL_BASE_FEE * cross_gas);
+        assert_eq!(value(0, "cross_tip"), cross_gas);
+        assert_eq!(value(0, "move_burned_fee"), 0);
+        assert_eq!(
+            value(0, "total_fee"),
+            (INITIAL_BASE_FEE + 1) * (intra_gas + cross_gas)
+        );
+        assert_eq!(value(0, "transaction_num"), 1);
+
+        // The sender of a sponsored transaction pays nothing, and the fee payer all of it
+        assert_eq!(value(1, "total_fee"), 0);
+        assert_eq!(value(1, "transaction_num"), 1);
+        let transfer_gas = GAS_TRANSFER as Wei;
+        assert_eq!(
+            value(2, "intra_burned_fee"),
+            INITIAL_BASE_FEE * transfer_gas
+        );
+        assert_eq!(value(2, "intra_tip"), transfer_gas);
+        assert_eq!(value(2, "cross_burned_fee"), 0);
+        assert_eq!(value(2, "transaction_num"), 0);
+        std::fs::remove_dir_all(output_dir_path).unwrap();
     }
 }
@@ -0,0 +1,61 @@
+/// What the columns of an output with one record per slot stand for,
+/// which names them in the CSV header and in the tidy schema.
+pub enum SlotColumns {
+    /// One column per shard, from the shard of the first column.
+    Shards {
+        first_shard_id: usize,
+        shard_num: usize,
+    },
+    /// One column per label, summed over shards.
+    Labels(Vec<String>),
+}
+
+impl SlotColumns {
+    /// The names of the columns in the CSV header.
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            SlotColumns::Shards {
+                first_shard_id,
+                shard_num,
+            } => (*first_shard_id..first_shard_id + shard_num)
+                .map(|shard_id| format!("shard_{}", shard_id))
+                .collect(),
+            SlotColumns::Labels(labels) => labels.clone(),
+        }
+    }
+
+    /// The shard and the metric of a column of the output.
+    pub fn key(&self, metric: &str, column: usize) -> (Option<usize>, String) {
+        match self {
+            SlotColumns::Shards { first_shard_id, .. } => {
+                (Some(first_shard_id + column), metric.to_string())
+            }
+            SlotColumns::Labels(labels) => (None, format!("{}.{}", metric, labels[column])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_columns_are_named_and_keyed_by_shard() {
+        let columns = SlotColumns::Shards {
+            first_shard_id: 2,
+            shard_num: 3,
+        };
+        assert_eq!(columns.names(), vec!["shard_2", "shard_3", "shard_4"]);
+        assert_eq!(
+            columns.key("base_fee", 1),
+            (Some(3), "base_fee".to_string())
+        );
+    }
+
+    #[test]
+    fn label_columns_are_named_and_keyed_by_label() {
+        let columns = SlotColumns::Labels(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(columns.names(), vec!["a", "b"]);
+        assert_eq!(columns.key("metric", 1), (None, "metric.b".to_string()));
+    }
+}
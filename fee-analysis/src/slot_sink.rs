@@ -0,0 +1,157 @@
+use crate::*;
+
+/// Computes the record of a slot of an output.
+pub type SlotRecord = fn(&Simulator, Slot) -> Vec<String>;
+
+/// An output with one record per slot, appended as soon as the slot is processed,
+/// so that a run that stops halfway keeps the slots so far.
+pub struct SlotSink {
+    filename: String,
+    /// The filename stem, which is the metric in the tidy schema.
+    metric: String,
+    record: SlotRecord,
+    columns: SlotColumns,
+    /// None if the CSV format is not selected.
+    wtr: Option<csv::Writer<File>>,
+}
+
+impl SlotSink {
+    pub fn create(
+        output_dir_path: &Path,
+        filename: &str,
+        record: SlotRecord,
+        columns: SlotColumns,
+        writes_csv: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let wtr = if writes_csv {
+            let file = File::create(output_dir_path.join(filename))?;
+            let mut wtr = csv::Writer::from_writer(file);
+            let mut header = vec!["slot".to_string()];
+            header.extend(columns.names());
+            wtr.write_record(header)?;
+            Some(wtr)
+        } else {
+            None
+        };
+        let metric = Path::new(filename)
+            .file_stem()
+            .map_or(filename.to_string(), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+        Ok(Self {
+            filename: filename.to_string(),
+            metric,
+            record,
+            columns,
+            wtr,
+        })
+    }
+
+    /// The filename of the CSV file, if it is written.
+    pub fn csv_filename(&self) -> Option<&str> {
+        self.wtr.as_ref().map(|_| self.filename.as_str())
+    }
+
+    /// Write the record of the slot into the CSV file, and add it in the tidy schema to
+    /// the tidy records of the slot if they are collected.
+    /// Values that are not numbers, e.g. empty ones, are left out of the tidy records.
+    pub fn append(
+        &mut self,
+        sim: &Simulator,
+        slot: Slot,
+        tidy_records: Option<&mut Vec<TidyRecord>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let record = (self.record)(sim, slot);
+        if let Some(wtr) = self.wtr.as_mut() {
+            wtr.write_field(slot.to_string())?;
+            wtr.write_record(&record)?;
+            wtr.flush()?;
+        }
+        if let Some(tidy_records) = tidy_records {
+            for (column, value) in record.iter().enumerate() {
+                if let Ok(value) = value.parse() {
+                    let (shard, metric) = self.columns.key(&self.metric, column);
+                    tidy_records.push(TidyRecord {
+                        slot,
+                        shard,
+                        metric,
+                        value,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(_: &Simulator, slot: Slot) -> Vec<String> {
+        vec![(slot * 10).to_string(), "".to_string()]
+    }
+
+    fn output_dir_path(name: &str) -> PathBuf {
+        let output_dir_path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&output_dir_path).unwrap();
+        output_dir_path
+    }
+
+    #[test]
+    fn appends_each_slot_to_the_csv_file_as_it_comes() {
+        let output_dir_path = output_dir_path("slot_sink_test_csv");
+        let sim = Simulator::new(Scenario::default());
+        let mut sink = SlotSink::create(
+            &output_dir_path,
+            "metric.csv",
+            record,
+            SlotColumns::Shards {
+                first_shard_id: 2,
+                shard_num: 2,
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(sink.csv_filename(), Some("metric.csv"));
+        let file_path = output_dir_path.join("metric.csv");
+
+        sink.append(&sim, 0, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "slot,shard_2,shard_3\n0,0,\n"
+        );
+        sink.append(&sim, 1, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "slot,shard_2,shard_3\n0,0,\n1,10,\n"
+        );
+        std::fs::remove_dir_all(output_dir_path).unwrap();
+    }
+
+    #[test]
+    fn collects_the_numbers_as_tidy_records_without_a_csv_file() {
+        let output_dir_path = output_dir_path("slot_sink_test_tidy");
+        let sim = Simulator::new(Scenario::default());
+        let mut sink = SlotSink::create(
+            &output_dir_path,
+            "metric.csv",
+            record,
+            SlotColumns::Labels(vec!["a".to_string(), "b".to_string()]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(sink.csv_filename(), None);
+
+        let mut tidy_records = Vec::new();
+        sink.append(&sim, 3, Some(&mut tidy_records)).unwrap();
+        // The empty value of the second column is left out
+        assert_eq!(tidy_records.len(), 1);
+        assert_eq!(tidy_records[0].slot, 3);
+        assert_eq!(tidy_records[0].shard, None);
+        assert_eq!(tidy_records[0].metric, "metric.a");
+        assert_eq!(tidy_records[0].value, 30.);
+        assert!(!output_dir_path.join("metric.csv").exists());
+        std::fs::remove_dir_all(output_dir_path).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use crate::*;
+use rusqlite::{params, Connection};
+
+/// Writes the tidy records into the `outputs` table of a SQLite database,
+/// committed every slot.
+pub struct SqliteWriter {
+    conn: Connection,
+}
+
+impl SqliteWriter {
+    pub fn create(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        // Start from an empty database, as the other outputs overwrite their files
+        if file_path.exists() {
+            std::fs::remove_file(file_path)?;
+        }
+        let conn = Connection::open(file_path)?;
+        conn.execute(
+            "CREATE TABLE outputs (
+                slot INTEGER NOT NULL,
+                shard INTEGER,
+                metric TEXT NOT NULL,
+                value REAL NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self { conn })
+    }
+}
+
+impl TidyWriter for SqliteWriter {
+    fn write_records(&mut self, records: &[TidyRecord]) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO outputs (slot, shard, metric, value) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for record in records {
+                stmt.execute(params![
+                    record.slot as i64,
+                    record.shard.map(|shard| shard as i64),
+                    record.metric,
+                    record.value
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .execute("CREATE INDEX outputs_metric ON outputs (metric, slot)", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip_through_the_outputs_table() {
+        let file_path = std::env::temp_dir().join("sqlite_writer_test.sqlite");
+        let mut wtr = Box::new(SqliteWriter::create(&file_path).unwrap());
+        wtr.write_records(&[
+            TidyRecord {
+                slot: 3,
+                shard: Some(1),
+                metric: "base_fee".to_string(),
+                value: 7.5,
+            },
+            TidyRecord {
+                slot: 4,
+                shard: None,
+                metric: "functions.transfer".to_string(),
+                value: 2.,
+            },
+        ])
+        .unwrap();
+        wtr.finish().unwrap();
+
+        let conn = Connection::open(&file_path).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT slot, shard, metric, value FROM outputs ORDER BY slot")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (3, Some(1), "base_fee".to_string(), 7.5),
+                (4, None, "functions.transfer".to_string(), 2.),
+            ]
+        );
+        std::fs::remove_file(file_path).unwrap();
+    }
+}
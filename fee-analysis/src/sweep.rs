@@ -0,0 +1,210 @@
+use crate::*;
+
+/// A grid of scenarios, run in parallel with one output directory per run.
+pub struct Sweep {
+    base_scenario: Scenario,
+    parameters: Vec<SweepParameter>,
+    /// The number of runs at the same time.
+    job_num: usize,
+}
+
+impl Sweep {
+    pub fn new(base_scenario: Scenario, parameters: Vec<SweepParameter>, job_num: usize) -> Self {
+        Self {
+            base_scenario,
+            parameters,
+            job_num,
+        }
+    }
+
+    /// Every combination of the parameter values. The last parameter changes fastest.
+    fn grid(&self) -> Vec<Vec<toml::Value>> {
+        self.parameters
+            .iter()
+            .fold(vec![Vec::new()], |combinations, parameter| {
+                combinations
+                    .iter()
+                    .flat_map(|combination| {
+                        parameter.values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.push(value.clone());
+                            combination
+                        })
+                    })
+                    .collect()
+            })
+    }
+
+    /// The base scenario with the parameters set to the values.
+    fn scenario_of(&self, values: &[toml::Value]) -> Result<Scenario, Box<dyn Error>> {
+        let mut scenario = toml::Value::try_from(&self.base_scenario)?;
+        let table = scenario
+            .as_table_mut()
+            .ok_or("the scenario is not a table")?;
+        for (parameter, value) in self.parameters.iter().zip(values) {
+            table.insert(parameter.name.clone(), value.clone());
+        }
+        let scenario: Scenario = scenario.try_into()?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Run every scenario of the grid and write the index of the runs.
+    /// Fails without running anything if a scenario of the grid is invalid,
+    /// and after writing the index if any run fails.
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let output_dir_path = PathBuf::from(&self.base_scenario.output_dir_path);
+        let grid = self.grid();
+        let scenarios = grid
+            .iter()
+            .enumerate()
+            .map(|(i, values)| {
+                let mut scenario = self
+                    .scenario_of(values)
+                    .map_err(|e| format!("PARAM has an invalid value: {}", e))?;
+                scenario.output_dir_path = output_dir_path
+                    .join(Self::run_dir_name(i))
+                    .to_string_lossy()
+                    .into_owned();
+                Ok(scenario)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if let Err(e) = std::fs::create_dir_all(&output_dir_path) {
+            eprintln!("Error: {:?}", e)
+        }
+        println!(
+            "Sweep {} runs with {} jobs...",
+            scenarios.len(),
+            self.job_num
+        );
+
+        // Runs are already in parallel, so the replicas of each run are not
+        let summaries = run_in_parallel(scenarios.len(), self.job_num, |i| {
+            let summary = Self::run_scenario(&scenarios[i]);
+            if summary.is_some() {
+                println!("{} is complete.", Self::run_dir_name(i));
+            }
+            summary
+        })
+        .into_iter()
+        .map(Option::flatten)
+        .collect::<Vec<_>>();
+        summaries
+            .iter()
+            .enumerate()
+            .filter(|(_, summary)| summary.is_none())
+            .for_each(|(i, _)| eprintln!("Error: {} failed", Self::run_dir_name(i)));
+
+        if let Err(e) = self.output_csv_index(&output_dir_path, &grid, &summaries) {
+            eprintln!("Error: {:?}", e)
+        }
+        let failed_run_num = summaries.iter().filter(|summary| summary.is_none()).count();
+        if failed_run_num > 0 {
+            return Err(format!("{} of {} runs failed", failed_run_num, summaries.len()).into());
+        }
+        Ok(())
+    }
+
+    /// Run the scenario without printing the progress of each slot, replicated if requested.
+    fn run_scenario(scenario: &Scenario) -> Option<RunSummary> {
+        if scenario.replicas > 1 {
+            return Replicas::new(scenario.clone()).run(1);
+        }
+        let mut sim = Simulator::new(scenario.clone());
+        sim.set_log_level(LogLevel::Error);
+        sim.run();
+        Some(sim.summary())
+    }
+
+    fn run_dir_name(i: usize) -> String {
+        format!("run_{:04}", i)
+    }
+
+    /// One row per run, with a header: the run directory, the parameter values and the summary metrics.
+    fn output_csv_index(
+        &self,
+        output_dir_path: &Path,
+        grid: &[Vec<toml::Value>],
+        summaries: &[Option<RunSummary>],
+    ) -> Result<(), Box<dyn Error>> {
+        let file_path = output_dir_path.join(OUTPUT_SWEEP_INDEX_CSV_FILENAME);
+        let file = File::create(file_path)?;
+        let mut wtr = csv::Writer::from_writer(file);
+
+        let mut header = vec!["run".to_string()];
+        header.extend(
+            self.parameters
+                .iter()
+                .map(|parameter| parameter.name.clone()),
+        );
+        header.extend(RunSummary::FIELD_NAMES.iter().map(|name| name.to_string()));
+        wtr.write_record(header)?;
+
+        for (i, (values, summary)) in grid.iter().zip(summaries).enumerate() {
+            let mut record = vec![Self::run_dir_name(i)];
+            record.extend(values.iter().map(|value| match value {
+                toml::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            }));
+            match summary {
+                Some(summary) => record.extend(summary.to_record()),
+                None => record.extend(RunSummary::FIELD_NAMES.iter().map(|_| "".to_string())),
+            }
+            if let Err(e) = wtr.write_record(record) {
+                eprintln!("Error: {:?}", e)
+            };
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_is_every_combination_with_the_last_parameter_fastest() {
+        let sweep = Sweep::new(
+            Scenario::default(),
+            vec![
+                "shard_num=2,4".parse().unwrap(),
+                "async_calls=false,true".parse().unwrap(),
+            ],
+            1,
+        );
+        let grid = sweep.grid();
+        assert_eq!(
+            grid,
+            vec![
+                vec![toml::Value::Integer(2), toml::Value::Boolean(false)],
+                vec![toml::Value::Integer(2), toml::Value::Boolean(true)],
+                vec![toml::Value::Integer(4), toml::Value::Boolean(false)],
+                vec![toml::Value::Integer(4), toml::Value::Boolean(true)],
+            ]
+        );
+
+        let scenario = sweep.scenario_of(&grid[3]).unwrap();
+        assert_eq!(scenario.shard_num, 4);
+        assert!(scenario.async_calls);
+    }
+
+    #[test]
+    fn grid_without_parameters_is_the_base_scenario() {
+        let sweep = Sweep::new(Scenario::default(), Vec::new(), 1);
+        assert_eq!(sweep.grid(), vec![Vec::<toml::Value>::new()]);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_before_running() {
+        let sweep = Sweep::new(Scenario::default(), vec!["shard_num=0".parse().unwrap()], 1);
+        assert!(sweep.scenario_of(&sweep.grid()[0]).is_err());
+        let sweep = Sweep::new(
+            Scenario::default(),
+            vec!["no_such_key=1".parse().unwrap()],
+            1,
+        );
+        assert!(sweep.scenario_of(&sweep.grid()[0]).is_err());
+    }
+}
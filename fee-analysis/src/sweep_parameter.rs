@@ -0,0 +1,139 @@
+/// A scenario parameter and the values a sweep takes it through.
+#[derive(Clone, Debug)]
+pub struct SweepParameter {
+    /// The key of the parameter in scenario files.
+    pub name: String,
+    pub values: Vec<toml::Value>,
+}
+
+impl SweepParameter {
+    /// Parse a value as an integer, a real number, a boolean or otherwise a string.
+    fn parse_value(s: &str) -> toml::Value {
+        if let Ok(integer) = s.parse::<i64>() {
+            toml::Value::Integer(integer)
+        } else if let Ok(float) = s.parse::<f64>() {
+            toml::Value::Float(float)
+        } else if let Ok(boolean) = s.parse::<bool>() {
+            toml::Value::Boolean(boolean)
+        } else {
+            toml::Value::String(s.to_string())
+        }
+    }
+
+    /// Parse the values of an inclusive range start:end:step.
+    fn parse_range(start: &str, end: &str, step: &str) -> Result<Vec<toml::Value>, String> {
+        if [start, end, step].iter().all(|s| s.parse::<i64>().is_ok()) {
+            let (start, end, step) = (
+                start.parse::<i64>().unwrap(),
+                end.parse::<i64>().unwrap(),
+                step.parse::<i64>().unwrap(),
+            );
+            if step <= 0 || end < start {
+                return Err(format!("empty range: {}:{}:{}", start, end, step));
+            }
+            return Ok((start..=end)
+                .step_by(step as usize)
+                .map(toml::Value::Integer)
+                .collect());
+        }
+        let parse = |s: &str| {
+            s.parse::<f64>()
+                .map_err(|_| format!("not a number in a range: {}", s))
+        };
+        let (start, end, step) = (parse(start)?, parse(end)?, parse(step)?);
+        if step <= 0. || end < start {
+            return Err(format!("empty range: {}:{}:{}", start, end, step));
+        }
+        // Multiply instead of accumulating the steps, so that the end is hit exactly
+        let value_num = ((end - start) / step + 1e-9).floor() as usize + 1;
+        Ok((0..value_num)
+            .map(|i| toml::Value::Float(start + i as f64 * step))
+            .collect())
+    }
+}
+
+impl std::str::FromStr for SweepParameter {
+    type Err = String;
+
+    /// name=v1,v2,... or name=start:end:step
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, values) = s
+            .split_once('=')
+            .ok_or_else(|| format!("unknown sweep parameter: {}", s))?;
+        let values = match values.split(':').collect::<Vec<_>>()[..] {
+            [start, end, step] => Self::parse_range(start, end, step)?,
+            [_] => values.split(',').map(Self::parse_value).collect(),
+            _ => return Err(format!("unknown sweep parameter: {}", s)),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(parameter: &SweepParameter) -> Vec<f64> {
+        parameter
+            .values
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lists_parse_each_value_by_its_type() {
+        let parameter: SweepParameter = "fee_market=1,0.5,true,multi_dimensional".parse().unwrap();
+        assert_eq!(parameter.name, "fee_market");
+        assert_eq!(
+            parameter.values,
+            vec![
+                toml::Value::Integer(1),
+                toml::Value::Float(0.5),
+                toml::Value::Boolean(true),
+                toml::Value::String("multi_dimensional".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_ranges_include_the_end_when_a_step_hits_it() {
+        let parameter: SweepParameter = "shard_num=16:64:16".parse().unwrap();
+        assert_eq!(
+            parameter.values,
+            [16, 32, 48, 64].map(toml::Value::Integer).to_vec()
+        );
+        let parameter: SweepParameter = "shard_num=1:6:2".parse().unwrap();
+        assert_eq!(
+            parameter.values,
+            [1, 3, 5].map(toml::Value::Integer).to_vec()
+        );
+    }
+
+    #[test]
+    fn float_ranges_hit_the_end_exactly() {
+        let parameter: SweepParameter = "percentage_of_minimum=0:0.6:0.3".parse().unwrap();
+        assert_eq!(floats(&parameter), vec![0., 0.3, 0.6]);
+        let parameter: SweepParameter = "percentage_of_minimum=0:1:0.1".parse().unwrap();
+        let values = floats(&parameter);
+        assert_eq!(values.len(), 11);
+        assert!((values[10] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn empty_ranges_and_malformed_parameters_are_rejected() {
+        for s in [
+            "shard_num=64:16:16",
+            "shard_num=16:64:0",
+            "percentage_of_minimum=0:0.6:-0.3",
+            "percentage_of_minimum=0:high:0.1",
+            "shard_num=1:2",
+            "shard_num",
+        ] {
+            assert!(s.parse::<SweepParameter>().is_err(), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use crate::*;
+
+/// A value of a per-slot output in the long, tidy schema: one row per slot, shard and metric.
+#[derive(Clone, Debug, Serialize)]
+pub struct TidyRecord {
+    pub slot: Slot,
+    /// None for the metrics summed over shards, e.g. the number of functions of a type.
+    pub shard: Option<usize>,
+    pub metric: String,
+    pub value: f64,
+}
@@ -0,0 +1,10 @@
+use crate::*;
+
+/// A writer of the per-slot outputs in the tidy schema, shared by all the outputs of a run.
+pub trait TidyWriter {
+    /// Write the records of a slot.
+    fn write_records(&mut self, records: &[TidyRecord]) -> Result<(), Box<dyn Error>>;
+
+    /// Complete the file at the end of the run.
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
@@ -0,0 +1,25 @@
+use crate::*;
+
+/// The issuer of a token, the only account allowed to mint it on the home shard of the token.
+pub struct TokenIssuer {
+    pub token: Address,
+    pub addr: Address,
+    pub home_shard_id: usize,
+    nonce: Nonce,
+}
+
+impl TokenIssuer {
+    pub fn new(token: Address, addr: Address, home_shard_id: usize) -> Self {
+        Self {
+            token,
+            addr,
+            home_shard_id,
+            nonce: 0,
+        }
+    }
+
+    pub fn next_nonce(&mut self) -> Nonce {
+        self.nonce += 1;
+        self.nonce - 1
+    }
+}
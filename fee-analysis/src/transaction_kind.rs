@@ -0,0 +1,56 @@
+use crate::*;
+
+/// What a function of a transaction does for its sender.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransactionKind {
+    /// Stays on one shard, e.g. a local transfer or a batch of a rollup.
+    Intra,
+    /// Starts a cross-shard transfer or call on the source shard.
+    CrossCreate,
+    /// Any later leg of a cross-shard transfer or call, including commits, aborts and reclaims.
+    CrossApply,
+    /// Sends an account away from its shard.
+    MoveOut,
+    /// Receives a moving account on the target shard.
+    MoveIn,
+}
+
+impl TransactionKind {
+    pub fn of(ftype: &FunctionType) -> Self {
+        match ftype {
+            FunctionType::Transfer
+            | FunctionType::Mint
+            | FunctionType::TokenTransfer
+            | FunctionType::PostBatch => TransactionKind::Intra,
+            FunctionType::CreateCrossTransfer
+            | FunctionType::CreateCrossTokenTransfer
+            | FunctionType::CreateCrossCall
+            | FunctionType::LockCrossTransfer => TransactionKind::CrossCreate,
+            FunctionType::ApplyCrossTransfer
+            | FunctionType::ApplyCrossTokenTransfer
+            | FunctionType::ApplyCrossCall
+            | FunctionType::ApplyCallback
+            | FunctionType::ConfirmCrossTransfer
+            | FunctionType::CommitCrossTransfer
+            | FunctionType::AbortCrossTransfer
+            | FunctionType::FinalizeCrossTransfer
+            | FunctionType::ReclaimCrossTransfer => TransactionKind::CrossApply,
+            FunctionType::CreateCrossTransferAll | FunctionType::YankAccount => {
+                TransactionKind::MoveOut
+            }
+            FunctionType::ApplyCrossTransferAll | FunctionType::PullAccount => {
+                TransactionKind::MoveIn
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionKind::Intra => "intra",
+            TransactionKind::CrossCreate => "cross_create",
+            TransactionKind::CrossApply => "cross_apply",
+            TransactionKind::MoveOut => "move_out",
+            TransactionKind::MoveIn => "move_in",
+        }
+    }
+}
@@ -6,6 +6,7 @@ type Timestamp = String;
 type Hash = String;
 
 /// CSV data records in BigQuery Ethereum.
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Clone)]
 pub struct TransactionRecord {
     //hash: Hash,
@@ -0,0 +1,21 @@
+use crate::*;
+
+/// A function of a transaction as it was sent, for the trace of the run.
+/// Its inclusion and outcome are looked up in the blocks at the end of the run.
+pub struct TransactionTrace {
+    /// The hash of the receipt of the function.
+    pub hash: TransactionHash,
+    pub transaction_hash: TransactionHash,
+    /// The index of the function in the transaction.
+    pub index: usize,
+    pub creator: Address,
+    pub kind: TransactionKind,
+    /// The shard the transaction comes from: the shard of the previous leg for a later leg
+    /// of a cross-shard transfer, call or move, or the shard of the transaction otherwise.
+    pub source_shard_id: usize,
+    /// The shard the transaction goes to: the destination shard for the first leg,
+    /// or the shard of the transaction otherwise.
+    pub destination_shard_id: usize,
+    /// The slot in which the transaction was sent.
+    pub created_slot: Slot,
+}
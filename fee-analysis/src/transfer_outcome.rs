@@ -0,0 +1,25 @@
+/// How a cross-shard transfer or an account move ended, if it did.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransferOutcome {
+    /// Not all of its legs are included yet.
+    Pending,
+    Completed,
+    /// A leg was included with a failed receipt.
+    Failed,
+    /// A locked transfer was aborted, and its funds stay with the sender.
+    Aborted,
+    /// The sender reclaimed an expired transfer.
+    Refunded,
+}
+
+impl TransferOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransferOutcome::Pending => "pending",
+            TransferOutcome::Completed => "completed",
+            TransferOutcome::Failed => "failed",
+            TransferOutcome::Aborted => "aborted",
+            TransferOutcome::Refunded => "refunded",
+        }
+    }
+}
@@ -9,6 +9,15 @@ pub enum StrategyType {
 }
 
 impl StrategyType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StrategyType::NonSwitcher => "non_switcher",
+            StrategyType::WeightedRandom => "weighted_random",
+            StrategyType::Minimum => "minimum",
+            StrategyType::DecreasingMinimum => "decreasing_minimum",
+        }
+    }
+
     pub fn is_switcher(&self) -> bool {
         !(self == &StrategyType::NonSwitcher)
     }
@@ -25,16 +34,22 @@ pub struct User {
     pub unsent_transactions_in_shard: Vec<VecDeque<(Transaction, TransactionHash)>>,
     pub nonce_in_shard: Vec<Nonce>,
     pub user_type: StrategyType,
+    /// Whether or not the user's wallet only sends legacy transactions.
+    pub legacy: bool,
+    /// The number of slots the account has spent on each shard.
+    pub slots_in_shard: Vec<Slot>,
 }
 
 impl User {
-    pub fn new(account_addr: Address, user_type: StrategyType) -> Self {
+    pub fn new(account_addr: Address, user_type: StrategyType, shard_num: usize) -> Self {
         Self {
             account_addr,
-            unconfirmed_transactions_in_shard: vec![Vec::new(); SHARD_NUM],
-            unsent_transactions_in_shard: vec![VecDeque::new(); SHARD_NUM],
-            nonce_in_shard: vec![0; SHARD_NUM],
+            unconfirmed_transactions_in_shard: vec![Vec::new(); shard_num],
+            unsent_transactions_in_shard: vec![VecDeque::new(); shard_num],
+            nonce_in_shard: vec![0; shard_num],
             user_type,
+            legacy: false,
+            slots_in_shard: vec![0; shard_num],
         }
     }
 
@@ -42,14 +57,29 @@ impl User {
     pub fn pick_low_fee_shard_id_and_movement_fee_cap(
         &self,
         environment: &Environment,
+        rng: &mut XorShiftRng,
     ) -> (usize, Option<GasPrice>) {
         // How long the account has been on the same shard
         let (_, account) = environment.blockchain.get_account(self.account_addr);
-        let account = account.unwrap();
+        let account = match account {
+            Some(account) => account,
+            // Evicted or hibernated
+            None => {
+                return (
+                    environment.blockchain.addr_to_shard_id[&self.account_addr],
+                    None,
+                )
+            }
+        };
 
         // Expected transaction fees (per slot) when you are on a certain shard
         let mut expected_fees_per_slot = Vec::new();
         for shard_id_f in 0..environment.blockchain.shards.len() {
+            if environment.blockchain.shards[shard_id_f].kind == ShardKind::Data {
+                // Accounts cannot live on data shards
+                expected_fees_per_slot.push(Wei::MAX);
+                continue;
+            }
             let mut expected_fee: Wei = 0;
             for (&to, edge) in environment
                 .user_graph
@@ -64,34 +94,40 @@ impl User {
                 }
                 let account_t = account_t.unwrap();
                 let shard_id_t = account_t.shard_id;
-                let gas_price_f;
-                let gas_price_t;
-                if environment.blockchain.slot < INITIAL_SETUP_SLOTS {
-                    gas_price_f = edge.fee_cap;
-                    gas_price_t = edge.fee_cap;
-                } else {
-                    gas_price_f = environment.blockchain.shards[shard_id_f].get_base_fee();
-                    gas_price_t = environment.blockchain.shards[shard_id_t].get_base_fee();
-                }
+                let shard_f = &environment.blockchain.shards[shard_id_f];
+                let shard_t = &environment.blockchain.shards[shard_id_t];
 
-                let fee = if shard_id_f == shard_id_t {
-                    (edge.transfer_probability_in_slot * (GAS_TRANSFER * gas_price_f) as f64) as Wei
+                let fee = if environment.blockchain.slot < INITIAL_SETUP_SLOTS {
+                    let gas = if shard_id_f == shard_id_t {
+                        GAS_TRANSFER
+                    } else {
+                        GAS_CREATE_CROSS_TRANSFER + GAS_APPLY_CROSS_TRANSFER
+                    };
+                    (edge.transfer_probability_in_slot * (gas * edge.fee_cap) as f64) as Wei
+                } else if shard_id_f == shard_id_t {
+                    (edge.transfer_probability_in_slot
+                        * shard_f.estimate_base_fee_cost(&FunctionType::Transfer) as f64)
+                        as Wei
                 } else {
                     (edge.transfer_probability_in_slot
-                        * (GAS_APPLY_CROSS_TRANSFER * gas_price_f
-                            + GAS_CREATE_CROSS_TRANSFER * gas_price_t)
+                        * (shard_f.estimate_base_fee_cost(&FunctionType::CreateCrossTransfer)
+                            + shard_t.estimate_base_fee_cost(&FunctionType::ApplyCrossTransfer))
                             as f64) as Wei
                 };
 
                 expected_fee += fee;
             }
 
-            expected_fee *= AVERAGE_SHARD_SWITCHING_INTERVAL as Wei;
+            expected_fee *= environment.average_shard_switching_interval as Wei;
+            // Storage pressure
+            expected_fee += environment.blockchain.shards[shard_id_f].estimate_rent(account)
+                * environment.average_shard_switching_interval as Wei
+                / SLOTS_PER_EPOCH as Wei;
             if account.shard_id != shard_id_f {
-                let gas_price_f = environment.blockchain.shards[account.shard_id].get_base_fee();
-                let gas_price_t = environment.blockchain.shards[shard_id_f].get_base_fee();
-                expected_fee += GAS_CREATE_CROSS_TRANSFER_ALL * gas_price_f
-                    + GAS_APPLY_CROSS_TRANSFER_ALL * gas_price_t;
+                expected_fee += environment.blockchain.shards[account.shard_id]
+                    .estimate_base_fee_cost(&FunctionType::CreateCrossTransferAll)
+                    + environment.blockchain.shards[shard_id_f]
+                        .estimate_base_fee_cost(&FunctionType::ApplyCrossTransferAll);
             }
 
             expected_fees_per_slot.push(expected_fee);
@@ -116,7 +152,7 @@ impl User {
         let total_reduction: u128 = sorted_reduction_and_shard_ids.iter().map(|x| x.0).sum();
         if total_reduction > 0 {
             if self.user_type == StrategyType::WeightedRandom {
-                let threshold = RND.lock().unwrap().next_u32() as f64 / u32::MAX as f64;
+                let threshold = rng.next_u32() as f64 / u32::MAX as f64;
                 let mut cumulative_reduction = 0;
                 for &(reduction, shard_id) in sorted_reduction_and_shard_ids.iter() {
                     cumulative_reduction += reduction;
@@ -1,6 +1,7 @@
 use crate::*;
 
 /// UserGraph edge.
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct UserGraphEdge {
     pub from: Address,
@@ -36,6 +37,8 @@ pub struct UserGraphNode {
 pub struct UserGraph {
     pub nodes: Vec<UserGraphNode>,
     pub edges: Vec<HashMap<Address, UserGraphEdge>>,
+    /// The sum of the transfer probabilities of the inserted edges before normalization.
+    yet_normalized_total: f64,
 }
 
 impl UserGraph {
@@ -43,6 +46,7 @@ impl UserGraph {
         UserGraph {
             nodes: Vec::new(),
             edges: Vec::new(),
+            yet_normalized_total: 0.,
         }
     }
 
@@ -57,11 +61,8 @@ impl UserGraph {
     }
 
     /// Generate an user graph randomly.
-    pub fn new_random(
-        user_num: usize,
-        popular_user_exists: bool,
-        popular_user_is_switcher: bool,
-    ) -> Self {
+    /// The transfer probabilities are not yet normalized.
+    pub fn new_random(user_num: usize, rng: &mut XorShiftRng) -> Self {
         let mut user_graph = Self::new();
         let max_target_user_num = std::cmp::min(MAX_TARGET_USER_NUM, user_num);
 
@@ -76,58 +77,83 @@ impl UserGraph {
         user_graph.edges.resize(user_num, HashMap::new());
 
         // Construct UserGraphEdge
-        let mut number_of_transactions: u64 = 0;
         for from in 0..user_num {
-            let target_user_num =
-                RND.lock().unwrap().next_u32() as usize % (max_target_user_num + 1);
-
-            let transfer_to_popular_user = if popular_user_exists {
-                (popular_user_is_switcher && from == POPULAR_USER_ADDRESS)
-                    || RND.lock().unwrap().next_u32()
-                        < (PERCENTAGE_OF_USERS_TRANSFERRING_TO_POPULAR_USER * u32::MAX as f64)
-                            as u32
-            } else {
-                false
-            };
-
-            for i in 0..target_user_num {
-                let to = if i == 0 && transfer_to_popular_user {
-                    POPULAR_USER_ADDRESS
-                } else {
-                    RND.lock().unwrap().next_u32() as usize % user_num
-                };
+            let target_user_num = rng.next_u32() as usize % (max_target_user_num + 1);
+            for _ in 0..target_user_num {
+                let to = rng.next_u32() as usize % user_num;
                 if from == to {
                     continue;
                 }
-                let yet_normalized_transfer_probability_in_slot =
-                    RND.lock().unwrap().next_u32() % 100;
-                let fee_cap = RND.lock().unwrap().next_u64() as GasPrice % MAX_FEE_CAP;
-                user_graph.edges[from].insert(
+                let yet_normalized_transfer_probability_in_slot = rng.next_u32() % 100;
+                let fee_cap = rng.next_u64() as GasPrice % MAX_FEE_CAP;
+                user_graph.insert_edge(UserGraphEdge::new(
+                    from,
                     to,
-                    UserGraphEdge::new(
-                        from,
-                        to,
-                        fee_cap,
-                        yet_normalized_transfer_probability_in_slot as f64,
-                    ),
-                );
-                user_graph.nodes[from].out_degree += 1;
-                user_graph.nodes[to].in_degree += 1;
-                number_of_transactions += yet_normalized_transfer_probability_in_slot as u64;
+                    fee_cap,
+                    yet_normalized_transfer_probability_in_slot as f64,
+                ));
             }
         }
-        user_graph.edges.iter_mut().for_each(|edges| {
+        user_graph
+    }
+
+    fn insert_edge(&mut self, edge: UserGraphEdge) {
+        self.yet_normalized_total += edge.transfer_probability_in_slot;
+        self.nodes[edge.from].out_degree += 1;
+        if let Some(node) = self.nodes.get_mut(edge.to) {
+            node.in_degree += 1;
+        }
+        self.edges[edge.from].insert(edge.to, edge);
+    }
+
+    /// Let a share of users interact with the hubs of the group,
+    /// interaction_weight times as often as with another user on average.
+    /// The transfer probabilities are not yet normalized.
+    pub fn add_hub_group(&mut self, hub_group: &HubGroup, rng: &mut XorShiftRng) {
+        if hub_group.addrs.is_empty() {
+            return;
+        }
+        let edge_num: usize = self.edges.iter().map(|edges| edges.len()).sum();
+        let average_transfer_probability_in_slot =
+            self.yet_normalized_total / std::cmp::max(edge_num, 1) as f64;
+        for from in 0..self.nodes.len() {
+            if rng.next_u32() >= (hub_group.percentage_of_users * u32::MAX as f64) as u32 {
+                continue;
+            }
+            let to = hub_group.pick(rng);
+            let fee_cap = rng.next_u64() as GasPrice % MAX_FEE_CAP;
+            if from == to {
+                continue;
+            }
+            self.insert_edge(UserGraphEdge::new(
+                from,
+                to,
+                fee_cap,
+                average_transfer_probability_in_slot * hub_group.interaction_weight as f64,
+            ));
+        }
+    }
+
+    /// Scale the transfer probabilities so that users send global_transaction_num transactions
+    /// per slot in total.
+    pub fn normalize(&mut self, global_transaction_num: usize) {
+        let total_transfer_probability_in_slot = self.yet_normalized_total;
+        self.edges.iter_mut().for_each(|edges| {
             edges.values_mut().for_each(|edge| {
                 edge.transfer_probability_in_slot = edge.transfer_probability_in_slot
-                    / number_of_transactions as f64
-                    * GLOBAL_TRANSACTION_NUM as f64;
+                    / total_transfer_probability_in_slot
+                    * global_transaction_num as f64;
             });
         });
-        user_graph
     }
 
     /// User generation from historical transaction logs (by BigQuery Ethereum).
-    pub fn new_from_eth1_data(user_num: usize, eth1_data_file_name: &str) -> Self {
+    /// The transfer probabilities are not yet normalized.
+    pub fn new_from_eth1_data(
+        user_num: usize,
+        eth1_data_file_name: &str,
+        shard_num: usize,
+    ) -> Self {
         let mut user_graph = Self::new();
         // How many blocks are targeted
         let mut block_number_set = HashSet::new();
@@ -186,7 +212,7 @@ impl UserGraph {
                 .get(&eth1_addr_to)
                 .expect("failed to convert eth1 addr to shargri-la's addr");
 
-            let mut edge = edges
+            let edge = edges
                 .entry((shargrila_addr_from, shargrila_addr_to))
                 .or_insert(Edge {
                     from: shargrila_addr_from,
@@ -213,24 +239,18 @@ impl UserGraph {
         user_graph.edges.resize(current_user_num, HashMap::new());
         edges.iter_mut().for_each(|(_, edge)| {
             edge.gas_price /= edge.cnt as GasPrice;
-            let transaction_ratio = edge.cnt as f64 / number_of_transactions as f64;
-            let transfer_probability_in_slot = transaction_ratio * GLOBAL_TRANSACTION_NUM as f64;
-            user_graph.edges[edge.from].insert(
+            user_graph.insert_edge(UserGraphEdge::new(
+                edge.from,
                 edge.to,
-                UserGraphEdge::new(
-                    edge.from,
-                    edge.to,
-                    edge.gas_price,
-                    transfer_probability_in_slot,
-                ),
-            );
-            user_graph.nodes[edge.from].out_degree += 1;
-            user_graph.nodes[edge.to].in_degree += 1;
+                edge.gas_price,
+                edge.cnt as f64,
+            ));
         });
         user_graph.print_statistics(
             number_of_transactions,
             block_number_set.len(),
             shargrila_addr_to_eth1_addr,
+            shard_num,
         );
 
         user_graph
@@ -254,6 +274,7 @@ impl UserGraph {
         number_of_transactions: usize,
         block_number_set_len: usize,
         shargrila_addr_to_eth1_addr: HashMap<usize, String>,
+        shard_num: usize,
     ) {
         println!("STATISTICS");
         println!("The number of accounts: {}", self.nodes.len());
@@ -277,7 +298,7 @@ impl UserGraph {
                     addr,
                     shargrila_addr_to_eth1_addr.get(addr).unwrap(),
                     degree,
-                    addr % SHARD_NUM
+                    addr % shard_num
                 );
             });
 
@@ -298,7 +319,7 @@ impl UserGraph {
                     addr,
                     shargrila_addr_to_eth1_addr.get(addr).unwrap(),
                     degree,
-                    addr % SHARD_NUM
+                    addr % shard_num
                 );
             });
     }
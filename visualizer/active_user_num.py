@@ -10,18 +10,18 @@ sns.set_style("ticks")
 fig, ax = plt.subplots(figsize=(10, 6))
 
 dirpath = sys.argv[1] if len(sys.argv) > 1 else "data"
-raw_data = pd.read_csv(f'{dirpath}/active_user_num.csv', header=None)
+raw_data = pd.read_csv(f'{dirpath}/active_user_num.csv', index_col='slot')
 
 ROW_NUM = len(raw_data)
 SKIP_FRAME_NUM = 0
-SHARD_NUM = len(raw_data.loc[0])
+SHARD_NUM = len(raw_data.columns)
 FRAME_NUM = ROW_NUM - SKIP_FRAME_NUM
 
 height = []
 for slot in tqdm.tqdm(range(SKIP_FRAME_NUM, ROW_NUM)):
     h = [0 for _ in range(SHARD_NUM)]
     for i in range(SHARD_NUM):
-        h[i] = raw_data.loc[slot][i]
+        h[i] = raw_data.loc[slot, f'shard_{i}']
     height.append(h)
 
 x = [i for i in range(SKIP_FRAME_NUM, ROW_NUM)]
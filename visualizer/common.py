@@ -1,3 +1,7 @@
 color_list = ["#0074bf", "#c83955", "#88b83e"]
-strategy_labels = ["No Switch", "Weighted Random",
-                   "Minimum", "Decreasing Minimum"]
+strategy_labels = {
+    "non_switcher": "No Switch",
+    "weighted_random": "Weighted Random",
+    "minimum": "Minimum",
+    "decreasing_minimum": "Decreasing Minimum",
+}
@@ -10,7 +10,7 @@ sns.set_style("ticks")
 fig, ax = plt.subplots(figsize=(10, 6))
 
 dirpath = sys.argv[1] if len(sys.argv) > 1 else "data"
-raw_data = pd.read_csv(f'{dirpath}/function_num.csv', header=None)
+raw_data = pd.read_csv(f'{dirpath}/function_num.csv', index_col='slot')
 
 SLOT_NUM = len(raw_data)
 
@@ -21,19 +21,6 @@ ax.set_title('Number of Intra-shard/Cross-shard Transfers Over Time')
 ax.set_xlabel('Slot')
 ax.set_ylabel('Number of Transactions')
 
-# labels = [
-#     'Transfer (non-switchers)',
-#     'CreateCrossTransfer (non-switchers)',
-#     'ApplyCrossTransfer (non-switchers)',
-#     'CreateCrossTransferAll (non-switchers)',
-#     'ApplyCrossTransferAll (non-switchers)',
-#     'Transfer (switchers)',
-#     'CreateCrossTransfer (switchers)',
-#     'ApplyCrossTransfer (switchers)',
-#     'CreateCrossTransferAll (switchers)',
-#     'ApplyCrossTransferAll (switchers)',
-# ]
-
 labels = [
     "Non-switchers' Intra-shard",
     "Non-switchers' Cross-shard",
@@ -41,10 +28,21 @@ labels = [
     "Switchers' Cross-shard",
 ]
 
-transfer_n = raw_data[0]
-cross_transfer_n = raw_data[1] + raw_data[3]
-transfer_s = raw_data[5]
-cross_transfer_s = raw_data[6] + raw_data[8]
+# Columns are named <non_switcher or switcher>.<function type>
+intra_shard_functions = ['transfer', 'token_transfer']
+cross_shard_functions = ['create_cross_transfer', 'create_cross_transfer_all',
+                         'create_cross_token_transfer', 'create_cross_call',
+                         'lock_cross_transfer', 'yank_account']
+
+
+def function_num(user_class, functions):
+    return sum(raw_data[f'{user_class}.{function}'] for function in functions)
+
+
+transfer_n = function_num('non_switcher', intra_shard_functions)
+cross_transfer_n = function_num('non_switcher', cross_shard_functions)
+transfer_s = function_num('switcher', intra_shard_functions)
+cross_transfer_s = function_num('switcher', cross_shard_functions)
 y = [transfer_n, cross_transfer_n, transfer_s, cross_transfer_s]
 colors = ['blue', 'skyblue', 'red', 'pink']
 